@@ -0,0 +1,50 @@
+//! Alignment against circular references.
+
+use super::Aligner;
+use crate::prelude::Result;
+use crate::sequence::CircularAlignment;
+
+impl Aligner {
+    /// Align a query against a circular reference (e.g., a plasmid or a
+    /// mitochondrial genome), in both orientations.
+    ///
+    /// The query is aligned against the reference doubled, so that queries
+    /// spanning the origin align contiguously, and reference coordinates are
+    /// reported modulo the reference length (see `CircularAlignment`). The
+    /// aligner should be semi-global with free reference end gaps (the
+    /// default for `semi_global`), and profile aligners need a reverse
+    /// complement profile as for `align_both_strands`.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::Aligner;
+    ///
+    /// let plasmid = b"GATCCTAGGCTTACGTTCAGGCATCAAGTC";
+    /// // spans the origin of the plasmid
+    /// let read = b"ATCAAGTCGATCCTAGGC";
+    /// let aligner = Aligner::new()
+    ///     .semi_global()
+    ///     .gap_open(5)
+    ///     .gap_extend(1)
+    ///     .use_trace()
+    ///     .build();
+    ///
+    /// let result = aligner.align_circular(Some(read), plasmid)?;
+    /// let pieces = result.split_cigar(read, plasmid)?;
+    /// assert_eq!(pieces[0].to_string(), "8=10S");
+    /// assert_eq!(pieces[1].to_string(), "8S10=");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn align_circular(
+        &self,
+        query: Option<&[u8]>,
+        reference: &[u8],
+    ) -> Result<CircularAlignment> {
+        let doubled = [reference, reference].concat();
+        let result = self.align_both_strands(query, &doubled)?;
+        Ok(CircularAlignment {
+            strand: result.strand,
+            alignment: result.alignment,
+            ref_len: reference.len(),
+        })
+    }
+}
@@ -7,6 +7,10 @@ pub enum Error {
     #[from]
     InteriorNulByte(NulError),
     NoBandwidth,
+    ScoreMismatch {
+        expected: i32,
+        found: i32,
+    },
     #[from]
     Alignment(crate::alignment::Error),
 }
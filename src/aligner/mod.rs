@@ -12,7 +12,15 @@ use std::sync::Arc;
 use crate::alignment::*;
 use crate::prelude::{Matrix, Profile, Result};
 
+mod circular;
 mod error;
+mod paths;
+mod quality;
+mod search;
+#[cfg(feature = "serde")]
+mod settings;
+mod strands;
+mod translated;
 pub use error::Error;
 
 /// Parasail alignment function type.
@@ -64,6 +72,22 @@ impl AlignerFn {
 /// are set. Vectorization strategy is set to striped by default.
 ///
 /// To configure these options, use the setter methods listed below.
+///
+/// With the `serde` feature, builder settings can also be loaded from (and
+/// saved to) configuration files. Settings mirror the setter methods and are
+/// all optional. The matrix may be given by name or inline (see `Matrix`).
+/// Query profiles are not part of the settings and should be set on the
+/// builder afterwards. For example, in TOML:
+///
+/// ```toml
+/// mode = "semi_global" # or "global", "local"
+/// gap_open = 10
+/// gap_extend = 1
+/// strategy = "scan" # or "striped", "diag"
+/// solution_width = 16
+/// matrix = "blosum62"
+/// use_trace = true
+/// ```
 pub struct AlignerBuilder {
     mode: String,
     solution_width: String,
@@ -71,6 +95,7 @@ pub struct AlignerBuilder {
     gap_open: i32,
     gap_extend: i32,
     profile: Arc<Profile>,
+    reverse_profile: Arc<Profile>,
     allow_query_gaps: Vec<String>,
     allow_ref_gaps: Vec<String>,
     vec_strategy: String,
@@ -92,6 +117,7 @@ impl Default for AlignerBuilder {
             gap_open: 0,
             gap_extend: 0,
             profile: Profile::default().into(),
+            reverse_profile: Profile::default().into(),
             allow_query_gaps: Vec::default(),
             allow_ref_gaps: Vec::default(),
             vec_strategy: String::from("_striped"),
@@ -159,6 +185,14 @@ impl AlignerBuilder {
         self
     }
 
+    /// Set the profile of the reverse complement of the query, used with the
+    /// query profile by `Aligner::align_both_strands`. It should be built with
+    /// the same options as the query profile.
+    pub fn reverse_profile(&mut self, profile: Profile) -> &mut Self {
+        self.reverse_profile = Arc::new(profile);
+        self
+    }
+
     /// Set allowed gaps on query sequence for semi-global alignment.
     /// By default, gaps are allowed at the beginning and end of the query sequence.
     /// Example:
@@ -363,6 +397,7 @@ impl AlignerBuilder {
             gap_open: self.gap_open,
             gap_extend: self.gap_extend,
             profile: Arc::clone(&self.profile),
+            reverse_profile: Arc::clone(&self.reverse_profile),
             vec_strategy: self.vec_strategy.clone(),
             bandwidth: self.bandwidth,
         }
@@ -377,6 +412,7 @@ pub struct Aligner {
     pub gap_open: i32,
     pub gap_extend: i32,
     profile: Arc<Profile>,
+    reverse_profile: Arc<Profile>,
     pub vec_strategy: String,
     bandwidth: Option<i32>,
 }
@@ -387,6 +423,7 @@ impl Aligner {
     /// The default aligner uses global alignment with an identity matrix for DNA
     /// sequences and no gap penalties. No profile, trace, table, or stats options
     /// are set. Vectorization strategy is set to striped by default.
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> AlignerBuilder {
         AlignerBuilder::default()
     }
@@ -423,32 +460,43 @@ impl Aligner {
 
                 Ok(Alignment {
                     inner: result,
-                    matrix: **self.matrix,
+                    matrix: Arc::clone(&self.matrix),
                     query_len,
                     ref_len,
                 })
             }
-            AlignerFn::PFunction(f) => {
-                // already checked that aligner function f is some variant during build step
+            AlignerFn::PFunction(_) => self.align_profile(&self.profile, &reference, ref_len),
+        }
+    }
 
-                let result = unsafe {
-                    f.unwrap()(
-                        **self.profile,
-                        reference.as_ptr(),
-                        ref_len,
-                        self.gap_open,
-                        self.gap_extend,
-                    )
-                };
+    /// Perform alignment between a query profile built for this aligner and a
+    /// reference sequence.
+    fn align_profile(
+        &self,
+        profile: &Profile,
+        reference: &CString,
+        ref_len: i32,
+    ) -> Result<Alignment> {
+        let AlignerFn::PFunction(f) = self.parasail_fn else {
+            unreachable!("Profile alignment requires a profile function.");
+        };
+        // already checked that aligner function f is some variant during build step
+        let result = unsafe {
+            f.unwrap()(
+                **profile,
+                reference.as_ptr(),
+                ref_len,
+                self.gap_open,
+                self.gap_extend,
+            )
+        };
 
-                Ok(Alignment {
-                    inner: result,
-                    matrix: **self.matrix,
-                    query_len: self.profile.query_len,
-                    ref_len,
-                })
-            }
-        }
+        Ok(Alignment {
+            inner: result,
+            matrix: Arc::clone(&self.matrix),
+            query_len: profile.query_len,
+            ref_len,
+        })
     }
 
     /// Perform banded global alignment between a query and reference sequence.
@@ -482,7 +530,7 @@ impl Aligner {
 
         Ok(Alignment {
             inner: result,
-            matrix: **self.matrix,
+            matrix: Arc::clone(&self.matrix),
             query_len,
             ref_len,
         })
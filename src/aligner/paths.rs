@@ -0,0 +1,217 @@
+//! Enumeration of co-optimal (tied) traceback paths.
+
+use super::{Aligner, Error};
+use crate::alignment::table::{TraceOp, TraceStep};
+use crate::prelude::{MatrixKind, Result};
+
+// parasail result flags (see parasail.h)
+const FLAG_SW: i32 = 1 << 2;
+const FLAG_SG_S1_BEG: i32 = 1 << 3;
+const FLAG_SG_S2_BEG: i32 = 1 << 14;
+const FLAG_SG: i32 = 1 << 1;
+
+const NEG_INF: i32 = i32::MIN / 2;
+
+/// Dynamic programming matrices of the affine gap recurrence, with an extra
+/// leading row and column for the boundary.
+struct Scores {
+    cols: usize,
+    h: Vec<i32>,
+    e: Vec<i32>,
+    f: Vec<i32>,
+    sub: Vec<i32>,
+}
+
+impl Scores {
+    fn at(&self, i: usize, j: usize) -> usize {
+        i * self.cols + j
+    }
+}
+
+impl Aligner {
+    /// Enumerate up to `max_paths` co-optimal traceback paths of the alignment
+    /// of `query` against `reference`.
+    ///
+    /// Parasail only records one traceback flag per cell and state, so ties
+    /// are not available from `TracebackTable`. Instead, the score matrices
+    /// are recomputed with the aligner's matrix and gap penalties, and every
+    /// path reaching the alignment end with the optimal score is followed.
+    /// Paths use the same representation as `TracebackTable::path`, and the
+    /// first path prefers diagonal steps, then deletions, then insertions, as
+    /// parasail's traceback does.
+    ///
+    /// Profile aligners score with the aligner's matrix, so the profile
+    /// should be built with the same matrix; otherwise an
+    /// `aligner::Error::ScoreMismatch` error is returned.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::Aligner;
+    ///
+    /// // the deleted T can be any of the three
+    /// let aligner = Aligner::new().gap_open(5).gap_extend(1).build();
+    /// let paths = aligner.tied_paths(b"ACTTTG", b"ACTTG", 10)?;
+    /// assert_eq!(paths.len(), 3);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn tied_paths(
+        &self,
+        query: &[u8],
+        reference: &[u8],
+        max_paths: usize,
+    ) -> Result<Vec<Vec<TraceStep>>> {
+        let result = self.align(Some(query), reference)?;
+        let (rows, cols) = (result.query_len as usize, result.ref_len as usize);
+        if max_paths == 0 || rows == 0 || cols == 0 {
+            return Ok(Vec::new());
+        }
+
+        let flag = unsafe { (*result.inner).flag };
+        let local = flag & FLAG_SW != 0;
+        let semi_global = flag & FLAG_SG != 0;
+        let free_col = local || (semi_global && flag & FLAG_SG_S1_BEG != 0);
+        let free_row = local || (semi_global && flag & FLAG_SG_S2_BEG != 0);
+
+        let scores = self.score_matrices(query, reference, free_row, free_col, local);
+        let end = (
+            result.get_end_query() as usize + 1,
+            result.get_end_ref() as usize + 1,
+        );
+        let found = scores.h[scores.at(end.0, end.1)];
+        if found != result.get_score() {
+            return Err(Error::ScoreMismatch {
+                expected: result.get_score(),
+                found,
+            }
+            .into());
+        }
+
+        let mut paths = Vec::new();
+        let mut stack = vec![(end.0, end.1, TraceOp::Diag, Vec::new())];
+        while let Some((i, j, state, mut path)) = stack.pop() {
+            let at = scores.at(i, j);
+            // branches are pushed in reverse order of preference
+            match state {
+                TraceOp::Diag => {
+                    let h = scores.h[at];
+                    if i == 0 || j == 0 || (local && h == 0) {
+                        path.reverse();
+                        paths.push(path);
+                        if paths.len() == max_paths {
+                            break;
+                        }
+                        continue;
+                    }
+                    if h == scores.e[at] {
+                        stack.push((i, j, TraceOp::Ins, path.clone()));
+                    }
+                    if h == scores.f[at] {
+                        stack.push((i, j, TraceOp::Del, path.clone()));
+                    }
+                    if h == scores.h[scores.at(i - 1, j - 1)] + scores.sub[at] {
+                        path.push(TraceStep {
+                            row: i - 1,
+                            col: j - 1,
+                            op: TraceOp::Diag,
+                        });
+                        stack.push((i - 1, j - 1, TraceOp::Diag, path));
+                    }
+                }
+                TraceOp::Ins => {
+                    let e = scores.e[at];
+                    let prev = scores.at(i, j - 1);
+                    path.push(TraceStep {
+                        row: i - 1,
+                        col: j - 1,
+                        op: TraceOp::Ins,
+                    });
+                    if e == scores.h[prev] - self.gap_open {
+                        stack.push((i, j - 1, TraceOp::Diag, path.clone()));
+                    }
+                    if e == scores.e[prev] - self.gap_extend {
+                        stack.push((i, j - 1, TraceOp::Ins, path));
+                    }
+                }
+                TraceOp::Del => {
+                    let f = scores.f[at];
+                    let prev = scores.at(i - 1, j);
+                    path.push(TraceStep {
+                        row: i - 1,
+                        col: j - 1,
+                        op: TraceOp::Del,
+                    });
+                    if f == scores.h[prev] - self.gap_open {
+                        stack.push((i - 1, j, TraceOp::Diag, path.clone()));
+                    }
+                    if f == scores.f[prev] - self.gap_extend {
+                        stack.push((i - 1, j, TraceOp::Del, path));
+                    }
+                }
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Fill the H, E and F matrices as parasail's reference implementations
+    /// do. `free_row` and `free_col` make gaps before the first query or
+    /// reference residue free.
+    fn score_matrices(
+        &self,
+        query: &[u8],
+        reference: &[u8],
+        free_row: bool,
+        free_col: bool,
+        local: bool,
+    ) -> Scores {
+        let (rows, cols) = (query.len() + 1, reference.len() + 1);
+        let mut scores = Scores {
+            cols,
+            h: vec![0; rows * cols],
+            e: vec![NEG_INF; rows * cols],
+            f: vec![NEG_INF; rows * cols],
+            sub: vec![0; rows * cols],
+        };
+        let (open, extend) = (self.gap_open, self.gap_extend);
+
+        for j in 1..cols {
+            scores.h[j] = if free_row {
+                0
+            } else {
+                -open - (j as i32 - 1) * extend
+            };
+        }
+        for i in 1..rows {
+            let at = scores.at(i, 0);
+            scores.h[at] = if free_col {
+                0
+            } else {
+                -open - (i as i32 - 1) * extend
+            };
+        }
+
+        for i in 1..rows {
+            for j in 1..cols {
+                let at = scores.at(i, j);
+                let up = scores.at(i - 1, j);
+                let left = scores.at(i, j - 1);
+                let sub = match self.matrix.kind() {
+                    MatrixKind::Square => self.matrix.score(query[i - 1], reference[j - 1]),
+                    MatrixKind::Pssm => self.matrix.pssm_score(i - 1, reference[j - 1]),
+                }
+                .unwrap_or(self.matrix.min_score());
+
+                scores.f[at] = (scores.h[up] - open).max(scores.f[up] - extend);
+                scores.e[at] = (scores.h[left] - open).max(scores.e[left] - extend);
+                let diag = scores.h[scores.at(i - 1, j - 1)] + sub;
+                let mut h = diag.max(scores.e[at]).max(scores.f[at]);
+                if local {
+                    h = h.max(0);
+                }
+                scores.h[at] = h;
+                scores.sub[at] = sub;
+            }
+        }
+
+        scores
+    }
+}
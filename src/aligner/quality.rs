@@ -0,0 +1,47 @@
+//! Quality-aware alignment.
+
+use std::sync::Arc;
+
+use super::{Aligner, AlignerFn};
+use crate::prelude::Result;
+
+impl Aligner {
+    /// Create an aligner scoring a query with per-base Phred qualities (as in
+    /// FASTQ files, Phred+33 encoded), using a PSSM derived from the
+    /// aligner's matrix (see `Matrix::quality_pssm`), so that matches and
+    /// mismatches of low quality bases weigh less. Other settings are kept.
+    ///
+    /// The returned aligner should be used to align this query only.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::{Aligner, Matrix};
+    ///
+    /// let read = b"ACGTTGCAAGGCTTAGCCTA";
+    /// let qualities = b"IIIIIIIIIIIIIIII####";
+    /// let aligner = Aligner::new()
+    ///     .matrix(Matrix::create(b"ACGT", 10, -10)?)
+    ///     .gap_open(20)
+    ///     .gap_extend(5)
+    ///     .semi_global()
+    ///     .build();
+    ///
+    /// let result = aligner
+    ///     .with_qualities(read, qualities)?
+    ///     .align(Some(read), b"TTACGTTGCAAGGCTTAGGGGGTT")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the aligner has a profile, since profiles are built with
+    /// their own matrix, or if there is not one quality per query base.
+    pub fn with_qualities(&self, query: &[u8], qualities: &[u8]) -> Result<Aligner> {
+        assert!(
+            matches!(self.parasail_fn, AlignerFn::Function(_)),
+            "Quality-aware aligners can not be built from profile aligners."
+        );
+        let pssm = self.matrix.quality_pssm(query, qualities)?;
+        let mut aligner = self.clone();
+        aligner.matrix = Arc::new(pssm);
+        Ok(aligner)
+    }
+}
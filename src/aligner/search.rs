@@ -0,0 +1,136 @@
+//! Search for all approximate occurrences of a pattern in a text.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use super::Aligner;
+use crate::prelude::{Cigar, CigarOp, Result};
+use crate::sequence::{reverse_complement, PatternHit, Strand};
+
+impl Aligner {
+    /// Find all occurrences of a pattern (e.g., a primer or a guide) in a
+    /// text with a score of at least `min_score`, on both strands of the
+    /// text. With a matrix scoring matches 0 and gaps and mismatches 1 per
+    /// residue, `-min_score` is a maximum edit distance.
+    ///
+    /// The pattern is aligned globally against the text with free end gaps
+    /// in the text, using the matrix and gap penalties of the aligner
+    /// (whatever its alignment mode and profile), and the last row of the
+    /// score table gives the best score of the pattern ending at each text
+    /// position. End positions scoring above the threshold are reported
+    /// unless a higher scoring one (or an equal one to their left) is within
+    /// one pattern length, and each hit is then aligned again with traceback
+    /// to recover its start and CIGAR. Hits are sorted by position in the
+    /// text. Coordinates are in forward text space, and hits on the reverse
+    /// strand are alignments of the reverse complement of the pattern.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::Aligner;
+    /// use parasail_rs::sequence::Strand;
+    ///
+    /// let aligner = Aligner::new().gap_open(2).gap_extend(2).build();
+    /// let text = b"TTTACGTACTTTTACGAACTTGTACGTTTT";
+    /// let hits = aligner.find_all(b"ACGTAC", text, 4)?;
+    ///
+    /// assert_eq!(hits.len(), 4);
+    /// assert_eq!((hits[1].start, hits[1].end, hits[1].score), (3, 9, 6));
+    /// assert_eq!(hits[2].cigar.to_string(), "3=1X2=");
+    /// assert_eq!((hits[3].start, hits[3].strand), (21, Strand::Reverse));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn find_all(&self, pattern: &[u8], text: &[u8], min_score: i32) -> Result<Vec<PatternHit>> {
+        let mut hits = self.find_strand(pattern, text, min_score, Strand::Forward)?;
+        hits.extend(self.find_strand(
+            &reverse_complement(pattern),
+            text,
+            min_score,
+            Strand::Reverse,
+        )?);
+        hits.sort_by_key(|hit| (hit.start, hit.end, hit.strand == Strand::Reverse));
+        Ok(hits)
+    }
+
+    fn find_strand(
+        &self,
+        pattern: &[u8],
+        text: &[u8],
+        min_score: i32,
+        strand: Strand,
+    ) -> Result<Vec<PatternHit>> {
+        if pattern.is_empty() || text.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut builder = Aligner::new();
+        builder.matrix = Arc::clone(&self.matrix);
+        builder
+            .semi_global()
+            .gap_open(self.gap_open)
+            .gap_extend(self.gap_extend)
+            .allow_query_gaps(Vec::new());
+        let scanner = builder
+            .allow_ref_gaps(vec![String::from("prefix"), String::from("suffix")])
+            .use_last_rowcol()
+            .build();
+        let tracer = builder
+            .allow_ref_gaps(vec![String::from("prefix")])
+            .use_trace()
+            .build();
+
+        // end positions above the threshold that are the best (the leftmost
+        // on ties) within one pattern length, with a sliding window maximum
+        let alignment = scanner.align(Some(pattern), text)?;
+        let row = alignment.get_score_row()?;
+        let m = pattern.len();
+        let mut window: VecDeque<usize> = VecDeque::new();
+        let mut kept = Vec::new();
+        for k in 0..row.len() + m - 1 {
+            if k < row.len() {
+                while window.back().is_some_and(|&back| row[back] < row[k]) {
+                    window.pop_back();
+                }
+                window.push_back(k);
+            }
+            // window of j is complete, from j - m + 1 to j + m - 1
+            let Some(j) = (k + 1).checked_sub(m) else {
+                continue;
+            };
+            while window.front().is_some_and(|&front| front + m <= j) {
+                window.pop_front();
+            }
+            if window.front() == Some(&j) && row[j] >= min_score {
+                kept.push(j);
+            }
+        }
+
+        let mut hits = Vec::with_capacity(kept.len());
+        for end in kept {
+            // window long enough for the pattern with as many gaps, widened
+            // until it contains the best alignment (with cheap gaps)
+            let mut window_len = 2 * pattern.len();
+            let (window, alignment) = loop {
+                let window = (end + 1).saturating_sub(window_len)..end + 1;
+                let alignment = tracer.align(Some(pattern), &text[window.clone()])?;
+                if alignment.get_score() >= row[end] || window.start == 0 {
+                    break (window, alignment);
+                }
+                window_len *= 2;
+            };
+            let cigar = alignment.cigar(pattern, &text[window.clone()])?;
+
+            let mut ops = cigar.ops();
+            let mut start = window.start + cigar.ref_start;
+            while let Some((&(CigarOp::Del, len), rest)) = ops.split_first() {
+                start += len;
+                ops = rest;
+            }
+            hits.push(PatternHit {
+                strand,
+                start,
+                end: end + 1,
+                score: row[end],
+                cigar: Cigar::new(cigar.query_start, start, ops),
+            });
+        }
+        Ok(hits)
+    }
+}
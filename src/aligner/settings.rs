@@ -0,0 +1,126 @@
+//! Serde support for aligner settings (requires the `serde` feature).
+//! See `AlignerBuilder` for the format.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::AlignerBuilder;
+use crate::prelude::Matrix;
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+    #[default]
+    Global,
+    SemiGlobal,
+    Local,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum Strategy {
+    #[default]
+    Striped,
+    Scan,
+    Diag,
+}
+
+/// Serializable builder settings. The matrix is borrowed when serializing.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct AlignerSettings<M = Matrix> {
+    mode: Mode,
+    gap_open: i32,
+    gap_extend: i32,
+    /// Solution width in bits, or saturating mode if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solution_width: Option<i32>,
+    strategy: Strategy,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matrix: Option<M>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    allow_query_gaps: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    allow_ref_gaps: Vec<String>,
+    use_stats: bool,
+    use_table: bool,
+    use_last_rowcol: bool,
+    use_trace: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bandwidth: Option<i32>,
+}
+
+impl Serialize for AlignerBuilder {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let settings = AlignerSettings {
+            mode: match self.mode.as_str() {
+                "sg" => Mode::SemiGlobal,
+                "sw" => Mode::Local,
+                _ => Mode::Global,
+            },
+            gap_open: self.gap_open,
+            gap_extend: self.gap_extend,
+            solution_width: self.solution_width.parse().ok(),
+            strategy: match self.vec_strategy.as_str() {
+                "_scan" => Strategy::Scan,
+                "_diag" => Strategy::Diag,
+                _ => Strategy::Striped,
+            },
+            matrix: Some(self.matrix.as_ref()),
+            allow_query_gaps: self.allow_query_gaps.clone(),
+            allow_ref_gaps: self.allow_ref_gaps.clone(),
+            use_stats: !self.use_stats.is_empty(),
+            use_table: self.use_table == "_table",
+            use_last_rowcol: self.use_table == "_rowcol",
+            use_trace: !self.use_trace.is_empty(),
+            bandwidth: self.bandwidth,
+        };
+        settings.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AlignerBuilder {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let settings = AlignerSettings::<Matrix>::deserialize(deserializer)?;
+        let mut builder = AlignerBuilder::default();
+
+        match settings.mode {
+            Mode::Global => builder.global(),
+            Mode::SemiGlobal => builder.semi_global(),
+            Mode::Local => builder.local(),
+        };
+        match settings.strategy {
+            Strategy::Striped => builder.striped(),
+            Strategy::Scan => builder.scan(),
+            Strategy::Diag => builder.diag(),
+        };
+        builder
+            .gap_open(settings.gap_open)
+            .gap_extend(settings.gap_extend)
+            .allow_query_gaps(settings.allow_query_gaps)
+            .allow_ref_gaps(settings.allow_ref_gaps);
+
+        if let Some(solution_width) = settings.solution_width {
+            builder.solution_width(solution_width);
+        }
+        if let Some(matrix) = settings.matrix {
+            builder.matrix(matrix);
+        }
+        if let Some(bandwidth) = settings.bandwidth {
+            builder.bandwidth(bandwidth);
+        }
+        if settings.use_stats {
+            builder.use_stats();
+        }
+        if settings.use_table {
+            builder.use_table();
+        }
+        if settings.use_last_rowcol {
+            builder.use_last_rowcol();
+        }
+        if settings.use_trace {
+            builder.use_trace();
+        }
+
+        Ok(builder)
+    }
+}
@@ -0,0 +1,79 @@
+//! Alignment of queries of unknown orientation against both strands.
+
+use std::ffi::CString;
+
+use super::{Aligner, AlignerFn, Error};
+use crate::prelude::Result;
+use crate::sequence::{reverse_complement, Strand, StrandedAlignment};
+
+impl Aligner {
+    /// Align a query and its reverse complement against a reference, and
+    /// return the alignment with the higher score (the forward one on ties)
+    /// with its strand. Coordinates and CIGARs are in forward reference
+    /// space (see `StrandedAlignment`).
+    ///
+    /// Profile aligners need a profile of the reverse complement of the
+    /// query (see `AlignerBuilder::reverse_profile`), so that both profiles
+    /// are reused across references; pass `None` as the query in this case.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::Aligner;
+    /// use parasail_rs::sequence::Strand;
+    ///
+    /// let reference = b"TTGACCATGGCAGTAACG";
+    /// let read = b"CTGCCATGG";
+    /// let aligner = Aligner::new()
+    ///     .semi_global()
+    ///     .gap_open(5)
+    ///     .gap_extend(1)
+    ///     .use_trace()
+    ///     .build();
+    ///
+    /// let result = aligner.align_both_strands(Some(read), reference)?;
+    /// assert_eq!(result.strand, Strand::Reverse);
+    /// assert_eq!(result.alignment.get_end_ref(), 12);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the aligner has a profile but no reverse complement
+    /// profile, or no profile and no query.
+    pub fn align_both_strands(
+        &self,
+        query: Option<&[u8]>,
+        reference: &[u8],
+    ) -> Result<StrandedAlignment> {
+        let (forward, reverse) = match self.parasail_fn {
+            AlignerFn::Function(_) => {
+                let query =
+                    query.expect("Query sequence is required for alignment without a profile.");
+                let forward = self.align(Some(query), reference)?;
+                let reverse = self.align(Some(&reverse_complement(query)), reference)?;
+                (forward, reverse)
+            }
+            AlignerFn::PFunction(_) => {
+                assert!(
+                    !self.reverse_profile.is_null(),
+                    "A reverse complement profile is required to align both strands with a profile."
+                );
+                let forward = self.align(None, reference)?;
+                let ref_len = reference.len() as i32;
+                let reference = CString::new(reference).map_err(Error::InteriorNulByte)?;
+                let reverse = self.align_profile(&self.reverse_profile, &reference, ref_len)?;
+                (forward, reverse)
+            }
+        };
+
+        if reverse.get_score() > forward.get_score() {
+            Ok(StrandedAlignment {
+                strand: Strand::Reverse,
+                alignment: reverse,
+            })
+        } else {
+            Ok(StrandedAlignment {
+                strand: Strand::Forward,
+                alignment: forward,
+            })
+        }
+    }
+}
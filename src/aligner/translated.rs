@@ -0,0 +1,94 @@
+//! Translated searches of proteins against nucleotide sequences and vice
+//! versa.
+
+use super::{Aligner, AlignerFn};
+use crate::prelude::Result;
+use crate::translate::{GeneticCode, TranslatedAlignment};
+
+impl Aligner {
+    /// Align a protein query against the six-frame translation of a
+    /// nucleotide reference (as in tblastn), with a protein matrix such as
+    /// blosum62. Alignments are sorted by decreasing score, and frames whose
+    /// translation is empty are skipped.
+    ///
+    /// As for `align`, pass `None` as the query if the aligner was built with
+    /// a profile.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::{Aligner, Matrix};
+    /// use parasail_rs::translate::GeneticCode;
+    ///
+    /// let protein = b"MKTAYIAK";
+    /// let read = b"GGCTCTTTGCGATATACGCGGTTTTCATGG";
+    /// let aligner = Aligner::new()
+    ///     .matrix(Matrix::from("blosum62")?)
+    ///     .gap_open(11)
+    ///     .gap_extend(1)
+    ///     .local()
+    ///     .use_trace()
+    ///     .build();
+    ///
+    /// let hits = aligner.align_translated(Some(protein), read, &GeneticCode::standard())?;
+    /// let best = &hits[0];
+    /// assert_eq!(best.frame, -3);
+    /// assert_eq!(best.nucleotide_range(protein)?, 4..28);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn align_translated(
+        &self,
+        query: Option<&[u8]>,
+        reference: &[u8],
+        code: &GeneticCode,
+    ) -> Result<Vec<TranslatedAlignment>> {
+        let mut hits = Vec::with_capacity(6);
+        for translation in code.six_frames(reference) {
+            if translation.protein.is_empty() {
+                continue;
+            }
+            let alignment = self.align(query, &translation.protein)?;
+            hits.push(TranslatedAlignment {
+                frame: translation.frame,
+                translation,
+                alignment,
+                query_translated: false,
+            });
+        }
+        hits.sort_by_key(|hit| -hit.score());
+        Ok(hits)
+    }
+
+    /// Align the six-frame translation of a nucleotide query against a
+    /// protein reference (as in blastx), with a protein matrix such as
+    /// blosum62. Alignments are sorted by decreasing score, and frames whose
+    /// translation is empty are skipped.
+    ///
+    /// # Panics
+    /// Panics if the aligner was built with a profile, since the query is
+    /// different in each frame.
+    pub fn align_translated_query(
+        &self,
+        query: &[u8],
+        reference: &[u8],
+        code: &GeneticCode,
+    ) -> Result<Vec<TranslatedAlignment>> {
+        assert!(
+            matches!(self.parasail_fn, AlignerFn::Function(_)),
+            "Translated queries can not be aligned with a profile."
+        );
+        let mut hits = Vec::with_capacity(6);
+        for translation in code.six_frames(query) {
+            if translation.protein.is_empty() {
+                continue;
+            }
+            let alignment = self.align(Some(&translation.protein), reference)?;
+            hits.push(TranslatedAlignment {
+                frame: translation.frame,
+                translation,
+                alignment,
+                query_translated: true,
+            });
+        }
+        hits.sort_by_key(|hit| -hit.score());
+        Ok(hits)
+    }
+}
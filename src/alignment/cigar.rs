@@ -0,0 +1,454 @@
+//! Structured CIGAR strings and indel normalization.
+
+use libparasail_sys::{parasail_cigar_decode, parasail_result_get_cigar};
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use super::{Alignment, CigarString, Error};
+use crate::alignment::table::{TraceOp, TraceStep};
+use crate::prelude::Result;
+
+/// CIGAR operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CigarOp {
+    /// Aligned residues of unknown identity (`M`).
+    Match,
+    /// Identical aligned residues (`=`).
+    Equal,
+    /// Different aligned residues (`X`).
+    Diff,
+    /// Query residue aligned to a gap in the reference (`I`).
+    Ins,
+    /// Reference residue aligned to a gap in the query (`D`).
+    Del,
+    /// Query residue left out of the alignment (`S`).
+    SoftClip,
+}
+
+impl CigarOp {
+    /// Get the CIGAR character of the operation.
+    pub fn as_char(self) -> char {
+        match self {
+            CigarOp::Match => 'M',
+            CigarOp::Equal => '=',
+            CigarOp::Diff => 'X',
+            CigarOp::Ins => 'I',
+            CigarOp::Del => 'D',
+            CigarOp::SoftClip => 'S',
+        }
+    }
+
+    /// Check if the operation consumes query residues.
+    pub fn consumes_query(self) -> bool {
+        !matches!(self, CigarOp::Del)
+    }
+
+    /// Check if the operation consumes reference residues.
+    pub fn consumes_ref(self) -> bool {
+        !matches!(self, CigarOp::Ins | CigarOp::SoftClip)
+    }
+}
+
+/// Column of an expanded CIGAR, with the positions of its residues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Aligned(usize, usize),
+    Ins(usize),
+    Del(usize),
+    Clip(usize),
+}
+
+/// CIGAR string with the 0-based query and reference positions of its first
+/// operation, e.g. to post-process alignments before reporting them.
+///
+/// Consecutive operations of the same kind are merged. `Display` and
+/// `FromStr` use the SAM format (e.g., `"3=1X2I4="`); parsed CIGARs start at
+/// position 0 of both sequences.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Cigar {
+    pub query_start: usize,
+    pub ref_start: usize,
+    ops: Vec<(CigarOp, usize)>,
+}
+
+impl Cigar {
+    /// Create a CIGAR from operations and their lengths.
+    pub fn new(query_start: usize, ref_start: usize, ops: &[(CigarOp, usize)]) -> Self {
+        let mut cigar = Cigar {
+            query_start,
+            ref_start,
+            ops: Vec::with_capacity(ops.len()),
+        };
+        for &(op, len) in ops {
+            cigar.push(op, len);
+        }
+        cigar
+    }
+
+    /// Create a CIGAR from a traceback path (see `TracebackTable::path` and
+    /// `Aligner::tied_paths`). Diagonal steps become `=` or `X` depending
+    /// on whether `query` and `reference` residues are identical (ignoring
+    /// case).
+    pub fn from_path(path: &[TraceStep], query: &[u8], reference: &[u8]) -> Self {
+        let (query_start, ref_start) = match path.first() {
+            None => (0, 0),
+            Some(step) => match step.op {
+                TraceOp::Diag => (step.row, step.col),
+                TraceOp::Ins => (step.row + 1, step.col),
+                TraceOp::Del => (step.row, step.col + 1),
+            },
+        };
+        let columns: Vec<Column> = path
+            .iter()
+            .map(|step| match step.op {
+                TraceOp::Diag => Column::Aligned(step.row, step.col),
+                TraceOp::Ins => Column::Del(step.col),
+                TraceOp::Del => Column::Ins(step.row),
+            })
+            .collect();
+        Cigar::from_columns(query_start, ref_start, &columns, query, reference)
+    }
+
+    /// Get the operations and their lengths.
+    pub fn ops(&self) -> &[(CigarOp, usize)] {
+        &self.ops
+    }
+
+    /// Get the number of query residues covered by the CIGAR.
+    pub fn query_len(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|(op, _)| op.consumes_query())
+            .map(|(_, len)| len)
+            .sum()
+    }
+
+    /// Get the number of reference residues covered by the CIGAR.
+    pub fn ref_len(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|(op, _)| op.consumes_ref())
+            .map(|(_, len)| len)
+            .sum()
+    }
+
+    /// Append an operation, merging it with the last one if they are the
+    /// same.
+    pub fn push(&mut self, op: CigarOp, len: usize) {
+        if len == 0 {
+            return;
+        }
+        match self.ops.last_mut() {
+            Some((last, last_len)) if *last == op => *last_len += len,
+            _ => self.ops.push((op, len)),
+        }
+    }
+
+    /// Move every indel as far left as possible within repeats, such that
+    /// the same residues are aligned to the same score (e.g., a deleted `T`
+    /// in a run of `T`s is placed before the run). Adjacent insertions and
+    /// deletions are merged into one insertion followed by one deletion.
+    /// Aligned residues are reported as `=` or `X`.
+    ///
+    /// Shifting an indel never changes the alignment score. Merging adjacent
+    /// indels opens fewer gaps, so it can only increase the score with
+    /// affine gap penalties.
+    ///
+    /// `query` and `reference` are the full sequences that the CIGAR
+    /// positions refer to.
+    ///
+    /// # Panics
+    /// Panics if the CIGAR extends past the end of either sequence.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::Cigar;
+    ///
+    /// let cigar: Cigar = "4=1D2=".parse()?;
+    /// let left = cigar.left_align(b"ACGTTG", b"ACGTTTG");
+    /// assert_eq!(left.to_string(), "3=1D3=");
+    /// let right = cigar.right_align(b"ACGTTG", b"ACGTTTG");
+    /// assert_eq!(right.to_string(), "5=1D1=");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn left_align(&self, query: &[u8], reference: &[u8]) -> Cigar {
+        let columns = self.columns(query, reference);
+        let columns = shift_left(&columns, query, reference);
+        Cigar::from_columns(self.query_start, self.ref_start, &columns, query, reference)
+    }
+
+    /// Move every indel as far right as possible within repeats. See
+    /// `left_align`.
+    ///
+    /// # Panics
+    /// Panics if the CIGAR extends past the end of either sequence.
+    pub fn right_align(&self, query: &[u8], reference: &[u8]) -> Cigar {
+        // mirror the sequences and positions, shift left and mirror back
+        let (query_len, ref_len) = (query.len(), reference.len());
+        let mirror = |column: &Column| match *column {
+            Column::Aligned(i, j) => Column::Aligned(query_len - 1 - i, ref_len - 1 - j),
+            Column::Ins(i) => Column::Ins(query_len - 1 - i),
+            Column::Del(j) => Column::Del(ref_len - 1 - j),
+            Column::Clip(i) => Column::Clip(query_len - 1 - i),
+        };
+        let reversed_query: Vec<u8> = query.iter().rev().copied().collect();
+        let reversed_ref: Vec<u8> = reference.iter().rev().copied().collect();
+
+        let columns: Vec<Column> = self
+            .columns(query, reference)
+            .iter()
+            .rev()
+            .map(mirror)
+            .collect();
+        let mut columns: Vec<Column> = shift_left(&columns, &reversed_query, &reversed_ref)
+            .iter()
+            .rev()
+            .map(mirror)
+            .collect();
+        // merged indels are mirrored too, so put insertions first again
+        let mut start = 0;
+        while start < columns.len() {
+            let len = columns[start..]
+                .iter()
+                .take_while(|column| matches!(column, Column::Ins(_) | Column::Del(_)))
+                .count();
+            columns[start..start + len].sort_by_key(|column| matches!(column, Column::Del(_)));
+            start += len.max(1);
+        }
+        Cigar::from_columns(self.query_start, self.ref_start, &columns, query, reference)
+    }
+
+    /// Merge adjacent insertions and deletions into one insertion followed
+    /// by one deletion, without moving them.
+    pub fn merge_indels(&self) -> Cigar {
+        let mut cigar = Cigar {
+            query_start: self.query_start,
+            ref_start: self.ref_start,
+            ops: Vec::with_capacity(self.ops.len()),
+        };
+        let (mut ins, mut del) = (0, 0);
+        for &(op, len) in &self.ops {
+            match op {
+                CigarOp::Ins => ins += len,
+                CigarOp::Del => del += len,
+                _ => {
+                    cigar.push(CigarOp::Ins, ins);
+                    cigar.push(CigarOp::Del, del);
+                    (ins, del) = (0, 0);
+                    cigar.push(op, len);
+                }
+            }
+        }
+        cigar.push(CigarOp::Ins, ins);
+        cigar.push(CigarOp::Del, del);
+        cigar
+    }
+
+    /// Expand the CIGAR into one column per operation.
+    fn columns(&self, query: &[u8], reference: &[u8]) -> Vec<Column> {
+        assert!(
+            self.query_start + self.query_len() <= query.len(),
+            "CIGAR string is longer than the query sequence."
+        );
+        assert!(
+            self.ref_start + self.ref_len() <= reference.len(),
+            "CIGAR string is longer than the reference sequence."
+        );
+
+        let (mut i, mut j) = (self.query_start, self.ref_start);
+        let mut columns = Vec::new();
+        for &(op, len) in &self.ops {
+            for _ in 0..len {
+                columns.push(match op {
+                    CigarOp::Match | CigarOp::Equal | CigarOp::Diff => Column::Aligned(i, j),
+                    CigarOp::Ins => Column::Ins(i),
+                    CigarOp::Del => Column::Del(j),
+                    CigarOp::SoftClip => Column::Clip(i),
+                });
+                i += op.consumes_query() as usize;
+                j += op.consumes_ref() as usize;
+            }
+        }
+        columns
+    }
+
+    fn from_columns(
+        query_start: usize,
+        ref_start: usize,
+        columns: &[Column],
+        query: &[u8],
+        reference: &[u8],
+    ) -> Cigar {
+        let mut cigar = Cigar {
+            query_start,
+            ref_start,
+            ops: Vec::new(),
+        };
+        for column in columns {
+            let op = match *column {
+                Column::Aligned(i, j) if query[i].eq_ignore_ascii_case(&reference[j]) => {
+                    CigarOp::Equal
+                }
+                Column::Aligned(..) => CigarOp::Diff,
+                Column::Ins(_) => CigarOp::Ins,
+                Column::Del(_) => CigarOp::Del,
+                Column::Clip(_) => CigarOp::SoftClip,
+            };
+            cigar.push(op, 1);
+        }
+        cigar
+    }
+}
+
+/// Shift indels left past aligned columns whose residue equals the last
+/// residue of the indel, merging them with indels they become adjacent to.
+fn shift_left(columns: &[Column], query: &[u8], reference: &[u8]) -> Vec<Column> {
+    let mut shifted: Vec<Column> = Vec::with_capacity(columns.len());
+    let mut k = 0;
+    while k < columns.len() {
+        if matches!(columns[k], Column::Aligned(..) | Column::Clip(_)) {
+            shifted.push(columns[k]);
+            k += 1;
+            continue;
+        }
+
+        // block of adjacent indels
+        let mut ins = VecDeque::new();
+        let mut del = VecDeque::new();
+        while let Some(&column) = columns.get(k) {
+            match column {
+                Column::Ins(i) => ins.push_back(i),
+                Column::Del(j) => del.push_back(j),
+                _ => break,
+            }
+            k += 1;
+        }
+
+        let mut moved = VecDeque::new();
+        loop {
+            // shift pure insertions or deletions past matching residues
+            while let Some(&Column::Aligned(i, j)) = shifted.last() {
+                let last = match (ins.back(), del.back()) {
+                    (Some(&last), None) if query[i].eq_ignore_ascii_case(&query[last]) => {
+                        ins.pop_back();
+                        ins.push_front(i);
+                        Column::Aligned(last, j)
+                    }
+                    (None, Some(&last)) if reference[j].eq_ignore_ascii_case(&reference[last]) => {
+                        del.pop_back();
+                        del.push_front(j);
+                        Column::Aligned(i, last)
+                    }
+                    _ => break,
+                };
+                shifted.pop();
+                moved.push_front(last);
+            }
+
+            // merge with a preceding indel and try again
+            match shifted.last() {
+                Some(&Column::Ins(i)) => {
+                    shifted.pop();
+                    ins.push_front(i);
+                }
+                Some(&Column::Del(j)) => {
+                    shifted.pop();
+                    del.push_front(j);
+                }
+                _ => break,
+            }
+            while let Some(&column) = shifted.last() {
+                match column {
+                    Column::Ins(i) => ins.push_front(i),
+                    Column::Del(j) => del.push_front(j),
+                    _ => break,
+                }
+                shifted.pop();
+            }
+        }
+
+        shifted.extend(ins.into_iter().map(Column::Ins));
+        shifted.extend(del.into_iter().map(Column::Del));
+        shifted.extend(moved);
+    }
+    shifted
+}
+
+impl Display for Cigar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (op, len) in &self.ops {
+            write!(f, "{len}{}", op.as_char())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Cigar {
+    type Err = crate::error::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut cigar = Cigar::default();
+        let mut len = String::new();
+        for c in s.chars() {
+            if c.is_ascii_digit() {
+                len.push(c);
+                continue;
+            }
+            let op = match c {
+                'M' => CigarOp::Match,
+                '=' => CigarOp::Equal,
+                'X' => CigarOp::Diff,
+                'I' => CigarOp::Ins,
+                'D' => CigarOp::Del,
+                'S' => CigarOp::SoftClip,
+                _ => return Err(Error::InvalidCigar(s.to_string()).into()),
+            };
+            let count = len
+                .parse()
+                .map_err(|_| Error::InvalidCigar(s.to_string()))?;
+            cigar.push(op, count);
+            len.clear();
+        }
+        if !len.is_empty() {
+            return Err(Error::InvalidCigar(s.to_string()).into());
+        }
+        Ok(cigar)
+    }
+}
+
+impl Alignment {
+    /// Get the CIGAR of the alignment with its start positions (see
+    /// `get_cigar` for the string only).
+    pub fn cigar(&self, query: &[u8], reference: &[u8]) -> Result<Cigar> {
+        if !self.is_trace() {
+            return Err(Error::NoTrace(String::from("cigar()")).into());
+        }
+
+        let query_len = query.len() as i32;
+        let query = CString::new(query).map_err(Error::InteriorNulByte)?;
+        let ref_len = reference.len() as i32;
+        let reference = CString::new(reference).map_err(Error::InteriorNulByte)?;
+        unsafe {
+            let inner = parasail_result_get_cigar(
+                self.inner,
+                query.as_ptr(),
+                query_len,
+                reference.as_ptr(),
+                ref_len,
+                **self.matrix,
+            );
+            if inner.is_null() {
+                return Err(Error::NoTrace(String::from("cigar()")).into());
+            }
+            let encoded = CigarString { inner };
+            let decoded = parasail_cigar_decode(encoded.inner);
+            let parsed = CStr::from_ptr(decoded).to_string_lossy().parse();
+            libc::free(decoded as *mut libc::c_void);
+            let mut cigar: Cigar = parsed?;
+            cigar.query_start = (*encoded.inner).beg_query as usize;
+            cigar.ref_start = (*encoded.inner).beg_ref as usize;
+            Ok(cigar)
+        }
+    }
+}
@@ -14,6 +14,9 @@ pub enum Error {
     #[from]
     InteriorNulByte(NulError),
     NoBandwidth,
+    InvalidCigar(String),
+    #[from]
+    Io(std::io::Error),
 }
 
 impl Display for Error {
@@ -0,0 +1,538 @@
+//! Rendering pairwise alignments as text blocks.
+
+use std::io::Write;
+
+use super::{Alignment, Error};
+use crate::prelude::{Matrix, MatrixKind, Result};
+
+/// Layout of the blocks written by `Formatter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FormatStyle {
+    /// BLAST-style blocks, with the start coordinate after the name:
+    ///
+    /// ```text
+    /// Query  1  ACGTACGT  8
+    ///           |||.||||
+    /// Sbjct  1  ACGAACGT  8
+    /// ```
+    #[default]
+    Blast,
+    /// EMBOSS-style blocks (as written by `needle` and `water`), with
+    /// right-aligned coordinates:
+    ///
+    /// ```text
+    /// Query              1 ACGTACGT      8
+    ///                      |||.||||
+    /// Sbjct              1 ACGAACGT      8
+    /// ```
+    Emboss,
+}
+
+/// Renders pairwise alignments as BLAST- or EMBOSS-style blocks to any
+/// writer.
+///
+/// By default, lines are 60 columns wide, sequences are named "Query" and
+/// "Sbjct", 1-based coordinates are shown and the comparison line uses `|`
+/// for matches, `:` for similar residues (positive scores), `.` for
+/// mismatches and a space for gaps. Traceback must be enabled on the aligner.
+///
+/// Besides plain text, alignments can be rendered with ANSI colors for
+/// terminals (`write_ansi`) or as a standalone HTML page (`write_html`), with
+/// matches, similar residues, mismatches and gaps colored differently.
+///
+/// ```rust,no_run
+/// use parasail_rs::alignment::{FormatStyle, Formatter};
+/// use parasail_rs::prelude::Aligner;
+///
+/// let query = b"ACGTACGT";
+/// let reference = b"ACGAACGT";
+/// let aligner = Aligner::new().gap_open(5).gap_extend(1).use_trace().build();
+/// let result = aligner.align(Some(query), reference)?;
+///
+/// Formatter::new()
+///     .style(FormatStyle::Emboss)
+///     .width(50)
+///     .names("read", "amplicon")
+///     .write(&result, query, reference, std::io::stdout())?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Formatter {
+    style: FormatStyle,
+    width: usize,
+    query_name: String,
+    ref_name: String,
+    coordinates: bool,
+    match_symbol: u8,
+    similar_symbol: u8,
+    mismatch_symbol: u8,
+    gap_symbol: u8,
+    ruler: bool,
+    score_matrix: Option<Matrix>,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Formatter {
+            style: FormatStyle::default(),
+            width: 60,
+            query_name: String::from("Query"),
+            ref_name: String::from("Sbjct"),
+            coordinates: true,
+            match_symbol: b'|',
+            similar_symbol: b':',
+            mismatch_symbol: b'.',
+            gap_symbol: b' ',
+            ruler: false,
+            score_matrix: None,
+        }
+    }
+}
+
+impl Formatter {
+    /// Create a new formatter with the default layout.
+    pub fn new() -> Self {
+        Formatter::default()
+    }
+
+    /// Set the block layout. The default is `FormatStyle::Blast`.
+    pub fn style(&mut self, style: FormatStyle) -> &mut Self {
+        self.style = style;
+        self
+    }
+
+    /// Set the number of alignment columns per line. The default is 60.
+    pub fn width(&mut self, width: usize) -> &mut Self {
+        assert!(width > 0, "Line width must be greater than 0.");
+        self.width = width;
+        self
+    }
+
+    /// Set the names shown before the query and reference lines.
+    pub fn names(&mut self, query_name: &str, ref_name: &str) -> &mut Self {
+        self.query_name = query_name.to_string();
+        self.ref_name = ref_name.to_string();
+        self
+    }
+
+    /// Show or hide the 1-based start and end coordinates of each line.
+    /// Coordinates are shown by default.
+    pub fn coordinates(&mut self, coordinates: bool) -> &mut Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Set the comparison symbol for identical residues. The default is `|`.
+    pub fn match_symbol(&mut self, symbol: u8) -> &mut Self {
+        self.match_symbol = symbol;
+        self
+    }
+
+    /// Set the comparison symbol for different residues with a positive
+    /// substitution score. The default is `:`.
+    pub fn similar_symbol(&mut self, symbol: u8) -> &mut Self {
+        self.similar_symbol = symbol;
+        self
+    }
+
+    /// Set the comparison symbol for other mismatches. The default is `.`.
+    pub fn mismatch_symbol(&mut self, symbol: u8) -> &mut Self {
+        self.mismatch_symbol = symbol;
+        self
+    }
+
+    /// Set the comparison symbol for gap columns. The default is a space.
+    pub fn gap_symbol(&mut self, symbol: u8) -> &mut Self {
+        self.gap_symbol = symbol;
+        self
+    }
+
+    /// Show a ruler above each block with the alignment column numbers, every
+    /// 10 columns. The ruler is hidden by default.
+    pub fn ruler(&mut self, ruler: bool) -> &mut Self {
+        self.ruler = ruler;
+        self
+    }
+
+    /// Show a track below each block with the substitution score of each
+    /// column from `matrix` (usually the aligner's matrix). Scores are drawn
+    /// as bars from the lowest to the highest score of the matrix, with
+    /// the exact score as a tooltip in HTML. Gap columns are left empty.
+    pub fn score_track(&mut self, matrix: &Matrix) -> &mut Self {
+        self.score_matrix = Some(matrix.clone());
+        self
+    }
+
+    /// Write the alignment of `query` and `reference` as blocks of three
+    /// lines (query, comparison and reference) separated by empty lines.
+    pub fn write<W: Write>(
+        &self,
+        alignment: &Alignment,
+        query: &[u8],
+        reference: &[u8],
+        writer: W,
+    ) -> Result<()> {
+        write_all(&self.render(alignment, query, reference)?, writer)
+    }
+
+    /// Render the alignment as in `write`, but into a string.
+    pub fn render(&self, alignment: &Alignment, query: &[u8], reference: &[u8]) -> Result<String> {
+        self.render_as(Target::Text, alignment, query, reference)
+    }
+
+    /// Write the alignment as in `write`, with residues colored by ANSI
+    /// escape codes: green for matches, yellow for similar residues, red for
+    /// mismatches and dimmed for gaps.
+    pub fn write_ansi<W: Write>(
+        &self,
+        alignment: &Alignment,
+        query: &[u8],
+        reference: &[u8],
+        writer: W,
+    ) -> Result<()> {
+        write_all(&self.render_ansi(alignment, query, reference)?, writer)
+    }
+
+    /// Render the alignment as in `write_ansi`, but into a string.
+    pub fn render_ansi(
+        &self,
+        alignment: &Alignment,
+        query: &[u8],
+        reference: &[u8],
+    ) -> Result<String> {
+        self.render_as(Target::Ansi, alignment, query, reference)
+    }
+
+    /// Write the alignment as a standalone HTML page, e.g. for reports viewed
+    /// in a browser. Residues are wrapped in `match`, `similar`, `mismatch`
+    /// and `gap` classes, styled in the page header.
+    pub fn write_html<W: Write>(
+        &self,
+        alignment: &Alignment,
+        query: &[u8],
+        reference: &[u8],
+        writer: W,
+    ) -> Result<()> {
+        write_all(&self.render_html(alignment, query, reference)?, writer)
+    }
+
+    /// Render the alignment as in `write_html`, but into a string.
+    pub fn render_html(
+        &self,
+        alignment: &Alignment,
+        query: &[u8],
+        reference: &[u8],
+    ) -> Result<String> {
+        let blocks = self.render_as(Target::Html, alignment, query, reference)?;
+        Ok(format!("{HTML_HEADER}{blocks}{HTML_FOOTER}"))
+    }
+
+    fn render_as(
+        &self,
+        target: Target,
+        alignment: &Alignment,
+        query: &[u8],
+        reference: &[u8],
+    ) -> Result<String> {
+        let (blocks, indent) = self.blocks(alignment, query, reference)?;
+        let indent = " ".repeat(indent);
+        let mut rendered = String::new();
+        for block in blocks {
+            if self.ruler {
+                let ruler = ruler(block.first_column, block.columns.len());
+                rendered.push_str(&format!("{indent}{ruler}\n"));
+            }
+            let comparison = String::from_utf8_lossy(&block.comparison);
+            let comparison = format!("{indent}{comparison}");
+            rendered.push_str(&target.escape(&block.query_label));
+            rendered.push_str(&target.residues(&block.query, &block.columns));
+            rendered.push_str(&target.escape(&block.query_end));
+            rendered.push('\n');
+            rendered.push_str(&target.escape(comparison.trim_end()));
+            rendered.push('\n');
+            rendered.push_str(&target.escape(&block.ref_label));
+            rendered.push_str(&target.residues(&block.reference, &block.columns));
+            rendered.push_str(&target.escape(&block.ref_end));
+            rendered.push('\n');
+            if let Some(matrix) = &self.score_matrix {
+                let track = target.score_track(&block.scores, matrix);
+                rendered.push_str(&format!("{indent}{}\n", track.trim_end()));
+            }
+            rendered.push('\n');
+        }
+
+        Ok(rendered)
+    }
+
+    /// Split the alignment into blocks of `width` columns, each labeled with
+    /// its names and coordinates. Also returns the width of the labels.
+    fn blocks(
+        &self,
+        alignment: &Alignment,
+        query: &[u8],
+        reference: &[u8],
+    ) -> Result<(Vec<Block>, usize)> {
+        if !alignment.is_trace() {
+            return Err(Error::NoTrace(String::from("Formatter::write()")).into());
+        }
+
+        let traceback = alignment.traceback_with(query, reference, b'|', b':', b'.')?;
+        let record = alignment.record(query, reference)?;
+        let mut query_pos = record.query_start.unwrap_or(0) as usize;
+        let mut ref_pos = record.ref_start.unwrap_or(0) as usize;
+
+        let residues = |line: &str| line.bytes().filter(|&residue| residue != b'-').count();
+        let coord_width = (query_pos + residues(&traceback.query))
+            .max(ref_pos + residues(&traceback.reference))
+            .to_string()
+            .len();
+        let (name_width, coord_width) = match self.style {
+            FormatStyle::Blast => (self.query_name.len().max(self.ref_name.len()), coord_width),
+            FormatStyle::Emboss => (
+                self.query_name.len().max(self.ref_name.len()).max(13),
+                coord_width.max(6),
+            ),
+        };
+        let indent = match (self.style, self.coordinates) {
+            (_, false) => name_width + 2,
+            (FormatStyle::Blast, true) => name_width + coord_width + 4,
+            (FormatStyle::Emboss, true) => name_width + coord_width + 2,
+        };
+
+        let query_lines = traceback.query.as_bytes().chunks(self.width);
+        let comparison_lines = traceback.comparison.as_bytes().chunks(self.width);
+        let ref_lines = traceback.reference.as_bytes().chunks(self.width);
+        let mut blocks = Vec::new();
+        for (line, ((query_line, comparison_line), ref_line)) in
+            query_lines.zip(comparison_lines).zip(ref_lines).enumerate()
+        {
+            let mut block = Block {
+                first_column: line * self.width + 1,
+                query: query_line.to_vec(),
+                reference: ref_line.to_vec(),
+                ..Block::default()
+            };
+            let mut position = query_pos;
+            for (&symbol, (&q, &r)) in comparison_line.iter().zip(query_line.iter().zip(ref_line)) {
+                let column = match symbol {
+                    _ if q == b'-' || r == b'-' => Column::Gap,
+                    b'|' => Column::Match,
+                    b':' => Column::Similar,
+                    _ => Column::Mismatch,
+                };
+                block.comparison.push(match column {
+                    Column::Match => self.match_symbol,
+                    Column::Similar => self.similar_symbol,
+                    Column::Mismatch => self.mismatch_symbol,
+                    Column::Gap => self.gap_symbol,
+                });
+                block.columns.push(column);
+                block.scores.push(match (&self.score_matrix, column) {
+                    (_, Column::Gap) | (None, _) => None,
+                    (Some(matrix), _) => match matrix.kind() {
+                        MatrixKind::Square => matrix.score(q, r),
+                        MatrixKind::Pssm => matrix.pssm_score(position, r),
+                    },
+                });
+                if q != b'-' {
+                    position += 1;
+                }
+            }
+
+            (block.query_label, block.query_end) = self.labels(
+                &self.query_name,
+                query_line,
+                &mut query_pos,
+                name_width,
+                coord_width,
+            );
+            (block.ref_label, block.ref_end) = self.labels(
+                &self.ref_name,
+                ref_line,
+                &mut ref_pos,
+                name_width,
+                coord_width,
+            );
+            blocks.push(block);
+        }
+
+        Ok((blocks, indent))
+    }
+
+    /// Format the text before and after the residues of a line, advancing
+    /// `pos` past its residues.
+    fn labels(
+        &self,
+        name: &str,
+        residues: &[u8],
+        pos: &mut usize,
+        name_width: usize,
+        coord_width: usize,
+    ) -> (String, String) {
+        let start = *pos + 1;
+        *pos += residues.iter().filter(|&&residue| residue != b'-').count();
+
+        match (self.style, self.coordinates) {
+            (_, false) => (format!("{name:<name_width$}  "), String::new()),
+            (FormatStyle::Blast, true) => (
+                format!("{name:<name_width$}  {start:<coord_width$}  "),
+                format!("  {}", *pos),
+            ),
+            (FormatStyle::Emboss, true) => (
+                format!("{name:<name_width$} {start:>coord_width$} "),
+                format!(" {:>coord_width$}", *pos),
+            ),
+        }
+    }
+}
+
+const HTML_HEADER: &str = "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<style>
+pre { font-family: monospace; }
+.match { background-color: #b7e4c7; }
+.similar { background-color: #ffe8a1; }
+.mismatch { background-color: #f4a6a6; }
+.gap { color: #999999; }
+</style>
+</head>
+<body>
+<pre>
+";
+
+const HTML_FOOTER: &str = "</pre>
+</body>
+</html>
+";
+
+/// Bars for the score track, from the lowest to the highest score.
+const SCORE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Kind of an alignment column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Match,
+    Similar,
+    Mismatch,
+    Gap,
+}
+
+/// One line of the alignment, with the labels and residues of both sequences
+/// and the kind, comparison symbol and score of each column.
+#[derive(Default)]
+struct Block {
+    first_column: usize,
+    query_label: String,
+    query: Vec<u8>,
+    query_end: String,
+    ref_label: String,
+    reference: Vec<u8>,
+    ref_end: String,
+    comparison: Vec<u8>,
+    columns: Vec<Column>,
+    scores: Vec<Option<i32>>,
+}
+
+/// Output format of `Formatter::render_as`.
+#[derive(Clone, Copy)]
+enum Target {
+    Text,
+    Ansi,
+    Html,
+}
+
+impl Target {
+    fn escape(self, text: &str) -> String {
+        match self {
+            Target::Html => text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;"),
+            _ => text.to_string(),
+        }
+    }
+
+    /// Color runs of residues by the kind of their columns.
+    fn residues(self, residues: &[u8], columns: &[Column]) -> String {
+        let mut rendered = String::new();
+        let mut start = 0;
+        while start < residues.len() {
+            let column = columns[start];
+            let len = columns[start..]
+                .iter()
+                .take_while(|&&other| other == column)
+                .count();
+            let run = String::from_utf8_lossy(&residues[start..start + len]);
+            match self {
+                Target::Text => rendered.push_str(&run),
+                Target::Ansi => {
+                    let color = match column {
+                        Column::Match => "32",
+                        Column::Similar => "33",
+                        Column::Mismatch => "31",
+                        Column::Gap => "2",
+                    };
+                    rendered.push_str(&format!("\x1b[{color}m{run}\x1b[0m"));
+                }
+                Target::Html => {
+                    let class = match column {
+                        Column::Match => "match",
+                        Column::Similar => "similar",
+                        Column::Mismatch => "mismatch",
+                        Column::Gap => "gap",
+                    };
+                    let run = self.escape(&run);
+                    rendered.push_str(&format!("<span class=\"{class}\">{run}</span>"));
+                }
+            }
+            start += len;
+        }
+        rendered
+    }
+
+    fn score_track(self, scores: &[Option<i32>], matrix: &Matrix) -> String {
+        let (min, max) = (matrix.min_score(), matrix.max_score());
+        scores
+            .iter()
+            .map(|score| match score {
+                None => String::from(" "),
+                Some(score) => {
+                    let level = if max > min {
+                        ((score - min) as usize * (SCORE_BARS.len() - 1)) / (max - min) as usize
+                    } else {
+                        SCORE_BARS.len() - 1
+                    };
+                    let bar = SCORE_BARS[level];
+                    match self {
+                        Target::Html => format!("<span title=\"{score}\">{bar}</span>"),
+                        _ => bar.to_string(),
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Alignment column numbers every 10 columns, right-aligned to their column.
+fn ruler(first_column: usize, len: usize) -> String {
+    let mut ruler = vec![b'.'; len];
+    for offset in 0..len {
+        let column = first_column + offset;
+        if column.is_multiple_of(10) {
+            for (digit, byte) in column.to_string().bytes().rev().enumerate() {
+                if digit <= offset {
+                    ruler[offset - digit] = byte;
+                }
+            }
+        }
+    }
+    String::from_utf8_lossy(&ruler).into_owned()
+}
+
+fn write_all<W: Write>(rendered: &str, mut writer: W) -> Result<()> {
+    writer.write_all(rendered.as_bytes()).map_err(Error::Io)?;
+    Ok(())
+}
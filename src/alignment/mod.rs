@@ -1,17 +1,20 @@
 //! Alignment result handling.
 
+mod cigar;
 mod error;
+mod format;
+mod record;
 pub mod table;
 
+use libc::c_char;
 use libparasail_sys::{
-    parasail_cigar_decode, parasail_cigar_free, parasail_cigar_t, parasail_matrix_t,
-    parasail_result_free, parasail_result_get_cigar, parasail_result_get_end_query,
-    parasail_result_get_end_ref, parasail_result_get_length, parasail_result_get_length_col,
-    parasail_result_get_length_row, parasail_result_get_length_table, parasail_result_get_matches,
-    parasail_result_get_matches_col, parasail_result_get_matches_row,
-    parasail_result_get_matches_table, parasail_result_get_score, parasail_result_get_score_col,
-    parasail_result_get_score_row, parasail_result_get_score_table, parasail_result_get_similar,
-    parasail_result_get_similar_col, parasail_result_get_similar_row,
+    parasail_cigar_decode, parasail_cigar_free, parasail_cigar_t, parasail_result_free,
+    parasail_result_get_cigar, parasail_result_get_end_query, parasail_result_get_end_ref,
+    parasail_result_get_length, parasail_result_get_length_col, parasail_result_get_length_row,
+    parasail_result_get_length_table, parasail_result_get_matches, parasail_result_get_matches_col,
+    parasail_result_get_matches_row, parasail_result_get_matches_table, parasail_result_get_score,
+    parasail_result_get_score_col, parasail_result_get_score_row, parasail_result_get_score_table,
+    parasail_result_get_similar, parasail_result_get_similar_col, parasail_result_get_similar_row,
     parasail_result_get_similar_table, parasail_result_get_trace_table,
     parasail_result_get_traceback, parasail_result_is_banded, parasail_result_is_blocked,
     parasail_result_is_diag, parasail_result_is_nw, parasail_result_is_rowcol,
@@ -19,16 +22,27 @@ use libparasail_sys::{
     parasail_result_is_stats, parasail_result_is_stats_rowcol, parasail_result_is_stats_table,
     parasail_result_is_striped, parasail_result_is_sw, parasail_result_is_table,
     parasail_result_is_trace, parasail_result_ssw_free, parasail_result_ssw_t, parasail_result_t,
-    parasail_traceback_generic,
+    parasail_traceback_free, parasail_traceback_generic,
 };
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::slice;
+use std::sync::Arc;
 
 use crate::alignment::table::TracebackTable;
+use crate::matrix::Matrix;
 use crate::prelude::Result;
+pub use cigar::{Cigar, CigarOp};
 pub use error::Error;
+pub use format::{FormatStyle, Formatter};
+pub use record::AlignmentRecord;
 pub use table::Table;
 
+// parasail result flags (see parasail.h)
+const FLAG_BITS_16: i32 = 1 << 21;
+const FLAG_BITS_32: i32 = 1 << 22;
+const FLAG_BITS_64: i32 = 1 << 23;
+const FLAG_LANES_1: i32 = 1 << 24;
+
 /// CIGAR string for sequence alignment.
 struct CigarString {
     inner: *mut parasail_cigar_t,
@@ -54,7 +68,8 @@ pub struct Traceback {
 #[derive(Debug, Clone)]
 pub struct Alignment {
     pub(crate) inner: *mut parasail_result_t,
-    pub(crate) matrix: *const parasail_matrix_t,
+    /// Matrix of the aligner, kept alive for traceback.
+    pub(crate) matrix: Arc<Matrix>,
     pub(crate) query_len: i32,
     pub(crate) ref_len: i32,
 }
@@ -288,25 +303,55 @@ impl Alignment {
     }
 
     /// Get the trace table.
+    ///
+    /// Striped and scan alignments store the trace table in a vectorized
+    /// layout, which is converted to the row-major layout of `TracebackTable`.
     pub fn get_trace_table(&self) -> Result<TracebackTable<'_>> {
         if self.is_trace() {
-            unsafe {
-                let table_ptr = parasail_result_get_trace_table(self.inner) as *const i8;
-                let table_size = (self.query_len * self.ref_len) as usize;
-                let data = slice::from_raw_parts(table_ptr, table_size);
+            let rows = self.query_len as usize;
+            let cols = self.ref_len as usize;
 
-                Ok(TracebackTable::new(
-                    data,
-                    self.query_len as usize,
-                    self.ref_len as usize,
-                ))
+            unsafe {
+                let table_ptr = parasail_result_get_trace_table(self.inner);
+
+                if !(self.is_striped() || self.is_scan()) {
+                    let data = slice::from_raw_parts(table_ptr as *const i8, rows * cols);
+                    return Ok(TracebackTable::new(data, rows, cols));
+                }
+
+                let flag = (*self.inner).flag;
+                let lanes = (0..7)
+                    .find(|k| flag & (FLAG_LANES_1 << k) != 0)
+                    .map_or(1, |k| 1 << k);
+                let seg_len = rows.div_ceil(lanes);
+
+                let mut data = Vec::with_capacity(rows * cols);
+                for i in 0..rows {
+                    for j in 0..cols {
+                        let loc = j * seg_len * lanes + (i % seg_len) * lanes + i / seg_len;
+                        // flags fit in the lowest byte of each element
+                        let value = if flag & FLAG_BITS_64 != 0 {
+                            *(table_ptr as *const i64).add(loc) as i8
+                        } else if flag & FLAG_BITS_32 != 0 {
+                            *table_ptr.add(loc) as i8
+                        } else if flag & FLAG_BITS_16 != 0 {
+                            *(table_ptr as *const i16).add(loc) as i8
+                        } else {
+                            *(table_ptr as *const i8).add(loc)
+                        };
+                        data.push(value);
+                    }
+                }
+
+                Ok(TracebackTable::new(data, rows, cols))
             }
         } else {
             Err(Error::NoTrace(String::from("get_trace_table()")))?
         }
     }
 
-    /// Get alignment strings and statistics
+    /// Print alignment strings and statistics to stdout. See `Formatter` for
+    /// writing alignments with a configurable layout to any writer.
     pub fn print_traceback(&self, query: &[u8], reference: &[u8]) {
         if self.is_trace() {
             let query_len = query.len() as i32;
@@ -328,7 +373,7 @@ impl Alignment {
                     ref_len,
                     query_str.as_ptr(),
                     ref_str.as_ptr(),
-                    self.matrix,
+                    **self.matrix,
                     self.inner,
                     *match_char.as_ptr(),
                     *mismatch_char.as_ptr(),
@@ -346,46 +391,57 @@ impl Alignment {
     /// Get alignment strings.
     pub fn get_traceback_strings(&self, query: &[u8], reference: &[u8]) -> Result<Traceback> {
         if self.is_trace() {
-            let query_len = query.len() as i32;
-            let ref_len = reference.len() as i32;
-            let query = CString::new(query).map_err(Error::InteriorNulByte)?;
-            let reference = CString::new(reference).map_err(Error::InteriorNulByte)?;
-            let match_char = CString::new("|").map_err(Error::InteriorNulByte)?;
-            let mismatch_char = CString::new(" ").map_err(Error::InteriorNulByte)?;
-            unsafe {
-                let alignment = parasail_result_get_traceback(
-                    self.inner,
-                    query.as_ptr(),
-                    query_len,
-                    reference.as_ptr(),
-                    ref_len,
-                    self.matrix,
-                    *match_char.as_ptr(),
-                    *mismatch_char.as_ptr(),
-                    *mismatch_char.as_ptr(),
-                );
-
-                let query_traceback = CString::from_raw((*alignment).query)
-                    .into_string()
-                    .map_err(Error::InvalidUTF8String)?;
-                let comparison_traceback = CString::from_raw((*alignment).comp)
-                    .into_string()
-                    .map_err(Error::InvalidUTF8String)?;
-                let reference_traceback = CString::from_raw((*alignment).ref_)
-                    .into_string()
-                    .map_err(Error::InvalidUTF8String)?;
-
-                Ok(Traceback {
-                    query: query_traceback.clone(),
-                    comparison: comparison_traceback.clone(),
-                    reference: reference_traceback.clone(),
-                })
-            }
+            self.traceback_with(query, reference, b'|', b' ', b' ')
         } else {
             Err(Error::NoTrace(String::from("get_traceback_strings()")))?
         }
     }
 
+    /// Get alignment strings with the given comparison symbols for matches,
+    /// similar residues (positive scores) and mismatches. Gap columns use a
+    /// space.
+    pub(crate) fn traceback_with(
+        &self,
+        query: &[u8],
+        reference: &[u8],
+        match_symbol: u8,
+        similar_symbol: u8,
+        mismatch_symbol: u8,
+    ) -> Result<Traceback> {
+        let query_len = query.len() as i32;
+        let ref_len = reference.len() as i32;
+        let query = CString::new(query).map_err(Error::InteriorNulByte)?;
+        let reference = CString::new(reference).map_err(Error::InteriorNulByte)?;
+        unsafe {
+            let alignment = parasail_result_get_traceback(
+                self.inner,
+                query.as_ptr(),
+                query_len,
+                reference.as_ptr(),
+                ref_len,
+                **self.matrix,
+                match_symbol as c_char,
+                similar_symbol as c_char,
+                mismatch_symbol as c_char,
+            );
+
+            let traceback = Traceback {
+                query: CStr::from_ptr((*alignment).query)
+                    .to_string_lossy()
+                    .into_owned(),
+                comparison: CStr::from_ptr((*alignment).comp)
+                    .to_string_lossy()
+                    .into_owned(),
+                reference: CStr::from_ptr((*alignment).ref_)
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+            parasail_traceback_free(alignment);
+
+            Ok(traceback)
+        }
+    }
+
     /// Get CIGAR string.
     pub fn get_cigar(&self, query: &[u8], reference: &[u8]) -> Result<String> {
         if self.is_trace() {
@@ -403,7 +459,7 @@ impl Alignment {
                         query_len,
                         reference.as_ptr(),
                         ref_len,
-                        self.matrix,
+                        **self.matrix,
                     ),
                 };
 
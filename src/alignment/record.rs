@@ -0,0 +1,104 @@
+//! Owned alignment records.
+
+use libparasail_sys::{
+    parasail_cigar_decode, parasail_result_get_cigar, parasail_result_get_length,
+    parasail_result_get_matches, parasail_result_get_similar,
+};
+use std::ffi::{CStr, CString};
+
+use super::{Alignment, CigarString, Error};
+use crate::prelude::Result;
+
+/// Owned snapshot of an alignment result that can outlive the `Alignment` and
+/// be serialized (with the `serde` feature), e.g. to send results as JSON.
+///
+/// Coordinates are 0-based and inclusive, as in `Alignment`. Start
+/// coordinates and the CIGAR string are only available if traceback was
+/// enabled, and statistics only if stats were enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlignmentRecord {
+    pub score: i32,
+    pub query_len: i32,
+    pub ref_len: i32,
+    pub query_start: Option<i32>,
+    pub query_end: i32,
+    pub ref_start: Option<i32>,
+    pub ref_end: i32,
+    pub cigar: Option<String>,
+    pub matches: Option<i32>,
+    pub similar: Option<i32>,
+    pub length: Option<i32>,
+}
+
+impl Alignment {
+    /// Create an owned record of the alignment. The query and reference
+    /// sequences are needed to compute the CIGAR string when traceback is
+    /// enabled.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::Aligner;
+    ///
+    /// let query = b"ACGTACGT";
+    /// let reference = b"ACGAACGT";
+    /// let aligner = Aligner::new().gap_open(5).gap_extend(1).use_trace().build();
+    /// let record = aligner.align(Some(query), reference)?.record(query, reference)?;
+    /// assert_eq!(record.cigar.as_deref(), Some("3=1X4="));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn record(&self, query: &[u8], reference: &[u8]) -> Result<AlignmentRecord> {
+        let mut record = AlignmentRecord {
+            score: self.get_score(),
+            query_len: self.query_len,
+            ref_len: self.ref_len,
+            query_start: None,
+            query_end: self.get_end_query(),
+            ref_start: None,
+            ref_end: self.get_end_ref(),
+            cigar: None,
+            matches: None,
+            similar: None,
+            length: None,
+        };
+
+        if self.is_stats() {
+            unsafe {
+                record.matches = Some(parasail_result_get_matches(self.inner));
+                record.similar = Some(parasail_result_get_similar(self.inner));
+                record.length = Some(parasail_result_get_length(self.inner));
+            }
+        }
+
+        if self.is_trace() {
+            let query_len = query.len() as i32;
+            let query = CString::new(query).map_err(Error::InteriorNulByte)?;
+            let ref_len = reference.len() as i32;
+            let reference = CString::new(reference).map_err(Error::InteriorNulByte)?;
+
+            unsafe {
+                let inner = parasail_result_get_cigar(
+                    self.inner,
+                    query.as_ptr(),
+                    query_len,
+                    reference.as_ptr(),
+                    ref_len,
+                    **self.matrix,
+                );
+                if inner.is_null() {
+                    return Err(Error::NoTrace(String::from("record()")).into());
+                }
+                let cigar = CigarString { inner };
+
+                record.query_start = Some((*cigar.inner).beg_query);
+                record.ref_start = Some((*cigar.inner).beg_ref);
+                // copy the decoded string, which was allocated by parasail
+                let decoded = parasail_cigar_decode(cigar.inner);
+                let owned = CString::from(CStr::from_ptr(decoded));
+                libc::free(decoded as *mut libc::c_void);
+                record.cigar = Some(owned.into_string().map_err(Error::InvalidUTF8String)?);
+            }
+        }
+
+        Ok(record)
+    }
+}
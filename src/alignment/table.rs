@@ -1,5 +1,11 @@
 use bitflags::bitflags;
+use std::borrow::Cow;
 use std::fmt::{self, Display};
+use std::io::Write;
+use std::slice;
+
+use super::Error;
+use crate::prelude::Result;
 
 /// A view into a score table from an alignment result.
 ///
@@ -31,7 +37,7 @@ use std::fmt::{self, Display};
 /// ```
 #[derive(Debug)]
 pub struct Table<'a> {
-    inner: &'a [i32],
+    inner: Cow<'a, [i32]>,
     rows: usize,
     cols: usize,
 }
@@ -52,7 +58,7 @@ impl<'a> Table<'a> {
             data.len()
         );
         Self {
-            inner: data,
+            inner: Cow::Borrowed(data),
             rows,
             cols,
         }
@@ -98,13 +104,145 @@ impl<'a> Table<'a> {
     /// The data is stored in row-major order, so element at (row, col)
     /// can be accessed at index `row * cols + col`.
     pub fn as_slice(&self) -> &[i32] {
-        self.inner
+        &self.inner
     }
 
     /// Get the value at the last cell (bottom-right of DP table).
     pub fn last(&self) -> i32 {
         self.inner[self.inner.len() - 1]
     }
+
+    /// Copy the table so that it no longer borrows from the alignment result
+    /// (e.g., to keep it after the result is dropped or send it to another
+    /// thread).
+    pub fn to_owned(&self) -> Table<'static> {
+        Table {
+            inner: Cow::Owned(self.inner.to_vec()),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+
+    /// Get a row (query position) of the table.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn row(&self, row: usize) -> Option<&[i32]> {
+        if row < self.rows {
+            Some(&self.inner[row * self.cols..(row + 1) * self.cols])
+        } else {
+            None
+        }
+    }
+
+    /// Get a column (reference position) of the table.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn col(&self, col: usize) -> Option<Vec<i32>> {
+        if col < self.cols {
+            Some(
+                self.inner[col..]
+                    .iter()
+                    .step_by(self.cols)
+                    .copied()
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Get an iterator over the rows of the table.
+    pub fn iter_rows(&self) -> slice::ChunksExact<'_, i32> {
+        self.inner.chunks_exact(self.cols.max(1))
+    }
+
+    /// Get the row and column index of the highest value in the table (the
+    /// first one in row-major order if there are ties).
+    ///
+    /// Returns `None` if the table is empty.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Aligner;
+    /// # let query = b"ACGT";
+    /// # let reference = b"TTACGTTT";
+    /// # let aligner = Aligner::new().local().use_table().build();
+    /// # let result = aligner.align(Some(query), reference)?;
+    /// let table = result.get_score_table()?;
+    /// if let Some((row, col)) = table.argmax() {
+    ///     println!("Best local score ends at ({row}, {col})");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn argmax(&self) -> Option<(usize, usize)> {
+        let (index, _) = self
+            .inner
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, value)| **value)?;
+        Some((index / self.cols, index % self.cols))
+    }
+
+    /// Write the table as comma-separated values, one line per row.
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<()> {
+        write_csv(&self.inner, self.cols, writer)
+    }
+
+    /// Write the table as a NumPy `.npy` file (32-bit little-endian integers
+    /// with shape `(rows, cols)`), e.g. for plotting with `numpy.load`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Aligner;
+    /// # let query = b"ACGT";
+    /// # let reference = b"ACGT";
+    /// # let aligner = Aligner::new().use_table().build();
+    /// # let result = aligner.align(Some(query), reference)?;
+    /// let table = result.get_score_table()?;
+    /// let file = std::fs::File::create("scores.npy")?;
+    /// table.write_npy(file)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn write_npy<W: Write>(&self, writer: W) -> Result<()> {
+        let data: Vec<u8> = self.inner.iter().flat_map(|v| v.to_le_bytes()).collect();
+        write_npy("<i4", self.rows, self.cols, &data, writer)
+    }
+}
+
+/// Write row-major values as CSV.
+fn write_csv<T: Display, W: Write>(data: &[T], cols: usize, mut writer: W) -> Result<()> {
+    for row in data.chunks_exact(cols.max(1)) {
+        let line: Vec<String> = row.iter().map(T::to_string).collect();
+        writeln!(writer, "{}", line.join(",")).map_err(Error::Io)?;
+    }
+    Ok(())
+}
+
+/// Write a 2D array in the NumPy `.npy` format (version 1.0).
+fn write_npy<W: Write>(
+    descr: &str,
+    rows: usize,
+    cols: usize,
+    data: &[u8],
+    mut writer: W,
+) -> Result<()> {
+    let mut header =
+        format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': ({rows}, {cols}), }}");
+    // magic (6) + version (2) + header length (2) + header, padded to a
+    // multiple of 64 bytes and terminated by a newline
+    let padding = 63 - (10 + header.len()) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    let mut write = || -> std::io::Result<()> {
+        writer.write_all(b"\x93NUMPY\x01\x00")?;
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+        writer.write_all(data)
+    };
+    write().map_err(Error::Io)?;
+    Ok(())
 }
 
 impl fmt::Display for Table<'_> {
@@ -169,6 +307,28 @@ impl Display for TraceFlags {
     }
 }
 
+/// Operation of a step in a traceback path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TraceOp {
+    /// Query and reference residues are aligned (match or mismatch).
+    Diag,
+    /// Reference residue is aligned to a gap in the query (CIGAR `D`).
+    Ins,
+    /// Query residue is aligned to a gap in the reference (CIGAR `I`).
+    Del,
+}
+
+/// Step in a traceback path. `row` and `col` are the query and reference
+/// positions of the cell the step was taken from; `Diag` steps consume both
+/// residues, `Ins` only the reference residue and `Del` only the query
+/// residue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceStep {
+    pub row: usize,
+    pub col: usize,
+    pub op: TraceOp,
+}
+
 /// A view into a tracback table from an alignment result.
 ///
 /// # Layout
@@ -195,7 +355,7 @@ impl Display for TraceFlags {
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 pub struct TracebackTable<'a> {
-    inner: &'a [i8],
+    inner: Cow<'a, [i8]>,
     rows: usize,
     cols: usize,
 }
@@ -205,7 +365,8 @@ impl<'a> TracebackTable<'a> {
     ///
     /// # Panics
     /// Panics in debug mode if data.len() != rows * cols
-    pub(crate) fn new(data: &'a [i8], rows: usize, cols: usize) -> Self {
+    pub(crate) fn new(data: impl Into<Cow<'a, [i8]>>, rows: usize, cols: usize) -> Self {
+        let data = data.into();
         debug_assert_eq!(
             data.len(),
             rows * cols,
@@ -295,7 +456,153 @@ impl<'a> TracebackTable<'a> {
     /// The data is stored in row-major order, so element at (row, col)
     /// can be accessed at index `row * cols + col`.
     pub fn as_slice(&self) -> &[i8] {
-        self.inner
+        &self.inner
+    }
+
+    /// Copy the table so that it no longer borrows from the alignment result
+    /// (e.g., to keep it after the result is dropped or send it to another
+    /// thread).
+    pub fn to_owned(&self) -> TracebackTable<'static> {
+        TracebackTable {
+            inner: Cow::Owned(self.inner.to_vec()),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+
+    /// Get the raw traceback flags of a row (query position) of the table.
+    /// See `TraceFlags::from_bits_truncate` for converting them to flags.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn row(&self, row: usize) -> Option<&[i8]> {
+        if row < self.rows {
+            Some(&self.inner[row * self.cols..(row + 1) * self.cols])
+        } else {
+            None
+        }
+    }
+
+    /// Get the raw traceback flags of a column (reference position) of the
+    /// table.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn col(&self, col: usize) -> Option<Vec<i8>> {
+        if col < self.cols {
+            Some(
+                self.inner[col..]
+                    .iter()
+                    .step_by(self.cols)
+                    .copied()
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Get an iterator over the rows of raw traceback flags.
+    pub fn iter_rows(&self) -> slice::ChunksExact<'_, i8> {
+        self.inner.chunks_exact(self.cols.max(1))
+    }
+
+    /// Follow the traceback from the cell at `end_row` and `end_col` (e.g., the
+    /// alignment end coordinates) and return the steps of the path in forward
+    /// order.
+    ///
+    /// The path follows the same state machine as parasail's traceback: in
+    /// the H state, the `DIAG`, `INS` and `DEL` flags select a diagonal step or
+    /// a switch to the E (insertion) or F (deletion) state, and the E and F
+    /// states take gap steps until `DIAG_E` or `DIAG_F` closes the gap. The
+    /// path stops at the first row or column of the table, or at a cell
+    /// without flags (e.g., the start of a local alignment). Leading gaps of
+    /// global and semi-global alignments are not part of the path; they span
+    /// the rows and columns before the first step. Trailing end gaps of
+    /// semi-global alignments are likewise left out.
+    ///
+    /// Parasail stores a single flag per state, so tied paths are not recorded
+    /// in the table. See `Aligner::tied_paths` for enumerating them.
+    ///
+    /// Returns `None` if the indices are out of bounds.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::{Aligner, TraceOp};
+    ///
+    /// let query = b"ACGTACGT";
+    /// let reference = b"ACGACGT";
+    /// let aligner = Aligner::new().gap_open(5).gap_extend(1).use_trace().build();
+    /// let result = aligner.align(Some(query), reference)?;
+    /// let table = result.get_trace_table()?;
+    /// let end_row = result.get_end_query() as usize;
+    /// let end_col = result.get_end_ref() as usize;
+    /// let path = table.path(end_row, end_col).unwrap();
+    /// assert_eq!(path.iter().filter(|step| step.op == TraceOp::Del).count(), 1);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn path(&self, end_row: usize, end_col: usize) -> Option<Vec<TraceStep>> {
+        if end_row >= self.rows || end_col >= self.cols {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        let (mut row, mut col) = (end_row as isize, end_col as isize);
+        let mut state = TraceOp::Diag;
+        while row >= 0 && col >= 0 {
+            let flags = self.get_detailed(row as usize, col as usize)?;
+            let step = TraceStep {
+                row: row as usize,
+                col: col as usize,
+                op: state,
+            };
+            match state {
+                TraceOp::Diag => {
+                    if flags.contains(TraceFlags::DIAG) {
+                        path.push(step);
+                        row -= 1;
+                        col -= 1;
+                    } else if flags.contains(TraceFlags::INS) {
+                        state = TraceOp::Ins;
+                    } else if flags.contains(TraceFlags::DEL) {
+                        state = TraceOp::Del;
+                    } else {
+                        break;
+                    }
+                }
+                TraceOp::Ins => {
+                    path.push(step);
+                    col -= 1;
+                    if flags.contains(TraceFlags::DIAG_E) {
+                        state = TraceOp::Diag;
+                    } else if !flags.contains(TraceFlags::INS_E) {
+                        break;
+                    }
+                }
+                TraceOp::Del => {
+                    path.push(step);
+                    row -= 1;
+                    if flags.contains(TraceFlags::DIAG_F) {
+                        state = TraceOp::Diag;
+                    } else if !flags.contains(TraceFlags::DEL_F) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
+    /// Write the raw traceback flags as comma-separated values, one line per
+    /// row.
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<()> {
+        write_csv(&self.inner, self.cols, writer)
+    }
+
+    /// Write the raw traceback flags as a NumPy `.npy` file (8-bit integers
+    /// with shape `(rows, cols)`).
+    pub fn write_npy<W: Write>(&self, writer: W) -> Result<()> {
+        let data: Vec<u8> = self.inner.iter().map(|v| *v as u8).collect();
+        write_npy("|i1", self.rows, self.cols, &data, writer)
     }
 }
 
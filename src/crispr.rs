@@ -0,0 +1,355 @@
+//! CRISPR guide off-target search, with mismatches and DNA/RNA bulges.
+//!
+//! Guides are aligned globally against the reference with free end gaps in
+//! the reference, with unit mismatch costs and bulges as gaps (a DNA bulge is
+//! an extra base in the reference, a RNA bulge an extra base in the guide).
+//! The last row of the score table gives the best alignment of the guide
+//! ending at each position, so candidates are the positions directly
+//! followed by a PAM, and are then aligned with traceback and filtered on
+//! their numbers of mismatches and bulges (if the cheapest alignment of a
+//! site has too many bulges, its best alignment without bulges is used
+//! instead). Both strands are searched, and references are processed in
+//! overlapping chunks.
+
+use std::borrow::Cow;
+use std::io::{self, BufRead, Write};
+
+use crate::alignment::Error;
+use crate::fastx::FastaReader;
+use crate::prelude::{Aligner, CigarOp, Matrix, Result};
+use crate::sequence::{iupac_matches, reverse_complement, Strand};
+
+/// Candidate off-target site of a guide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OffTarget {
+    /// Name of the reference sequence.
+    pub chrom: String,
+    /// Start position (0-based) of the site, including the PAM.
+    pub start: usize,
+    /// End position (0-based, exclusive) of the site, including the PAM.
+    pub end: usize,
+    pub strand: Strand,
+    /// Guide aligned to the site, with `-` for DNA bulges.
+    pub guide: String,
+    /// Protospacer of the site in the orientation of the guide, with
+    /// mismatches in lowercase and `-` for RNA bulges.
+    pub target: String,
+    /// PAM of the site in the orientation of the guide.
+    pub pam: String,
+    /// Positions (1-based, from the 5' end) of mismatched guide bases.
+    pub mismatches: Vec<usize>,
+    /// Number of extra bases in the reference.
+    pub dna_bulges: usize,
+    /// Number of extra bases in the guide.
+    pub rna_bulges: usize,
+}
+
+impl OffTarget {
+    /// Total number of bulged bases.
+    pub fn bulges(&self) -> usize {
+        self.dna_bulges + self.rna_bulges
+    }
+}
+
+/// Off-target search for a guide.
+///
+/// ```rust,no_run
+/// use parasail_rs::crispr::OffTargetSearch;
+/// use parasail_rs::sequence::Strand;
+///
+/// let reference = b"TTTTGACGCATAAAGATGAGACGCTGGTTTTTTTTCCTTCGTCTCATCTTTATGCGTCTTTT";
+/// let mut search = OffTargetSearch::new(b"GACGCATAAAGATGAGACGC");
+/// search.max_mismatches(2).max_bulges(0);
+///
+/// let off_targets = search.search("chr1", reference)?;
+/// assert_eq!(off_targets.len(), 2);
+/// assert_eq!((off_targets[0].start, off_targets[0].strand), (4, Strand::Forward));
+/// assert_eq!(off_targets[1].mismatches, [20]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct OffTargetSearch {
+    guide: Vec<u8>,
+    pam: Vec<u8>,
+    max_mismatches: usize,
+    max_bulges: usize,
+    chunk_size: usize,
+    matrix: Matrix,
+}
+
+impl OffTargetSearch {
+    /// Create a search for a guide (without its PAM, DNA or RNA), with a
+    /// `NGG` PAM, at most 4 mismatches and 1 bulged base, and chunks of 1 Mb.
+    pub fn new(guide: &[u8]) -> Self {
+        let mut matrix = Matrix::create(b"ACGTN", 0, -1).unwrap();
+        // N is a mismatch, even against N
+        matrix.set_value(4, 4, -1).unwrap();
+
+        OffTargetSearch {
+            guide: guide
+                .iter()
+                .map(|base| match base.to_ascii_uppercase() {
+                    b'U' => b'T',
+                    base => base,
+                })
+                .collect(),
+            pam: b"NGG".to_vec(),
+            max_mismatches: 4,
+            max_bulges: 1,
+            chunk_size: 1_000_000,
+            matrix,
+        }
+    }
+
+    /// Set the PAM following the protospacer, with IUPAC codes (e.g. `NGG`
+    /// for SpCas9 or `NNGRRT` for SaCas9).
+    pub fn pam(&mut self, pam: &[u8]) -> &mut Self {
+        assert!(!pam.is_empty(), "PAM should not be empty.");
+        self.pam = pam.to_ascii_uppercase();
+        self
+    }
+
+    /// Set the maximum number of mismatches.
+    pub fn max_mismatches(&mut self, max_mismatches: usize) -> &mut Self {
+        self.max_mismatches = max_mismatches;
+        self
+    }
+
+    /// Set the maximum number of bulged bases (DNA and RNA).
+    pub fn max_bulges(&mut self, max_bulges: usize) -> &mut Self {
+        self.max_bulges = max_bulges;
+        self
+    }
+
+    /// Set the length of the chunks references are processed in.
+    pub fn chunk_size(&mut self, chunk_size: usize) -> &mut Self {
+        assert!(chunk_size > 0, "Chunk size should be positive.");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Search a reference sequence on both strands. Candidates are sorted by
+    /// position.
+    pub fn search(&self, chrom: &str, reference: &[u8]) -> Result<Vec<OffTarget>> {
+        // bulges cost more than all allowed mismatches if none are allowed
+        let no_bulges = self.max_mismatches as i32 + 1;
+        let (scanner, tracer) = match self.max_bulges {
+            0 => self.aligners(no_bulges, no_bulges),
+            _ => self.aligners(2, 1),
+        };
+        // the cheapest alignment of a site may have too many bulges, while
+        // the best one without bulges is within the limits
+        let mut tracers = vec![tracer];
+        if self.max_bulges > 0 {
+            tracers.push(self.aligners(no_bulges, no_bulges).1);
+        }
+
+        // longest site, which chunks overlap by on both sides
+        let margin = self.guide.len() + self.max_bulges + self.pam.len();
+        let mut off_targets = Vec::new();
+
+        for chunk_start in (0..reference.len()).step_by(self.chunk_size) {
+            let owned = chunk_start..(chunk_start + self.chunk_size).min(reference.len());
+            let offset = owned.start.saturating_sub(margin);
+            let chunk =
+                reference[offset..(owned.end + margin).min(reference.len())].to_ascii_uppercase();
+
+            for strand in [Strand::Forward, Strand::Reverse] {
+                let text = match strand {
+                    Strand::Forward => Cow::Borrowed(&chunk[..]),
+                    Strand::Reverse => Cow::Owned(reverse_complement(&chunk)),
+                };
+                for mut off_target in self.scan(&scanner, &tracers, &text)? {
+                    // PAM start and site in forward reference coordinates
+                    let pam_start = match strand {
+                        Strand::Forward => offset + off_target.end - self.pam.len(),
+                        Strand::Reverse => offset + chunk.len() - off_target.end,
+                    };
+                    if !owned.contains(&pam_start) {
+                        continue;
+                    }
+                    if strand == Strand::Reverse {
+                        (off_target.start, off_target.end) =
+                            (chunk.len() - off_target.end, chunk.len() - off_target.start);
+                    }
+                    off_target.start += offset;
+                    off_target.end += offset;
+                    off_target.chrom = String::from(chrom);
+                    off_target.strand = strand;
+                    off_targets.push(off_target);
+                }
+            }
+        }
+
+        off_targets
+            .sort_by_key(|off_target| (off_target.start, off_target.strand == Strand::Reverse));
+        Ok(off_targets)
+    }
+
+    /// Search all sequences of a FASTA reference, named after their headers
+    /// up to the first whitespace.
+    pub fn search_fasta<R: BufRead>(&self, reader: R) -> Result<Vec<OffTarget>> {
+        let mut off_targets = Vec::new();
+        for record in FastaReader::new(reader) {
+            let record = record.map_err(Error::Io)?;
+            off_targets.extend(self.search(record.name(), &record.sequence)?);
+        }
+        Ok(off_targets)
+    }
+
+    /// Aligners of the guide scanning the reference for the best alignment
+    /// ending at each position, and aligning candidates with traceback.
+    fn aligners(&self, gap_open: i32, gap_extend: i32) -> (Aligner, Aligner) {
+        let mut builder = Aligner::new();
+        builder
+            .semi_global()
+            .matrix(self.matrix.clone())
+            .gap_open(gap_open)
+            .gap_extend(gap_extend)
+            .allow_query_gaps(Vec::new());
+        let scanner = builder
+            .allow_ref_gaps(vec![String::from("prefix"), String::from("suffix")])
+            .use_last_rowcol()
+            .build();
+        let tracer = builder
+            .allow_ref_gaps(vec![String::from("prefix")])
+            .use_trace()
+            .build();
+        (scanner, tracer)
+    }
+
+    /// Find candidates on the forward strand of a sequence, with coordinates
+    /// in the sequence, aligned by the first tracer giving an alignment within
+    /// the limits.
+    fn scan(&self, scanner: &Aligner, tracers: &[Aligner], text: &[u8]) -> Result<Vec<OffTarget>> {
+        let mut off_targets = Vec::new();
+        if text.len() < self.guide.len() + self.pam.len() {
+            return Ok(off_targets);
+        }
+        let min_score = -((self.max_mismatches + 2 * self.max_bulges) as i32);
+        let alignment = scanner.align(Some(&self.guide), text)?;
+        let row = alignment.get_score_row()?;
+
+        for pam_start in 1..=text.len() - self.pam.len() {
+            let pam = &text[pam_start..pam_start + self.pam.len()];
+            if row[pam_start - 1] < min_score
+                || !self
+                    .pam
+                    .iter()
+                    .zip(pam)
+                    .all(|(&code, &base)| iupac_matches(code, base))
+            {
+                continue;
+            }
+
+            for tracer in tracers {
+                let off_target = self.align_site(tracer, text, pam_start)?;
+                if off_target.mismatches.len() <= self.max_mismatches
+                    && off_target.bulges() <= self.max_bulges
+                {
+                    off_targets.push(off_target);
+                    break;
+                }
+            }
+        }
+        Ok(off_targets)
+    }
+
+    /// Align the guide with traceback against the sequence before a PAM.
+    fn align_site(&self, tracer: &Aligner, text: &[u8], pam_start: usize) -> Result<OffTarget> {
+        let window_start = pam_start.saturating_sub(self.guide.len() + self.max_bulges);
+        let window = &text[window_start..pam_start];
+        let alignment = tracer.align(Some(&self.guide), window)?;
+        let cigar = alignment.cigar(&self.guide, window)?;
+        let mut ops = cigar.ops();
+        let mut start = window_start + cigar.ref_start;
+        while let Some((&(CigarOp::Del, len), rest)) = ops.split_first() {
+            start += len;
+            ops = rest;
+        }
+
+        let (mut guide, mut target, mut mismatches) = (String::new(), String::new(), Vec::new());
+        let (mut dna_bulges, mut rna_bulges) = (0, 0);
+        let (mut i, mut j) = (cigar.query_start, start);
+        for &(op, len) in ops {
+            for _ in 0..len {
+                match op {
+                    CigarOp::Ins => {
+                        guide.push(self.guide[i] as char);
+                        target.push('-');
+                        rna_bulges += 1;
+                    }
+                    CigarOp::Del => {
+                        guide.push('-');
+                        target.push(text[j] as char);
+                        dna_bulges += 1;
+                    }
+                    CigarOp::Diff => {
+                        guide.push(self.guide[i] as char);
+                        target.push(text[j].to_ascii_lowercase() as char);
+                        mismatches.push(i + 1);
+                    }
+                    _ => {
+                        guide.push(self.guide[i] as char);
+                        target.push(text[j] as char);
+                    }
+                }
+                i += op.consumes_query() as usize;
+                j += op.consumes_ref() as usize;
+            }
+        }
+        let end = pam_start + self.pam.len();
+        Ok(OffTarget {
+            chrom: String::new(),
+            start,
+            end,
+            strand: Strand::Forward,
+            guide,
+            target,
+            pam: String::from_utf8_lossy(&text[pam_start..end]).into_owned(),
+            mismatches,
+            dna_bulges,
+            rna_bulges,
+        })
+    }
+}
+
+/// Write off-target candidates as a tab-separated table with a header, one
+/// row per candidate, with the aligned guide, protospacer and PAM used by
+/// CFD scoring. Start positions are 0-based, and mismatch positions are
+/// comma-separated (`.` if none).
+pub fn write_table<W: Write>(off_targets: &[OffTarget], mut writer: W) -> io::Result<()> {
+    writeln!(
+        writer,
+        "chrom\tstart\tend\tstrand\tguide\ttarget\tpam\tmismatches\tmismatch_positions\tdna_bulges\trna_bulges"
+    )?;
+    for off_target in off_targets {
+        let positions = if off_target.mismatches.is_empty() {
+            String::from(".")
+        } else {
+            off_target
+                .mismatches
+                .iter()
+                .map(|position| position.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            off_target.chrom,
+            off_target.start,
+            off_target.end,
+            off_target.strand,
+            off_target.guide,
+            off_target.target,
+            off_target.pam,
+            off_target.mismatches.len(),
+            positions,
+            off_target.dna_bulges,
+            off_target.rna_bulges
+        )?;
+    }
+    Ok(())
+}
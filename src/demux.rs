@@ -0,0 +1,447 @@
+//! Demultiplexing of reads by inline barcodes.
+//!
+//! Barcodes at the start of reads are matched by semi-global alignment of
+//! each barcode against the read prefix, with a free end gap after the
+//! barcode in the read, and unit costs for substitutions and indels so that
+//! alignment scores are edit distances. Reads are assigned to the sample
+//! with the closest barcode (or pair of barcodes, for dual indexes on both
+//! reads of a pair) if its distance is within a maximum and far enough from
+//! the next closest sample.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use crate::alignment::Error;
+use crate::fastx::FastqRecord;
+use crate::prelude::{Aligner, Matrix, Result};
+
+/// Sample with its barcode, and second barcode for dual indexes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub name: String,
+    pub barcode: Vec<u8>,
+    pub barcode2: Option<Vec<u8>>,
+}
+
+/// Samples to demultiplex reads into.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BarcodeSheet {
+    samples: Vec<Sample>,
+}
+
+impl BarcodeSheet {
+    /// Create an empty barcode sheet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a barcode sheet with one sample per line: its name, barcode and
+    /// optional second barcode, separated by tabs or commas. Empty lines and
+    /// lines starting with `#` are skipped.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut sheet = BarcodeSheet::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields = line.split(['\t', ',']).map(str::trim).collect::<Vec<_>>();
+            let (name, barcode, barcode2) = match fields[..] {
+                [name, barcode] => (name, barcode, None),
+                [name, barcode, barcode2] => (name, barcode, Some(barcode2.as_bytes())),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Barcode sheet line should have 2 or 3 fields: {line}"),
+                    ))
+                }
+            };
+            if barcode.is_empty() || barcode2.is_some_and(<[u8]>::is_empty) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Barcodes should not be empty: {line}"),
+                ));
+            }
+            if sheet
+                .samples
+                .first()
+                .is_some_and(|first| first.barcode2.is_some() != barcode2.is_some())
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "All samples should have one barcode, or all should have two.",
+                ));
+            }
+            sheet.add(name, barcode.as_bytes(), barcode2);
+        }
+        Ok(sheet)
+    }
+
+    /// Add a sample.
+    ///
+    /// # Panics
+    /// Panics if a barcode is empty, or if the sample has a second barcode and
+    /// others do not, or the other way around.
+    pub fn add(&mut self, name: &str, barcode: &[u8], barcode2: Option<&[u8]>) -> &mut Self {
+        assert!(
+            !barcode.is_empty() && !barcode2.is_some_and(<[u8]>::is_empty),
+            "Barcodes should not be empty."
+        );
+        assert!(
+            self.samples
+                .first()
+                .is_none_or(|first| first.barcode2.is_some() == barcode2.is_some()),
+            "All samples should have one barcode, or all should have two."
+        );
+        self.samples.push(Sample {
+            name: String::from(name),
+            barcode: barcode.to_ascii_uppercase(),
+            barcode2: barcode2.map(|barcode| barcode.to_ascii_uppercase()),
+        });
+        self
+    }
+
+    /// Get the samples.
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// Check if samples have dual indexes.
+    pub fn is_dual(&self) -> bool {
+        self.samples.iter().any(|sample| sample.barcode2.is_some())
+    }
+}
+
+/// Result of the assignment of a read (or pair) to a sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assignment {
+    /// Read assigned to the sample at this index of the barcode sheet, with
+    /// the edit distance of its barcodes (summed for dual indexes) and the
+    /// length of the barcodes in the reads.
+    Assigned {
+        sample: usize,
+        distance: usize,
+        barcode_lens: [usize; 2],
+    },
+    /// Closest sample within the maximum distance, but not by the minimum
+    /// margin.
+    Ambiguous { distance: usize },
+    /// No sample within the maximum distance.
+    Unassigned,
+}
+
+/// Demultiplexing summary.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DemuxStats {
+    /// Number of reads (or pairs).
+    pub total: usize,
+    /// Number of reads assigned to each sample.
+    pub assigned: Vec<usize>,
+    /// Number of reads assigned with exact barcode matches, per sample.
+    pub perfect: Vec<usize>,
+    /// Number of ambiguous reads (included in `unassigned`).
+    pub ambiguous: usize,
+    /// Number of reads written to the unassigned bin.
+    pub unassigned: usize,
+}
+
+impl DemuxStats {
+    /// Write the summary as a tab-separated table with a header: one row per
+    /// sample and one for unassigned reads, with read counts and fractions.
+    pub fn write<W: Write>(&self, sheet: &BarcodeSheet, mut writer: W) -> io::Result<()> {
+        let fraction = |count: usize| count as f64 / self.total.max(1) as f64;
+        writeln!(writer, "sample\treads\tperfect\tfraction")?;
+        for (k, sample) in sheet.samples().iter().enumerate() {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{:.4}",
+                sample.name,
+                self.assigned[k],
+                self.perfect[k],
+                fraction(self.assigned[k])
+            )?;
+        }
+        writeln!(
+            writer,
+            "unassigned\t{}\t0\t{:.4}",
+            self.unassigned,
+            fraction(self.unassigned)
+        )
+    }
+}
+
+/// Assigner of reads to samples by their inline barcodes.
+///
+/// ```rust,no_run
+/// use parasail_rs::demux::{Assignment, BarcodeSheet, Demultiplexer};
+/// use parasail_rs::fastx::FastqRecord;
+///
+/// let mut sheet = BarcodeSheet::new();
+/// sheet.add("s1", b"ACGTACGT", None).add("s2", b"TGCATGCA", None);
+/// let demux = Demultiplexer::new(sheet);
+///
+/// let read = FastqRecord::new("read1", b"ACGAACGTGGGCCC", b"IIIIIIIIIIIIII");
+/// let assignment = demux.assign(&read, None)?;
+/// assert!(matches!(assignment, Assignment::Assigned { sample: 0, distance: 1, .. }));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct Demultiplexer {
+    sheet: BarcodeSheet,
+    aligner: Aligner,
+    max_distance: usize,
+    min_margin: usize,
+    trim_barcodes: bool,
+}
+
+impl Demultiplexer {
+    /// Create a demultiplexer for the samples of a barcode sheet, allowing
+    /// one edit per barcode and a margin of one edit to the next closest
+    /// sample, and trimming barcodes from reads.
+    pub fn new(sheet: BarcodeSheet) -> Self {
+        let mut matrix = Matrix::create(b"ACGTN", 0, -1).unwrap();
+        // N is a mismatch, even against N
+        matrix.set_value(4, 4, -1).unwrap();
+        let aligner = Aligner::new()
+            .semi_global()
+            .allow_query_gaps(Vec::new())
+            .allow_ref_gaps(vec![String::from("suffix")])
+            .matrix(matrix)
+            .gap_open(1)
+            .gap_extend(1)
+            .build();
+
+        Demultiplexer {
+            sheet,
+            aligner,
+            max_distance: 1,
+            min_margin: 1,
+            trim_barcodes: true,
+        }
+    }
+
+    /// Set the maximum edit distance of each barcode.
+    pub fn max_distance(&mut self, max_distance: usize) -> &mut Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Set the minimum difference between the edit distances of the closest
+    /// and next closest samples.
+    pub fn min_margin(&mut self, min_margin: usize) -> &mut Self {
+        assert!(min_margin > 0, "Minimum margin should be positive.");
+        self.min_margin = min_margin;
+        self
+    }
+
+    /// Set whether to remove barcodes from assigned reads.
+    pub fn trim_barcodes(&mut self, trim_barcodes: bool) -> &mut Self {
+        self.trim_barcodes = trim_barcodes;
+        self
+    }
+
+    /// Get the barcode sheet.
+    pub fn sheet(&self) -> &BarcodeSheet {
+        &self.sheet
+    }
+
+    /// Edit distance of each distinct barcode to the start of a read, and
+    /// the barcode length in the read.
+    fn distances<'a>(
+        &self,
+        barcodes: impl Iterator<Item = &'a [u8]>,
+        read: &[u8],
+    ) -> Result<HashMap<&'a [u8], (usize, usize)>> {
+        let read = read.to_ascii_uppercase();
+        let mut distances = HashMap::new();
+        for barcode in barcodes {
+            if distances.contains_key(barcode) {
+                continue;
+            }
+            let prefix = &read[..read.len().min(barcode.len() + self.max_distance)];
+            if prefix.is_empty() {
+                distances.insert(barcode, (barcode.len(), 0));
+                continue;
+            }
+            let alignment = self.aligner.align(Some(barcode), prefix)?;
+            let distance = (-alignment.get_score()) as usize;
+            distances.insert(barcode, (distance, alignment.get_end_ref() as usize + 1));
+        }
+        Ok(distances)
+    }
+
+    /// Assign a read, or a pair of reads with the second barcode at the start
+    /// of the second read, to a sample.
+    ///
+    /// # Panics
+    /// Panics if samples have dual indexes and there is no second read.
+    pub fn assign(&self, read: &FastqRecord, read2: Option<&FastqRecord>) -> Result<Assignment> {
+        let samples = self.sheet.samples();
+        let distances = self.distances(samples.iter().map(|s| &s.barcode[..]), &read.sequence)?;
+        let distances2 = if self.sheet.is_dual() {
+            let read2 = read2.expect("A second read is required for dual index barcodes.");
+            self.distances(
+                samples.iter().filter_map(|s| s.barcode2.as_deref()),
+                &read2.sequence,
+            )?
+        } else {
+            HashMap::new()
+        };
+
+        let mut candidates = Vec::new();
+        for (k, sample) in samples.iter().enumerate() {
+            let (mut distance, len) = distances[&sample.barcode[..]];
+            if distance > self.max_distance {
+                continue;
+            }
+            let mut len2 = 0;
+            if let Some(barcode2) = &sample.barcode2 {
+                let (distance2, len) = distances2[&barcode2[..]];
+                if distance2 > self.max_distance {
+                    continue;
+                }
+                distance += distance2;
+                len2 = len;
+            }
+            candidates.push((distance, k, [len, len2]));
+        }
+        candidates.sort_unstable();
+
+        Ok(match candidates[..] {
+            [] => Assignment::Unassigned,
+            [(distance, ..), (next, ..), ..] if next < distance + self.min_margin => {
+                Assignment::Ambiguous { distance }
+            }
+            [(distance, sample, barcode_lens), ..] => Assignment::Assigned {
+                sample,
+                distance,
+                barcode_lens,
+            },
+        })
+    }
+
+    /// Demultiplex single-end reads into one writer per sample (in the order
+    /// of the barcode sheet) and a writer for unassigned reads.
+    ///
+    /// # Panics
+    /// Panics if there is not one writer per sample, or samples have dual
+    /// indexes.
+    pub fn demultiplex<I, W>(
+        &self,
+        reads: I,
+        writers: &mut [W],
+        unassigned: &mut W,
+    ) -> Result<DemuxStats>
+    where
+        I: IntoIterator<Item = io::Result<FastqRecord>>,
+        W: Write,
+    {
+        assert!(
+            !self.sheet.is_dual(),
+            "Dual index barcodes require paired reads."
+        );
+        self.run(
+            reads.into_iter().map(|read| Ok((read?, None))),
+            writers.len(),
+            |sample, read, _| match sample {
+                Some(sample) => read.write(&mut writers[sample]),
+                None => read.write(&mut *unassigned),
+            },
+        )
+    }
+
+    /// Demultiplex paired reads into one pair of writers per sample (in the
+    /// order of the barcode sheet) and a pair of writers for unassigned
+    /// reads. With single index barcodes, only the first read is matched.
+    /// Read files with different numbers of records are an error.
+    ///
+    /// # Panics
+    /// Panics if there is not one pair of writers per sample.
+    pub fn demultiplex_pairs<I, J, W>(
+        &self,
+        reads: I,
+        reads2: J,
+        writers: &mut [(W, W)],
+        unassigned: &mut (W, W),
+    ) -> Result<DemuxStats>
+    where
+        I: IntoIterator<Item = io::Result<FastqRecord>>,
+        J: IntoIterator<Item = io::Result<FastqRecord>>,
+        W: Write,
+    {
+        let (mut reads, mut reads2) = (reads.into_iter(), reads2.into_iter());
+        let pairs = std::iter::from_fn(move || match (reads.next(), reads2.next()) {
+            (None, None) => None,
+            (Some(read), Some(read2)) => Some(read.and_then(|read| Ok((read, Some(read2?))))),
+            _ => Some(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Paired read files should have the same number of records.",
+            ))),
+        });
+        self.run(pairs, writers.len(), |sample, read, read2| {
+            let (writer, writer2) = match sample {
+                Some(sample) => &mut writers[sample],
+                None => &mut *unassigned,
+            };
+            read.write(&mut *writer)?;
+            read2.map_or(Ok(()), |read2| read2.write(writer2))
+        })
+    }
+
+    fn run<I, F>(&self, reads: I, n_writers: usize, mut write: F) -> Result<DemuxStats>
+    where
+        I: Iterator<Item = io::Result<(FastqRecord, Option<FastqRecord>)>>,
+        F: FnMut(Option<usize>, &FastqRecord, Option<&FastqRecord>) -> io::Result<()>,
+    {
+        let n_samples = self.sheet.samples().len();
+        assert_eq!(
+            n_writers, n_samples,
+            "There should be one writer per sample."
+        );
+        let mut stats = DemuxStats {
+            assigned: vec![0; n_samples],
+            perfect: vec![0; n_samples],
+            ..Default::default()
+        };
+
+        for pair in reads {
+            let (mut read, mut read2) = pair.map_err(Error::Io)?;
+            stats.total += 1;
+            let sample = match self.assign(&read, read2.as_ref())? {
+                Assignment::Assigned {
+                    sample,
+                    distance,
+                    barcode_lens,
+                } => {
+                    stats.assigned[sample] += 1;
+                    stats.perfect[sample] += (distance == 0) as usize;
+                    if self.trim_barcodes {
+                        trim_start(&mut read, barcode_lens[0]);
+                        if let Some(read2) = &mut read2 {
+                            trim_start(read2, barcode_lens[1]);
+                        }
+                    }
+                    Some(sample)
+                }
+                Assignment::Ambiguous { .. } => {
+                    stats.ambiguous += 1;
+                    stats.unassigned += 1;
+                    None
+                }
+                Assignment::Unassigned => {
+                    stats.unassigned += 1;
+                    None
+                }
+            };
+            write(sample, &read, read2.as_ref()).map_err(Error::Io)?;
+        }
+        Ok(stats)
+    }
+}
+
+fn trim_start(read: &mut FastqRecord, len: usize) {
+    read.sequence.drain(..len);
+    read.qualities.drain(..len);
+}
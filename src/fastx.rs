@@ -0,0 +1,233 @@
+//! Reading FASTA and FASTQ records, and writing FASTQ records.
+
+use std::io::{self, BufRead, Write};
+
+/// FASTA record.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FastaRecord {
+    /// Header line without the leading `>` (name and description).
+    pub id: String,
+    pub sequence: Vec<u8>,
+}
+
+impl FastaRecord {
+    /// Name of the record, i.e. the header up to the first whitespace.
+    pub fn name(&self) -> &str {
+        self.id.split_whitespace().next().unwrap_or_default()
+    }
+}
+
+/// FASTQ record with Phred+33 encoded qualities.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FastqRecord {
+    /// Header line without the leading `@` (name and description).
+    pub id: String,
+    pub sequence: Vec<u8>,
+    pub qualities: Vec<u8>,
+}
+
+impl FastqRecord {
+    /// Create a new record.
+    ///
+    /// # Panics
+    /// Panics if there is not one quality per base.
+    pub fn new(id: &str, sequence: &[u8], qualities: &[u8]) -> Self {
+        assert_eq!(
+            sequence.len(),
+            qualities.len(),
+            "There should be one quality per base."
+        );
+        FastqRecord {
+            id: String::from(id),
+            sequence: sequence.to_vec(),
+            qualities: qualities.to_vec(),
+        }
+    }
+
+    /// Name of the record, i.e. the header up to the first whitespace.
+    pub fn name(&self) -> &str {
+        self.id.split_whitespace().next().unwrap_or_default()
+    }
+
+    /// Number of bases.
+    pub fn len(&self) -> usize {
+        self.sequence.len()
+    }
+
+    /// Check if the record has no bases.
+    pub fn is_empty(&self) -> bool {
+        self.sequence.is_empty()
+    }
+
+    /// Write the record in FASTQ format.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(b"@")?;
+        writer.write_all(self.id.as_bytes())?;
+        writer.write_all(b"\n")?;
+        writer.write_all(&self.sequence)?;
+        writer.write_all(b"\n+\n")?;
+        writer.write_all(&self.qualities)?;
+        writer.write_all(b"\n")
+    }
+}
+
+/// Iterator over the records of a FASTQ file with 4 lines per record.
+///
+/// ```rust,no_run
+/// use parasail_rs::fastx::FastqReader;
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let reader = FastqReader::new(BufReader::new(File::open("reads.fastq")?));
+/// for record in reader {
+///     let record = record?;
+///     println!("{}: {} bases", record.name(), record.len());
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct FastqReader<R> {
+    reader: R,
+    line: String,
+}
+
+impl<R: BufRead> FastqReader<R> {
+    /// Create a new reader.
+    pub fn new(reader: R) -> Self {
+        FastqReader {
+            reader,
+            line: String::new(),
+        }
+    }
+
+    /// Read the next line without its line ending, or `None` at the end of
+    /// the file.
+    fn next_line(&mut self) -> io::Result<Option<&str>> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.line.trim_end_matches(['\n', '\r'])))
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<FastqRecord>> {
+        let id = loop {
+            match self.next_line()? {
+                None => return Ok(None),
+                Some("") => continue,
+                Some(header) => match header.strip_prefix('@') {
+                    Some(id) => break String::from(id),
+                    None => return Err(invalid_data("FASTQ header should start with '@'.")),
+                },
+            }
+        };
+        let sequence = self
+            .next_line()?
+            .ok_or_else(|| invalid_data("FASTQ record is truncated."))?
+            .as_bytes()
+            .to_vec();
+        match self.next_line()? {
+            Some(separator) if separator.starts_with('+') => {}
+            _ => return Err(invalid_data("FASTQ separator should start with '+'.")),
+        }
+        let qualities = self
+            .next_line()?
+            .ok_or_else(|| invalid_data("FASTQ record is truncated."))?
+            .as_bytes()
+            .to_vec();
+        if qualities.len() != sequence.len() {
+            return Err(invalid_data(
+                "FASTQ sequence and qualities should have the same length.",
+            ));
+        }
+
+        Ok(Some(FastqRecord {
+            id,
+            sequence,
+            qualities,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for FastqReader<R> {
+    type Item = io::Result<FastqRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Iterator over the records of a FASTA file, with sequences possibly
+/// wrapped over several lines.
+///
+/// ```rust,no_run
+/// use parasail_rs::fastx::FastaReader;
+///
+/// let fasta = b">chr1 test\nACGT\nACGT\n>chr2\nTTTT\n";
+/// let records = FastaReader::new(&fasta[..]).collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(records[0].name(), "chr1");
+/// assert_eq!(records[0].sequence, b"ACGTACGT");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct FastaReader<R> {
+    reader: R,
+    line: String,
+    /// Header of the next record, read at the end of the previous one.
+    header: Option<String>,
+}
+
+impl<R: BufRead> FastaReader<R> {
+    /// Create a new reader.
+    pub fn new(reader: R) -> Self {
+        FastaReader {
+            reader,
+            line: String::new(),
+            header: None,
+        }
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<&str>> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.line.trim_end_matches(['\n', '\r'])))
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<FastaRecord>> {
+        let id = match self.header.take() {
+            Some(id) => id,
+            None => loop {
+                match self.next_line()? {
+                    None => return Ok(None),
+                    Some("") => continue,
+                    Some(header) => match header.strip_prefix('>') {
+                        Some(id) => break String::from(id),
+                        None => return Err(invalid_data("FASTA header should start with '>'.")),
+                    },
+                }
+            },
+        };
+
+        let mut sequence = Vec::new();
+        while let Some(line) = self.next_line()? {
+            if let Some(header) = line.strip_prefix('>') {
+                self.header = Some(String::from(header));
+                break;
+            }
+            sequence.extend_from_slice(line.trim().as_bytes());
+        }
+        Ok(Some(FastaRecord { id, sequence }))
+    }
+}
+
+impl<R: BufRead> Iterator for FastaReader<R> {
+    type Item = io::Result<FastaRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
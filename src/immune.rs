@@ -0,0 +1,660 @@
+//! V(D)J germline gene assignment of antibody and T cell receptor reads.
+//!
+//! Germline genes are read from IMGT-style FASTA files. Each read is aligned
+//! against all V genes with a profile of the read (and of its reverse
+//! complement, to find its orientation), reused across genes. J genes are
+//! then aligned against the rest of the read after the V gene, and D genes
+//! against the part of the read between the V and J genes. The junction
+//! spans from the conserved Cys codon of the V gene to the conserved Trp or
+//! Phe codon of the J gene, and assignments can be written in the AIRR
+//! rearrangement TSV format.
+
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+
+use crate::fastx::FastaReader;
+use crate::prelude::{Aligner, Cigar, CigarOp, Matrix, Profile, Result};
+use crate::sequence::{iupac_matches, reverse_complement, Strand};
+use crate::translate::GeneticCode;
+
+/// Match and mismatch scores, and gap penalties, for germline alignment.
+const MATCH: i32 = 5;
+const MISMATCH: i32 = -4;
+const GAP_OPEN: i32 = 12;
+const GAP_EXTEND: i32 = 2;
+
+/// Gapped IMGT position (0-based) of the first base of the 2nd-CYS codon of
+/// V genes (codon 104).
+const IMGT_CYS: usize = 309;
+
+/// Gene segment of a germline set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Segment {
+    V,
+    D,
+    J,
+}
+
+/// Germline gene (allele).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Germline {
+    /// Allele name, e.g. `IGHV1-2*02`.
+    pub name: String,
+    /// Ungapped sequence.
+    pub sequence: Vec<u8>,
+    /// Position (0-based) of the first base of the conserved codon bounding
+    /// the junction: the 2nd-CYS of V genes and the J-TRP or J-PHE of J
+    /// genes, if found.
+    pub anchor: Option<usize>,
+}
+
+impl Germline {
+    /// Create a germline gene from a sequence, possibly with IMGT gaps (`.`),
+    /// and find its anchor.
+    ///
+    /// The anchor of V genes is the codon at IMGT position 104 for gapped
+    /// sequences, and otherwise the Cys codon of their last `TAYTGY`
+    /// (Tyr-Cys) motif. The anchor of J genes is the first codon of their
+    /// first `TGGGGNNNNGGN` (Trp-Gly-X-Gly) or `TTYGGNNNNGGN` (Phe-Gly-X-Gly)
+    /// motif.
+    pub fn new(name: &str, sequence: &[u8], segment: Segment) -> Self {
+        let gapped = sequence.to_ascii_uppercase();
+        let sequence = gapped
+            .iter()
+            .copied()
+            .filter(|&base| base != b'.' && base != b'-')
+            .collect::<Vec<_>>();
+
+        let anchor = match segment {
+            Segment::V if gapped.len() > IMGT_CYS && gapped.contains(&b'.') => {
+                let ungapped = gapped[..IMGT_CYS].iter().filter(|&&base| base != b'.');
+                Some(ungapped.count())
+            }
+            Segment::V => find_motif(&sequence, b"TAYTGY").last().map(|k| k + 3),
+            Segment::D => None,
+            Segment::J => find_motif(&sequence, b"TGGGGNNNNGGN")
+                .chain(find_motif(&sequence, b"TTYGGNNNNGGN"))
+                .min(),
+        };
+
+        Germline {
+            name: String::from(name),
+            sequence,
+            anchor,
+        }
+    }
+}
+
+/// Positions of the matches of an IUPAC motif in a sequence.
+fn find_motif<'a>(sequence: &'a [u8], motif: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    sequence
+        .windows(motif.len())
+        .enumerate()
+        .filter_map(|(k, window)| {
+            motif
+                .iter()
+                .zip(window)
+                .all(|(&code, &base)| iupac_matches(code, base))
+                .then_some(k)
+        })
+}
+
+/// Germline genes of a segment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GermlineSet {
+    genes: Vec<Germline>,
+}
+
+impl GermlineSet {
+    /// Read germline genes of a segment from a FASTA file. Genes are named
+    /// after the second field of IMGT headers (separated by `|`), or the
+    /// header up to the first whitespace otherwise.
+    pub fn from_fasta<R: BufRead>(reader: R, segment: Segment) -> io::Result<Self> {
+        let mut set = GermlineSet::default();
+        for record in FastaReader::new(reader) {
+            let record = record?;
+            let name = match record.id.split('|').nth(1) {
+                Some(name) => name.trim(),
+                None => record.name(),
+            };
+            set.genes
+                .push(Germline::new(name, &record.sequence, segment));
+        }
+        Ok(set)
+    }
+
+    /// Get the genes.
+    pub fn genes(&self) -> &[Germline] {
+        &self.genes
+    }
+
+    /// Number of genes.
+    pub fn len(&self) -> usize {
+        self.genes.len()
+    }
+
+    /// Check if there are no genes.
+    pub fn is_empty(&self) -> bool {
+        self.genes.is_empty()
+    }
+}
+
+impl FromIterator<Germline> for GermlineSet {
+    fn from_iter<I: IntoIterator<Item = Germline>>(genes: I) -> Self {
+        GermlineSet {
+            genes: genes.into_iter().collect(),
+        }
+    }
+}
+
+/// Alignment mode of V genes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VMode {
+    /// Local alignment.
+    #[default]
+    Local,
+    /// Semi-global alignment with free end gaps in the read and the gene.
+    Glocal,
+}
+
+/// Assignment of a gene segment.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeneCall {
+    /// Best gene, followed by genes with the same score.
+    pub genes: Vec<String>,
+    pub score: i32,
+    /// Percent identity of the alignment of the best gene.
+    pub identity: f64,
+    /// Range of the read aligned (0-based, end-exclusive, in the orientation
+    /// of the genes).
+    pub sequence_range: Range<usize>,
+    /// Range of the best gene aligned.
+    pub germline_range: Range<usize>,
+    /// CIGAR of the alignment of the read against the best gene, starting at
+    /// the start of both ranges.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub cigar: Cigar,
+}
+
+impl GeneCall {
+    /// Call from the CIGAR of a read against a gene, without end gaps, and
+    /// with read positions offset by `offset`.
+    fn new(genes: Vec<String>, score: i32, cigar: &Cigar, offset: usize) -> Self {
+        let mut ops = cigar.ops();
+        let (mut i, mut j) = (cigar.query_start, cigar.ref_start);
+        while let Some((&(op, len), rest)) = ops.split_first() {
+            if !matches!(op, CigarOp::Ins | CigarOp::Del) {
+                break;
+            }
+            i += op.consumes_query() as usize * len;
+            j += op.consumes_ref() as usize * len;
+            ops = rest;
+        }
+        while let Some((&(op, _), rest)) = ops.split_last() {
+            if !matches!(op, CigarOp::Ins | CigarOp::Del) {
+                break;
+            }
+            ops = rest;
+        }
+
+        let cigar = Cigar::new(offset + i, j, ops);
+        let columns = ops.iter().map(|(_, len)| len).sum::<usize>();
+        let matches = ops
+            .iter()
+            .filter(|(op, _)| *op == CigarOp::Equal)
+            .map(|(_, len)| len)
+            .sum::<usize>();
+
+        GeneCall {
+            genes,
+            score,
+            identity: 100.0 * matches as f64 / columns.max(1) as f64,
+            sequence_range: offset + i..offset + i + cigar.query_len(),
+            germline_range: j..j + cigar.ref_len(),
+            cigar,
+        }
+    }
+
+    /// Position in the read aligned to a position of the best gene.
+    fn read_position(&self, germline_position: usize) -> Option<usize> {
+        let (mut i, mut j) = (self.cigar.query_start, self.cigar.ref_start);
+        for &(op, len) in self.cigar.ops() {
+            if op.consumes_ref() && (j..j + len).contains(&germline_position) {
+                return op.consumes_query().then_some(i + germline_position - j);
+            }
+            i += op.consumes_query() as usize * len;
+            j += op.consumes_ref() as usize * len;
+        }
+        None
+    }
+}
+
+/// V(D)J assignment of a read.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rearrangement {
+    pub sequence_id: String,
+    /// Read, as given.
+    pub sequence: String,
+    /// Whether the reverse complement of the read was assigned. Positions
+    /// are in the reverse complement of the read in this case.
+    pub rev_comp: bool,
+    pub v: Option<GeneCall>,
+    pub d: Option<GeneCall>,
+    pub j: Option<GeneCall>,
+    /// Range of the junction in the read, including the conserved codons.
+    pub junction: Option<Range<usize>>,
+    /// Whether the V and J genes are in the same frame.
+    pub vj_in_frame: bool,
+    /// Whether the V to J region contains a stop codon in the frame of the
+    /// V gene.
+    pub stop_codon: bool,
+    /// Whether the junction is in frame, without stop codons.
+    pub productive: bool,
+}
+
+impl Rearrangement {
+    /// Read in the orientation of the genes.
+    pub fn oriented_sequence(&self) -> Vec<u8> {
+        if self.rev_comp {
+            reverse_complement(self.sequence.as_bytes())
+        } else {
+            self.sequence.as_bytes().to_vec()
+        }
+    }
+
+    /// Junction nucleotide sequence.
+    pub fn junction_sequence(&self) -> Option<Vec<u8>> {
+        let junction = self.junction.clone()?;
+        Some(self.oriented_sequence()[junction].to_vec())
+    }
+
+    /// Junction amino acid sequence, if it is in frame.
+    pub fn junction_aa(&self) -> Option<Vec<u8>> {
+        let junction = self.junction_sequence()?;
+        junction
+            .len()
+            .is_multiple_of(3)
+            .then(|| GeneticCode::standard().translate(&junction))
+    }
+
+    /// CDR3 nucleotide sequence, i.e. the junction without its conserved
+    /// codons, if the junction contains both codons.
+    pub fn cdr3(&self) -> Option<Vec<u8>> {
+        let junction = self.junction_sequence()?;
+        (junction.len() >= 6).then(|| junction[3..junction.len() - 3].to_vec())
+    }
+}
+
+/// Assigner of V, D and J germline genes to reads.
+///
+/// ```rust,no_run
+/// use parasail_rs::immune::{Germline, GermlineSet, Segment, VdjAssigner};
+///
+/// let v = GermlineSet::from_iter([Germline::new(
+///     "IGHV-test*01",
+///     b"GAGGTGCAGCTGGTGGAGTCTGGGGGAGGCTTGGTACAGCCTGGGGGGTCCCTGAGACTCTCCTGTGCAGCCTCTGGATTCACCTTTAGCAGCTATGCCATGAGCTGGGTCCGCCAGGCTCCAGGGAAGGGGCTGGAGTGGGTCTCAGCTATTAGTGGTAGTGGTGGTAGCACATACTACGCAGACTCCGTGAAGGGCCGGTTCACCATCTCCAGAGACAATTCCAAGAACACGCTGTATCTGCAAATGAACAGCCTGAGAGCCGAGGACACGGCCGTATATTACTGTGCGAAAGA",
+///     Segment::V,
+/// )]);
+/// let j = GermlineSet::from_iter([Germline::new(
+///     "IGHJ-test*01",
+///     b"ACTACTTTGACTACTGGGGCCAGGGAACCCTGGTCACCGTCTCCTCAG",
+///     Segment::J,
+/// )]);
+/// let assigner = VdjAssigner::new(v, GermlineSet::default(), j);
+///
+/// let read = b"GCCGAGGACACGGCCGTATATTACTGTGCGAAAGATCGGGGTTATTTTGACTACTGGGGCCAGGGAACCCTGGTC";
+/// let rearrangement = assigner.assign("read1", read)?;
+/// assert_eq!(rearrangement.v.as_ref().unwrap().genes, ["IGHV-test*01"]);
+/// assert_eq!(rearrangement.junction_aa().unwrap(), b"CAKDRGYFDYW");
+/// assert!(rearrangement.productive);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct VdjAssigner {
+    v: GermlineSet,
+    d: GermlineSet,
+    j: GermlineSet,
+    v_mode: VMode,
+    min_v_score: i32,
+    min_d_score: i32,
+    min_j_score: i32,
+    /// Local aligners of parts of reads against D and J genes, without and
+    /// with traceback.
+    gene_scorer: Aligner,
+    gene_tracer: Aligner,
+}
+
+impl VdjAssigner {
+    /// Create an assigner from V, D and J germline sets (the D set is empty
+    /// for light chains and TRA/TRG). V genes are aligned locally, and V, D
+    /// and J genes need alignment scores of at least 100, 20 and 35 (20, 4 and
+    /// 7 matching bases).
+    pub fn new(v: GermlineSet, d: GermlineSet, j: GermlineSet) -> Self {
+        let mut builder = Aligner::new();
+        builder
+            .local()
+            .matrix(Self::matrix())
+            .gap_open(GAP_OPEN)
+            .gap_extend(GAP_EXTEND);
+        let gene_scorer = builder.build();
+        let gene_tracer = builder.use_trace().build();
+        VdjAssigner {
+            v,
+            d,
+            j,
+            v_mode: VMode::default(),
+            min_v_score: 20 * MATCH,
+            min_d_score: 4 * MATCH,
+            min_j_score: 7 * MATCH,
+            gene_scorer,
+            gene_tracer,
+        }
+    }
+
+    fn matrix() -> Matrix {
+        Matrix::create(b"ACGTN", MATCH, MISMATCH).unwrap()
+    }
+
+    /// Set the alignment mode of V genes.
+    pub fn v_mode(&mut self, v_mode: VMode) -> &mut Self {
+        self.v_mode = v_mode;
+        self
+    }
+
+    /// Set the minimum alignment score of V genes.
+    pub fn min_v_score(&mut self, min_v_score: i32) -> &mut Self {
+        self.min_v_score = min_v_score;
+        self
+    }
+
+    /// Set the minimum alignment score of D genes.
+    pub fn min_d_score(&mut self, min_d_score: i32) -> &mut Self {
+        self.min_d_score = min_d_score;
+        self
+    }
+
+    /// Set the minimum alignment score of J genes.
+    pub fn min_j_score(&mut self, min_j_score: i32) -> &mut Self {
+        self.min_j_score = min_j_score;
+        self
+    }
+
+    /// Assign germline genes to a read, in either orientation.
+    pub fn assign(&self, sequence_id: &str, read: &[u8]) -> Result<Rearrangement> {
+        let mut rearrangement = Rearrangement {
+            sequence_id: String::from(sequence_id),
+            sequence: String::from_utf8_lossy(read).into_owned(),
+            rev_comp: false,
+            v: None,
+            d: None,
+            j: None,
+            junction: None,
+            vj_in_frame: false,
+            stop_codon: false,
+            productive: false,
+        };
+        let upper = read.to_ascii_uppercase();
+        if upper.is_empty() || self.v.is_empty() {
+            return Ok(rearrangement);
+        }
+
+        // V genes scored with profiles of the read reused across genes, and
+        // the best one aligned again with traceback
+        let matrix = Self::matrix();
+        let mut builder = Aligner::new();
+        match self.v_mode {
+            VMode::Local => builder.local(),
+            VMode::Glocal => builder.semi_global(),
+        };
+        builder
+            .matrix(Self::matrix())
+            .gap_open(GAP_OPEN)
+            .gap_extend(GAP_EXTEND)
+            .profile(Profile::new(&upper, false, &matrix)?)
+            .reverse_profile(Profile::new(&reverse_complement(&upper), false, &matrix)?);
+        let v_scorer = builder.build();
+        let mut scores = Vec::with_capacity(self.v.len());
+        for gene in self.v.genes() {
+            let alignment = v_scorer.align_both_strands(None, &gene.sequence)?;
+            scores.push((alignment.score(), alignment.strand));
+        }
+        let Some((best, &(score, strand))) = scores
+            .iter()
+            .enumerate()
+            .max_by_key(|&(k, &(score, _))| (score, std::cmp::Reverse(k)))
+        else {
+            return Ok(rearrangement);
+        };
+        if score < self.min_v_score {
+            return Ok(rearrangement);
+        }
+        let genes = tied(self.v.genes(), best, |k| scores[k] == (score, strand));
+        let v_gene = &self.v.genes()[best];
+        let alignment = builder
+            .use_trace()
+            .build()
+            .align_both_strands(None, &v_gene.sequence)?;
+        let cigar = alignment.cigar(&upper, &v_gene.sequence)?;
+        let v = GeneCall::new(genes, score, &cigar, 0);
+
+        rearrangement.rev_comp = strand == Strand::Reverse;
+        let oriented = if rearrangement.rev_comp {
+            reverse_complement(&upper)
+        } else {
+            upper
+        };
+
+        // J genes after the V gene, and D genes in between
+        let v_end = v.sequence_range.end;
+        let j = self.call(&self.j, &oriented[v_end..], v_end, self.min_j_score)?;
+        let d_end = j
+            .as_ref()
+            .map_or(oriented.len(), |j| j.sequence_range.start);
+        let d = self.call(&self.d, &oriented[v_end..d_end], v_end, self.min_d_score)?;
+
+        // junction from the conserved Cys to the conserved Trp or Phe
+        if let Some(j) = &j {
+            let j_gene = self.j.genes().iter().find(|gene| gene.name == j.genes[0]);
+            let cys = v_gene.anchor.and_then(|anchor| v.read_position(anchor));
+            let trp = j_gene
+                .and_then(|gene| gene.anchor)
+                .and_then(|anchor| j.read_position(anchor + 2));
+            if let (Some(cys), Some(trp)) = (cys, trp) {
+                // junction spanning both conserved codons
+                if cys + 6 <= trp + 1 {
+                    let junction = cys..trp + 1;
+                    rearrangement.vj_in_frame = junction.len().is_multiple_of(3);
+                    let frame_start = v.sequence_range.start + (cys - v.sequence_range.start) % 3;
+                    let protein = GeneticCode::standard()
+                        .translate(&oriented[frame_start..j.sequence_range.end]);
+                    rearrangement.stop_codon = protein.contains(&b'*');
+                    rearrangement.productive =
+                        rearrangement.vj_in_frame && !rearrangement.stop_codon;
+                    rearrangement.junction = Some(junction);
+                }
+            }
+        }
+
+        rearrangement.v = Some(v);
+        rearrangement.d = d;
+        rearrangement.j = j;
+        Ok(rearrangement)
+    }
+
+    /// Best gene of a set aligned locally against part of a read, if its
+    /// score is at least `min_score`, with tied genes.
+    fn call(
+        &self,
+        set: &GermlineSet,
+        part: &[u8],
+        offset: usize,
+        min_score: i32,
+    ) -> Result<Option<GeneCall>> {
+        if part.is_empty() || set.is_empty() {
+            return Ok(None);
+        }
+        let mut scores = Vec::with_capacity(set.len());
+        for gene in set.genes() {
+            scores.push(
+                self.gene_scorer
+                    .align(Some(part), &gene.sequence)?
+                    .get_score(),
+            );
+        }
+        let Some((best, &score)) = scores
+            .iter()
+            .enumerate()
+            .max_by_key(|&(k, &score)| (score, std::cmp::Reverse(k)))
+        else {
+            return Ok(None);
+        };
+        if score < min_score {
+            return Ok(None);
+        }
+        let genes = tied(set.genes(), best, |k| scores[k] == score);
+        let gene = &set.genes()[best].sequence;
+        let cigar = self
+            .gene_tracer
+            .align(Some(part), gene)?
+            .cigar(part, gene)?;
+        Ok(Some(GeneCall::new(genes, score, &cigar, offset)))
+    }
+}
+
+/// Names of the best gene and of the genes tied with it.
+fn tied(genes: &[Germline], best: usize, is_tied: impl Fn(usize) -> bool) -> Vec<String> {
+    let mut names = vec![genes[best].name.clone()];
+    for (k, gene) in genes.iter().enumerate() {
+        if k != best && is_tied(k) && !names.contains(&gene.name) {
+            names.push(gene.name.clone());
+        }
+    }
+    names
+}
+
+/// Write rearrangements in the AIRR rearrangement TSV format, with the
+/// required fields (alignments are left empty) and gene scores, identities
+/// and positions (1-based, closed), junction and CDR3 fields. CIGARs have
+/// the unaligned ends of the read soft clipped (`S`) and the unaligned start
+/// of the gene skipped (`N`). Missing values are empty and booleans are `T`
+/// or `F`.
+pub fn write_airr<W: Write>(rearrangements: &[Rearrangement], mut writer: W) -> io::Result<()> {
+    let mut header = vec![
+        "sequence_id",
+        "sequence",
+        "rev_comp",
+        "productive",
+        "v_call",
+        "d_call",
+        "j_call",
+        "sequence_alignment",
+        "germline_alignment",
+        "junction",
+        "junction_aa",
+        "v_cigar",
+        "d_cigar",
+        "j_cigar",
+        "vj_in_frame",
+        "stop_codon",
+    ];
+    let segments = ["v", "d", "j"].map(|segment| {
+        [
+            "score",
+            "identity",
+            "sequence_start",
+            "sequence_end",
+            "germline_start",
+            "germline_end",
+        ]
+        .map(|field| format!("{segment}_{field}"))
+    });
+    header.extend(segments.iter().flatten().map(String::as_str));
+    header.extend(["junction_length", "cdr3", "cdr3_aa"]);
+    writeln!(writer, "{}", header.join("\t"))?;
+
+    let boolean = |value: bool| if value { "T" } else { "F" };
+    let string = |sequence: Option<Vec<u8>>| {
+        sequence.map_or_else(String::new, |sequence| {
+            String::from_utf8_lossy(&sequence).into_owned()
+        })
+    };
+    for rearrangement in rearrangements {
+        let calls = [&rearrangement.v, &rearrangement.d, &rearrangement.j];
+        let mut fields = vec![
+            rearrangement.sequence_id.clone(),
+            rearrangement.sequence.clone(),
+            String::from(boolean(rearrangement.rev_comp)),
+            String::from(boolean(rearrangement.productive)),
+        ];
+        fields.extend(calls.map(|call| {
+            call.as_ref()
+                .map_or_else(String::new, |call| call.genes.join(","))
+        }));
+        fields.extend([
+            String::new(),
+            String::new(),
+            string(rearrangement.junction_sequence()),
+            string(rearrangement.junction_aa()),
+        ]);
+        fields.extend(calls.map(|call| {
+            call.as_ref().map_or_else(String::new, |call| {
+                airr_cigar(call, rearrangement.sequence.len())
+            })
+        }));
+        fields.extend([
+            String::from(boolean(rearrangement.vj_in_frame)),
+            String::from(boolean(rearrangement.stop_codon)),
+        ]);
+        for call in calls {
+            match call {
+                Some(call) => fields.extend([
+                    call.score.to_string(),
+                    format!("{:.3}", call.identity),
+                    (call.sequence_range.start + 1).to_string(),
+                    call.sequence_range.end.to_string(),
+                    (call.germline_range.start + 1).to_string(),
+                    call.germline_range.end.to_string(),
+                ]),
+                None => fields.extend(std::iter::repeat_n(String::new(), 6)),
+            }
+        }
+        fields.extend([
+            rearrangement
+                .junction
+                .as_ref()
+                .map_or_else(String::new, |junction| junction.len().to_string()),
+            string(rearrangement.cdr3()),
+            string(
+                rearrangement
+                    .junction_aa()
+                    .filter(|protein| protein.len() >= 2)
+                    .map(|protein| protein[1..protein.len() - 1].to_vec()),
+            ),
+        ]);
+        writeln!(writer, "{}", fields.join("\t"))?;
+    }
+    Ok(())
+}
+
+/// AIRR CIGAR of a gene call in a read of length `read_len`.
+fn airr_cigar(call: &GeneCall, read_len: usize) -> String {
+    let mut cigar = String::new();
+    if call.sequence_range.start > 0 {
+        cigar.push_str(&format!("{}S", call.sequence_range.start));
+    }
+    if call.germline_range.start > 0 {
+        cigar.push_str(&format!("{}N", call.germline_range.start));
+    }
+    cigar.push_str(&call.cigar.to_string());
+    if call.sequence_range.end < read_len {
+        cigar.push_str(&format!("{}S", read_len - call.sequence_range.end));
+    }
+    cigar
+}
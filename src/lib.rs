@@ -2,7 +2,17 @@
 
 pub mod aligner;
 pub mod alignment;
+pub mod crispr;
+pub mod demux;
 pub mod error;
+pub mod fastx;
+pub mod immune;
 pub mod matrix;
+pub mod merge;
 pub mod prelude;
 pub mod profile;
+pub mod sequence;
+pub mod translate;
+pub mod trim;
+pub mod variants;
+pub mod viz;
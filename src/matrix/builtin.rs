@@ -0,0 +1,195 @@
+//! Catalogue of the substitution matrices shipped with parasail.
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use super::Error;
+
+/// Type of sequence a builtin matrix is intended for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoleculeType {
+    Protein,
+    Nucleotide,
+}
+
+macro_rules! builtin_matrices {
+    ($($variant:ident => ($name:literal, $molecule:ident)),+ $(,)?) => {
+        /// Substitution matrices shipped with parasail.
+        ///
+        /// Builtin matrices can be parsed from their (case-insensitive) name:
+        /// ```rust,no_run
+        /// use parasail_rs::matrix::BuiltinMatrix;
+        /// use parasail_rs::prelude::Matrix;
+        ///
+        /// let blosum62: BuiltinMatrix = "BLOSUM62".parse()?;
+        /// let matrix = Matrix::builtin(blosum62)?;
+        /// # Ok::<(), Box<dyn std::error::Error>>(())
+        /// ```
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum BuiltinMatrix {
+            $($variant,)+
+        }
+
+        impl BuiltinMatrix {
+            const ALL: &'static [BuiltinMatrix] = &[$(BuiltinMatrix::$variant,)+];
+
+            /// Get the parasail name of the matrix (e.g., "blosum62").
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(BuiltinMatrix::$variant => $name,)+
+                }
+            }
+
+            /// Get the type of sequence the matrix is intended for.
+            pub fn molecule_type(&self) -> MoleculeType {
+                match self {
+                    $(BuiltinMatrix::$variant => MoleculeType::$molecule,)+
+                }
+            }
+        }
+    };
+}
+
+builtin_matrices! {
+    Blosum30 => ("blosum30", Protein),
+    Blosum35 => ("blosum35", Protein),
+    Blosum40 => ("blosum40", Protein),
+    Blosum45 => ("blosum45", Protein),
+    Blosum50 => ("blosum50", Protein),
+    Blosum55 => ("blosum55", Protein),
+    Blosum60 => ("blosum60", Protein),
+    Blosum62 => ("blosum62", Protein),
+    Blosum65 => ("blosum65", Protein),
+    Blosum70 => ("blosum70", Protein),
+    Blosum75 => ("blosum75", Protein),
+    Blosum80 => ("blosum80", Protein),
+    Blosum85 => ("blosum85", Protein),
+    Blosum90 => ("blosum90", Protein),
+    Blosum100 => ("blosum100", Protein),
+    BlosumN => ("blosumn", Protein),
+    Pam10 => ("pam10", Protein),
+    Pam20 => ("pam20", Protein),
+    Pam30 => ("pam30", Protein),
+    Pam40 => ("pam40", Protein),
+    Pam50 => ("pam50", Protein),
+    Pam60 => ("pam60", Protein),
+    Pam70 => ("pam70", Protein),
+    Pam80 => ("pam80", Protein),
+    Pam90 => ("pam90", Protein),
+    Pam100 => ("pam100", Protein),
+    Pam110 => ("pam110", Protein),
+    Pam120 => ("pam120", Protein),
+    Pam130 => ("pam130", Protein),
+    Pam140 => ("pam140", Protein),
+    Pam150 => ("pam150", Protein),
+    Pam160 => ("pam160", Protein),
+    Pam170 => ("pam170", Protein),
+    Pam180 => ("pam180", Protein),
+    Pam190 => ("pam190", Protein),
+    Pam200 => ("pam200", Protein),
+    Pam210 => ("pam210", Protein),
+    Pam220 => ("pam220", Protein),
+    Pam230 => ("pam230", Protein),
+    Pam240 => ("pam240", Protein),
+    Pam250 => ("pam250", Protein),
+    Pam260 => ("pam260", Protein),
+    Pam270 => ("pam270", Protein),
+    Pam280 => ("pam280", Protein),
+    Pam290 => ("pam290", Protein),
+    Pam300 => ("pam300", Protein),
+    Pam310 => ("pam310", Protein),
+    Pam320 => ("pam320", Protein),
+    Pam330 => ("pam330", Protein),
+    Pam340 => ("pam340", Protein),
+    Pam350 => ("pam350", Protein),
+    Pam360 => ("pam360", Protein),
+    Pam370 => ("pam370", Protein),
+    Pam380 => ("pam380", Protein),
+    Pam390 => ("pam390", Protein),
+    Pam400 => ("pam400", Protein),
+    Pam410 => ("pam410", Protein),
+    Pam420 => ("pam420", Protein),
+    Pam430 => ("pam430", Protein),
+    Pam440 => ("pam440", Protein),
+    Pam450 => ("pam450", Protein),
+    Pam460 => ("pam460", Protein),
+    Pam470 => ("pam470", Protein),
+    Pam480 => ("pam480", Protein),
+    Pam490 => ("pam490", Protein),
+    Pam500 => ("pam500", Protein),
+    Nuc44 => ("nuc44", Nucleotide),
+    DnaFull => ("dnafull", Nucleotide),
+}
+
+impl BuiltinMatrix {
+    /// Iterate over every builtin matrix, e.g. to list choices in a UI.
+    pub fn all() -> impl Iterator<Item = BuiltinMatrix> {
+        Self::ALL.iter().copied()
+    }
+
+    /// Find the closest builtin matrix name to a misspelled name, if any is
+    /// close enough to be a likely typo.
+    fn suggest(name: &str) -> Option<&'static str> {
+        let name = normalize(name);
+        Self::all()
+            .map(|matrix| (edit_distance(&name, matrix.name()), matrix.name()))
+            .filter(|(distance, _)| *distance <= 2)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, name)| name)
+    }
+}
+
+/// Lowercase a matrix name and drop separators, so that "BLOSUM-62" and
+/// "NUC.4.4" match "blosum62" and "nuc44".
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.as_bytes();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.bytes().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl Display for BuiltinMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Parse a builtin matrix name. Names are case-insensitive and separators are
+/// ignored (e.g., "BLOSUM62", "blosum-62" and "NUC.4.4" are all accepted).
+/// The EMBOSS name "EDNAFULL" is accepted for dnafull. Unknown names return
+/// `Error::UnknownBuiltin` with the closest matching name, if any.
+impl FromStr for BuiltinMatrix {
+    type Err = crate::error::Error;
+
+    fn from_str(s: &str) -> crate::prelude::Result<Self> {
+        let name = normalize(s);
+        if name == "ednafull" {
+            return Ok(BuiltinMatrix::DnaFull);
+        }
+
+        Self::all()
+            .find(|matrix| matrix.name() == name)
+            .ok_or_else(|| {
+                Error::UnknownBuiltin {
+                    name: s.to_string(),
+                    suggestion: Self::suggest(s),
+                }
+                .into()
+            })
+    }
+}
@@ -14,6 +14,32 @@ pub enum Error {
     NotSquare,
     NotBuiltIn,
     InvalidIndex(i32, i32),
+    #[from]
+    Io(std::io::Error),
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    InvalidValues {
+        expected: usize,
+        found: usize,
+    },
+    UnknownBuiltin {
+        name: String,
+        suggestion: Option<&'static str>,
+    },
+    NotPssm,
+    UnknownSymbol(u8),
+    DuplicateSymbol(u8),
+    IncompatibleMatrices,
+    InvalidBackground(String),
+    EmptyAlignment,
+    UnequalLengths {
+        sequence: usize,
+        expected: usize,
+        found: usize,
+    },
 }
 
 impl Display for Error {
@@ -0,0 +1,108 @@
+//! IUPAC-aware nucleotide scoring matrices.
+
+use super::{Matrix, MatrixKind};
+use crate::prelude::Result;
+
+/// Nucleotide symbols in the matrix created by `Matrix::dna_iupac`.
+const IUPAC_ALPHABET: &[u8] = b"ACGTURYSWKMBDHVN*";
+
+/// How ambiguity codes (e.g., N, R, Y) are scored by `Matrix::dna_iupac`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AmbiguityPolicy {
+    /// Any pair involving an ambiguity code scores as a mismatch.
+    Mismatch,
+    /// Codes with at least one base in common score as a match (e.g., R vs A).
+    AnyOverlap,
+    /// Codes score the expected match score given their base overlap, rounded
+    /// to the nearest integer. For example, A vs R matches half of the time,
+    /// so it scores `(match + mismatch) / 2`.
+    Expected,
+    /// Like `Expected`, but all scores (including plain matches and
+    /// mismatches) are multiplied by the given factor before rounding to keep
+    /// more precision. Gap penalties should be scaled by the same factor.
+    Scaled(u32),
+}
+
+/// Get the set of bases (A=1, C=2, G=4, T=8) represented by a nucleotide code.
+pub(crate) fn base_set(code: u8) -> u8 {
+    match code.to_ascii_uppercase() {
+        b'A' => 0b0001,
+        b'C' => 0b0010,
+        b'G' => 0b0100,
+        b'T' | b'U' => 0b1000,
+        b'R' => 0b0101,
+        b'Y' => 0b1010,
+        b'S' => 0b0110,
+        b'W' => 0b1001,
+        b'K' => 0b1100,
+        b'M' => 0b0011,
+        b'B' => 0b1110,
+        b'D' => 0b1101,
+        b'H' => 0b1011,
+        b'V' => 0b0111,
+        b'N' => 0b1111,
+        _ => 0,
+    }
+}
+
+impl Matrix {
+    /// Create a nucleotide scoring matrix covering the IUPAC ambiguity codes
+    /// (R, Y, S, W, K, M, B, D, H, V, N) as well as U, which scores like T.
+    /// Upper and lower case (soft-masked) bases score identically, and any
+    /// other character scores as a mismatch.
+    ///
+    /// Pairs of unambiguous bases score `match_score` or `mismatch_score`.
+    /// Pairs involving ambiguity codes are scored by their base overlap as
+    /// described by the `AmbiguityPolicy`.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::matrix::AmbiguityPolicy;
+    /// use parasail_rs::prelude::Matrix;
+    ///
+    /// let matrix = Matrix::dna_iupac(2, -2, AmbiguityPolicy::Expected)?;
+    /// assert_eq!(matrix.score(b'A', b'a'), Some(2));
+    /// assert_eq!(matrix.score(b'A', b'R'), Some(0));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn dna_iupac(
+        match_score: i32,
+        mismatch_score: i32,
+        ambiguity_policy: AmbiguityPolicy,
+    ) -> Result<Self> {
+        assert!(match_score >= 0 && mismatch_score <= 0, "Match score should be a positive integer and mismatch score should be a negative integer.");
+
+        let scale = match ambiguity_policy {
+            AmbiguityPolicy::Scaled(factor) => factor as f64,
+            _ => 1.0,
+        };
+        let matched = match_score as f64 * scale;
+        let mismatched = mismatch_score as f64 * scale;
+
+        let mut values = Vec::with_capacity(IUPAC_ALPHABET.len() * IUPAC_ALPHABET.len());
+        for &a in IUPAC_ALPHABET {
+            for &b in IUPAC_ALPHABET {
+                let (x, y) = (base_set(a), base_set(b));
+                let overlap = (x & y).count_ones();
+                let ambiguous = x.count_ones() > 1 || y.count_ones() > 1;
+
+                let score = if overlap == 0 {
+                    mismatched
+                } else if !ambiguous {
+                    matched
+                } else {
+                    match ambiguity_policy {
+                        AmbiguityPolicy::Mismatch => mismatched,
+                        AmbiguityPolicy::AnyOverlap => matched,
+                        AmbiguityPolicy::Expected | AmbiguityPolicy::Scaled(_) => {
+                            let p = overlap as f64 / (x.count_ones() * y.count_ones()) as f64;
+                            p * matched + (1.0 - p) * mismatched
+                        }
+                    }
+                };
+                values.push(score.round() as i32);
+            }
+        }
+
+        Matrix::from_raw(MatrixKind::Square, IUPAC_ALPHABET, &values, None)
+    }
+}
@@ -1,19 +1,49 @@
 //! Substitution matrices.
 
+mod builtin;
 mod error;
+mod iupac;
+mod parser;
+mod psiblast;
+mod pssm;
+mod quality;
+#[cfg(feature = "serde")]
+mod serialize;
+mod transform;
 
 use crate::prelude::Result;
+pub use builtin::{BuiltinMatrix, MoleculeType};
 pub use error::Error;
+pub use iupac::AmbiguityPolicy;
 use libparasail_sys::{
     parasail_matrix_convert_square_to_pssm, parasail_matrix_copy, parasail_matrix_create,
-    parasail_matrix_free, parasail_matrix_from_file, parasail_matrix_lookup,
-    parasail_matrix_pssm_create, parasail_matrix_set_value, parasail_matrix_t,
+    parasail_matrix_free, parasail_matrix_lookup, parasail_matrix_pssm_create,
+    parasail_matrix_set_value, parasail_matrix_t,
 };
-use std::ffi::CString;
+pub use psiblast::PsiBlastPssm;
+pub use pssm::{Background, GapHandling, PssmBuilder};
+use std::ffi::{CStr, CString};
 use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
 use std::ops::Deref;
 use std::path::Path;
 use std::slice;
+use std::str::FromStr;
+
+/// Layout of a substitution matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum MatrixKind {
+    /// Square matrix scoring every pair of alphabet symbols.
+    Square,
+    /// Position-specific scoring matrix with one row per query position.
+    Pssm,
+}
 
 /// Substitution matrix for sequence alignment.
 /// Matrices can be created from:
@@ -45,8 +75,12 @@ impl Matrix {
 
     /// Create a new scoring matrix from a pre-defined matrix.
     /// The matrix name should be one of the following:
-    /// - blosum{30, 35, 40, 45, 50, 55, 60, 62, 65, 70, 75, 80, 85, 90, 95, 100}
+    /// - blosum{30, 35, 40, 45, 50, 55, 60, 62, 65, 70, 75, 80, 85, 90, 100} or blosumn
     /// - pam{10-500} (in steps of 10, i.e., pam10, pam20, ... pam500).
+    /// - nuc44 or dnafull
+    ///
+    /// See `BuiltinMatrix` for an enumerated list of these matrices and
+    /// `Matrix::builtin` to create one without a string lookup.
     ///
     /// For example:
     /// ```rust,no_run
@@ -72,6 +106,25 @@ impl Matrix {
         })
     }
 
+    /// Create a new scoring matrix from a builtin matrix.
+    ///
+    /// For example:
+    /// ```rust,no_run
+    /// use parasail_rs::matrix::BuiltinMatrix;
+    /// use parasail_rs::prelude::Matrix;
+    ///
+    /// let blosum62 = Matrix::builtin(BuiltinMatrix::Blosum62)?;
+    ///
+    /// // list all builtin matrices
+    /// for matrix in BuiltinMatrix::all() {
+    ///     println!("{} ({:?})", matrix, matrix.molecule_type());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn builtin(matrix: BuiltinMatrix) -> Result<Self> {
+        Self::from(matrix.name())
+    }
+
     /// Create a new scoring matrix from a file.
     /// Files should contain either square or position-specific scoring matrices.
     /// The examples below are taken directly from the [Parasail C library docs](https://github.com/jeffdaily/parasail?tab=readme-ov-file#substitution-matrices).
@@ -128,28 +181,128 @@ impl Matrix {
     /// P  -2   0  -4   0  -2  -4  -5  -5   5  -5  -3  -1   1   1  -3   2  -4  -4   1   3
     /// I  -5  -7   7   1   0  -2   3  -5  -6  -5   0  -4  -4  -1  -6   3  -6  -6  -6  -6
     /// ```
+    ///
+    /// Files are parsed in Rust, so malformed files are reported with the line
+    /// and column of the problem. NCBI BLAST matrix files (such as the
+    /// BLOSUM and PAM files distributed with BLAST) are also supported.
     pub fn from_file(file: &str) -> Result<Self> {
         let filepath = Path::new(file);
         if !filepath.exists() {
             return Err(Error::FileNotFound(filepath.to_str().unwrap_or("").to_string()).into());
         }
 
-        let file = CString::new(file).map_err(Error::InteriorNulByte)?;
+        let file = File::open(filepath).map_err(Error::Io)?;
+        Self::from_reader(file)
+    }
+
+    /// Create a new scoring matrix from a reader containing a square or
+    /// position-specific scoring matrix. See `from_file` for the format.
+    ///
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Matrix;
+    /// let file = std::fs::File::open("blosum62.txt")?;
+    /// let matrix = Matrix::from_reader(file)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        let parsed = parser::parse(BufReader::new(reader))?;
+        Self::from_raw(
+            parsed.kind,
+            &parsed.alphabet,
+            &parsed.values,
+            parsed.query.as_deref(),
+        )
+    }
+
+    /// Build a user defined matrix from its alphabet (including the catch-all
+    /// symbol as the last symbol) and row-major values. Square matrices need
+    /// `alphabet.len()` rows and PSSMs need one row per query position.
+    pub(crate) fn from_raw(
+        kind: MatrixKind,
+        alphabet: &[u8],
+        values: &[i32],
+        query: Option<&[u8]>,
+    ) -> Result<Self> {
+        let size = alphabet.len();
+        if size == 0 {
+            return Err(Error::NullMatrix.into());
+        }
+
+        let rows = match kind {
+            MatrixKind::Square => size,
+            MatrixKind::Pssm => values.len() / size,
+        };
+        if rows == 0 || values.len() != rows * size {
+            return Err(Error::InvalidValues {
+                expected: rows.max(1) * size,
+                found: values.len(),
+            }
+            .into());
+        }
+
+        // parasail adds the catch-all symbol itself
+        let symbols = CString::new(&alphabet[..size - 1]).map_err(Error::InteriorNulByte)?;
+        let min = values.iter().copied().min().unwrap_or_default();
+        let max = values.iter().copied().max().unwrap_or_default();
 
         unsafe {
-            let matrix = parasail_matrix_from_file(file.as_ptr());
+            let matrix = match kind {
+                MatrixKind::Square => parasail_matrix_create(symbols.as_ptr(), max, min),
+                MatrixKind::Pssm => {
+                    let trimmed: Vec<i32> = values
+                        .chunks_exact(size)
+                        .flat_map(|row| &row[..size - 1])
+                        .copied()
+                        .collect();
+                    parasail_matrix_pssm_create(symbols.as_ptr(), trimmed.as_ptr(), rows as i32)
+                }
+            };
 
             if matrix.is_null() {
                 return Err(Error::NullMatrix.into());
             }
 
+            for (i, value) in values.iter().enumerate() {
+                parasail_matrix_set_value(matrix, (i / size) as i32, (i % size) as i32, *value);
+            }
+
+            // keep the original catch-all symbol (e.g. 'N' for NCBI nucleotide matrices)
+            *((*matrix).alphabet as *mut u8).add(size - 1) = alphabet[size - 1];
+
+            if let Some(query) = query {
+                // freed by parasail_matrix_free
+                let query_ptr = libc::calloc(query.len() + 1, 1) as *mut u8;
+                if query_ptr.is_null() {
+                    parasail_matrix_free(matrix);
+                    return Err(Error::NullMatrix.into());
+                }
+                std::ptr::copy_nonoverlapping(query.as_ptr(), query_ptr, query.len());
+                (*matrix).query = query_ptr as *const libc::c_char;
+            }
+
             Ok(Self {
-                inner: parasail_matrix_from_file(file.as_ptr()),
+                inner: matrix,
                 builtin: false,
             })
         }
     }
 
+    /// Write the matrix in the same format read by `from_file`, so that
+    /// edited matrices can be saved and loaded again.
+    ///
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Matrix;
+    /// let mut matrix = Matrix::create(b"ACGT", 2, -1)?;
+    /// matrix.set_value(0, 0, 5)?;
+    /// let mut file = std::fs::File::create("custom.txt")?;
+    /// matrix.write_to(&mut file)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        write!(writer, "{self}").map_err(Error::Io)?;
+        Ok(())
+    }
+
     /// Create a new scoring matrix from a position-specific scoring matrix.
     pub fn create_pssm(alphabet: &str, values: Vec<i32>, rows: i32) -> Result<Self> {
         let alphabet = CString::new(alphabet).map_err(Error::InteriorNulByte)?;
@@ -240,6 +393,138 @@ impl Matrix {
 
         Ok(())
     }
+
+    /// Get the matrix name for a builtin matrix (e.g., "blosum62").
+    /// Returns `None` for user defined matrices and PSSMs, including PSSMs
+    /// built from a builtin matrix.
+    ///
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Matrix;
+    /// let blosum62 = Matrix::from("blosum62")?;
+    /// assert_eq!(blosum62.name(), Some("blosum62"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn name(&self) -> Option<&str> {
+        if !self.builtin || self.kind() != MatrixKind::Square {
+            return None;
+        }
+
+        unsafe {
+            let name = (*self.inner).name;
+            if name.is_null() {
+                return None;
+            }
+            CStr::from_ptr(name).to_str().ok()
+        }
+    }
+
+    /// Get the matrix kind (square or PSSM).
+    pub fn kind(&self) -> MatrixKind {
+        unsafe {
+            if (*self.inner).type_ == 0 {
+                MatrixKind::Square
+            } else {
+                MatrixKind::Pssm
+            }
+        }
+    }
+
+    /// Get the matrix alphabet, i.e., the symbol for each column.
+    /// The last symbol is the catch-all for characters outside the alphabet
+    /// (usually '*').
+    pub fn alphabet(&self) -> &[u8] {
+        unsafe {
+            let alphabet = (*self.inner).alphabet;
+            if alphabet.is_null() {
+                return &[];
+            }
+            CStr::from_ptr(alphabet).to_bytes()
+        }
+    }
+
+    /// Get the number of columns in the matrix (alphabet size, including the
+    /// catch-all symbol).
+    pub fn size(&self) -> usize {
+        unsafe { (*self.inner).size as usize }
+    }
+
+    /// Get the minimum score in the matrix.
+    pub fn min_score(&self) -> i32 {
+        unsafe { (*self.inner).min }
+    }
+
+    /// Get the maximum score in the matrix.
+    pub fn max_score(&self) -> i32 {
+        unsafe { (*self.inner).max }
+    }
+
+    /// Get the raw matrix values in row-major order. Square matrices have
+    /// `size() * size()` values and PSSMs have `pssm_rows().len() * size()`
+    /// values.
+    pub fn values(&self) -> &[i32] {
+        unsafe {
+            let length = (*self.inner).length as usize;
+            slice::from_raw_parts((*self.inner).matrix, length * self.size())
+        }
+    }
+
+    /// Get the substitution score for a pair of residues in a square matrix.
+    /// Residues outside of the alphabet are scored with the catch-all row or
+    /// column. Returns `None` for PSSMs (see `pssm_score`).
+    ///
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Matrix;
+    /// let blosum62 = Matrix::from("blosum62")?;
+    /// assert_eq!(blosum62.score(b'W', b'W'), Some(11));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn score(&self, a: u8, b: u8) -> Option<i32> {
+        if self.kind() != MatrixKind::Square {
+            return None;
+        }
+
+        let row = self.index_of(a);
+        let col = self.index_of(b);
+        Some(self.values()[row * self.size() + col])
+    }
+
+    /// Get the score for a residue at a given position of a PSSM.
+    /// Returns `None` for square matrices or if the position is out of bounds.
+    pub fn pssm_score(&self, position: usize, residue: u8) -> Option<i32> {
+        self.pssm_rows()?
+            .nth(position)
+            .map(|row| row[self.index_of(residue)])
+    }
+
+    /// Get an iterator over the rows (one per query position) of a PSSM.
+    /// Returns `None` for square matrices.
+    pub fn pssm_rows(&self) -> Option<slice::ChunksExact<'_, i32>> {
+        if self.kind() != MatrixKind::Pssm {
+            return None;
+        }
+
+        Some(self.values().chunks_exact(self.size()))
+    }
+
+    /// Get the representative query sequence of a PSSM, if one was provided.
+    pub fn pssm_query(&self) -> Option<&[u8]> {
+        if self.kind() != MatrixKind::Pssm {
+            return None;
+        }
+
+        unsafe {
+            let query = (*self.inner).query;
+            if query.is_null() {
+                return None;
+            }
+            Some(CStr::from_ptr(query).to_bytes())
+        }
+    }
+
+    /// Get the column index of a residue.
+    fn index_of(&self, residue: u8) -> usize {
+        unsafe { *(*self.inner).mapper.add(residue as usize) as usize }
+    }
 }
 
 /// Default scoring matrix is an identity matrix for DNA sequences.
@@ -249,24 +534,57 @@ impl Default for Matrix {
     }
 }
 
-#[doc(hidden)]
+/// Matrices are displayed with labeled rows and columns, in the format read by
+/// `Matrix::from_file`. Square matrix rows are labeled with the alphabet and
+/// PSSM rows with the query sequence (if available).
 impl Display for Matrix {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        unsafe {
-            let length = (*self.inner).length as usize;
-            let size = (*self.inner).size as usize;
-            let matrix = slice::from_raw_parts((*self.inner).matrix, length * size);
-            for i in 0..length {
-                for j in 0..size {
-                    write!(f, "{} ", matrix[i * size + j])?;
-                }
-                writeln!(f)?;
+        let alphabet = self.alphabet();
+        let size = self.size();
+        let width = self
+            .values()
+            .iter()
+            .map(|v| v.to_string().len())
+            .max()
+            .unwrap_or(1)
+            .max(2)
+            + 1;
+
+        write!(f, " ")?;
+        for symbol in alphabet {
+            write!(f, "{:>width$}", *symbol as char)?;
+        }
+        writeln!(f)?;
+
+        let labels: Vec<Option<u8>> = match self.kind() {
+            MatrixKind::Square => alphabet.iter().copied().map(Some).collect(),
+            MatrixKind::Pssm => match self.pssm_query() {
+                Some(query) => query.iter().copied().map(Some).collect(),
+                None => vec![None; self.values().len() / size],
+            },
+        };
+
+        for (label, row) in labels.iter().zip(self.values().chunks_exact(size)) {
+            write!(f, "{}", label.unwrap_or(b' ') as char)?;
+            for value in row {
+                write!(f, "{value:>width$}")?;
             }
+            writeln!(f)?;
         }
         Ok(())
     }
 }
 
+/// Parse a square or position-specific scoring matrix from a string.
+/// See `Matrix::from_file` for the format.
+impl FromStr for Matrix {
+    type Err = crate::error::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_reader(s.as_bytes())
+    }
+}
+
 #[doc(hidden)]
 impl Deref for Matrix {
     type Target = *const parasail_matrix_t;
@@ -0,0 +1,208 @@
+//! Parser for substitution matrix files.
+//!
+//! Supports the square and PSSM layouts read by parasail (see
+//! `Matrix::from_file`) as well as NCBI BLAST matrix files, which share the
+//! same layout but may indent the alphabet header or use tabs.
+
+use std::io::BufRead;
+
+use super::{Error, MatrixKind};
+use crate::prelude::Result;
+
+/// Matrix contents parsed from a file, before being handed to parasail.
+pub(crate) struct ParsedMatrix {
+    pub(crate) kind: MatrixKind,
+    pub(crate) alphabet: Vec<u8>,
+    pub(crate) values: Vec<i32>,
+    pub(crate) query: Option<Vec<u8>>,
+}
+
+/// Split a line into whitespace separated tokens, keeping the 1-based column
+/// of each token for diagnostics. Anything after a '#' is a comment.
+pub(super) fn tokens(line: &str) -> Vec<(usize, &str)> {
+    let line = match line.find('#') {
+        Some(comment) => &line[..comment],
+        None => line,
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in line.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                tokens.push((s + 1, &line[s..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        tokens.push((s + 1, &line[s..]));
+    }
+    tokens
+}
+
+pub(super) fn parse_error(line: usize, column: usize, message: String) -> crate::error::Error {
+    Error::Parse {
+        line,
+        column,
+        message,
+    }
+    .into()
+}
+
+/// Parse a square or position-specific scoring matrix.
+///
+/// The first non-comment line is the alphabet header. Every following line is
+/// a row, optionally starting with a single letter (or '*') label. If the row
+/// labels repeat the alphabet, the matrix is square. Otherwise it is a PSSM and
+/// the labels form the representative query sequence.
+pub(crate) fn parse<R: BufRead>(reader: R) -> Result<ParsedMatrix> {
+    let mut alphabet: Option<Vec<u8>> = None;
+    let mut labels: Vec<Option<u8>> = Vec::new();
+    let mut values = Vec::new();
+    let mut line_no = 0;
+
+    for line in reader.lines() {
+        line_no += 1;
+        let line = line.map_err(Error::Io)?;
+        let tokens = tokens(&line);
+        if tokens.is_empty() {
+            continue;
+        }
+
+        let Some(alphabet) = &alphabet else {
+            let mut header = Vec::with_capacity(tokens.len());
+            for (column, token) in tokens {
+                let &[symbol] = token.as_bytes() else {
+                    return Err(parse_error(
+                        line_no,
+                        column,
+                        format!("alphabet symbols must be single characters, found '{token}'"),
+                    ));
+                };
+                if header.contains(&symbol) {
+                    return Err(parse_error(
+                        line_no,
+                        column,
+                        format!("duplicate alphabet symbol '{token}'"),
+                    ));
+                }
+                header.push(symbol);
+            }
+            alphabet = Some(header);
+            continue;
+        };
+
+        let mut row = tokens.as_slice();
+        let (_, first) = row[0];
+        if first.parse::<i32>().is_err() {
+            match first.as_bytes() {
+                &[label] if label.is_ascii_alphabetic() || label == b'*' => {
+                    labels.push(Some(label));
+                    row = &row[1..];
+                }
+                _ => {
+                    return Err(parse_error(
+                        line_no,
+                        row[0].0,
+                        format!("expected a row label or integer, found '{first}'"),
+                    ));
+                }
+            }
+        } else {
+            labels.push(None);
+        }
+
+        if row.len() > alphabet.len() {
+            return Err(parse_error(
+                line_no,
+                row[alphabet.len()].0,
+                format!("too many values in row, expected {} values", alphabet.len()),
+            ));
+        }
+
+        if row.len() < alphabet.len() {
+            return Err(parse_error(
+                line_no,
+                line.trim_end().len() + 1,
+                format!(
+                    "expected {} values in row, found {}",
+                    alphabet.len(),
+                    row.len()
+                ),
+            ));
+        }
+
+        for &(column, token) in row {
+            let value = token.parse::<i32>().map_err(|_| {
+                parse_error(
+                    line_no,
+                    column,
+                    format!("expected integer, found '{token}'"),
+                )
+            })?;
+            values.push(value);
+        }
+    }
+
+    let Some(alphabet) = alphabet else {
+        return Err(parse_error(
+            line_no + 1,
+            1,
+            String::from("missing alphabet header"),
+        ));
+    };
+
+    if labels.is_empty() {
+        return Err(parse_error(
+            line_no + 1,
+            1,
+            String::from("matrix has no rows"),
+        ));
+    }
+
+    let is_square = labels.len() == alphabet.len()
+        && labels
+            .iter()
+            .zip(&alphabet)
+            .all(|(label, symbol)| *label == Some(*symbol));
+
+    if is_square {
+        return Ok(ParsedMatrix {
+            kind: MatrixKind::Square,
+            alphabet,
+            values,
+            query: None,
+        });
+    }
+
+    // a PSSM may omit the query sequence entirely
+    let query = if labels.iter().all(Option::is_none) {
+        None
+    } else {
+        Some(labels.iter().map(|label| label.unwrap_or(b'*')).collect())
+    };
+
+    // PSSM files may leave out the catch-all column, in which case residues
+    // outside of the alphabet get the lowest score (as with `create_pssm`)
+    let (alphabet, values) = if alphabet.last() == Some(&b'*') {
+        (alphabet, values)
+    } else {
+        let min = values.iter().copied().min().unwrap_or_default();
+        let size = alphabet.len();
+        let values = values
+            .chunks_exact(size)
+            .flat_map(|row| row.iter().copied().chain([min]))
+            .collect();
+        ([alphabet, vec![b'*']].concat(), values)
+    };
+
+    Ok(ParsedMatrix {
+        kind: MatrixKind::Pssm,
+        alphabet,
+        values,
+        query,
+    })
+}
@@ -0,0 +1,306 @@
+//! Reader and writer for PSI-BLAST ASCII PSSM files (`psiblast -out_ascii_pssm`).
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+use super::parser::{parse_error, tokens};
+use super::{Error, Matrix, MatrixKind};
+use crate::prelude::Result;
+
+const TITLE: &str = "Last position-specific scoring matrix computed, weighted observed percentages rounded down, information per position, and relative weight of gapless real matches to pseudocounts";
+
+/// Contents of a PSI-BLAST ASCII PSSM file.
+///
+/// The log-odds scores are loaded into a PSSM `Matrix` whose query is the
+/// representative sequence. The optional columns of the file (weighted
+/// observed percentages, information per position, and relative weight of
+/// gapless real matches to pseudocounts) are kept alongside.
+pub struct PsiBlastPssm {
+    /// Log-odds scores and representative sequence.
+    pub matrix: Matrix,
+    /// Weighted observed percentages, row-major with one row per position and
+    /// one column per residue of the matrix alphabet (excluding the catch-all
+    /// symbol).
+    pub frequencies: Option<Vec<u32>>,
+    /// Information content per position.
+    pub information: Option<Vec<f64>>,
+    /// Relative weight of gapless real matches to pseudocounts per position.
+    pub relative_weights: Option<Vec<f64>>,
+}
+
+impl PsiBlastPssm {
+    /// Read a PSI-BLAST ASCII PSSM file.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::matrix::PsiBlastPssm;
+    ///
+    /// let pssm = PsiBlastPssm::from_file("query.pssm")?;
+    /// println!("{:?}", pssm.matrix.pssm_query());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn from_file(file: &str) -> Result<Self> {
+        if !Path::new(file).exists() {
+            return Err(Error::FileNotFound(file.to_string()).into());
+        }
+
+        let file = File::open(file).map_err(Error::Io)?;
+        Self::from_reader(file)
+    }
+
+    /// Read a PSI-BLAST ASCII PSSM from a reader. Errors are reported as
+    /// `matrix::Error::Parse` with the line and column of the problem.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        let mut alphabet: Option<Vec<u8>> = None;
+        let mut has_frequencies = false;
+        let mut query = Vec::new();
+        let mut values = Vec::new();
+        let mut frequencies = Vec::new();
+        let mut information = Vec::new();
+        let mut relative_weights = Vec::new();
+        let mut line_no = 0;
+
+        for line in BufReader::new(reader).lines() {
+            line_no += 1;
+            let line = line.map_err(Error::Io)?;
+            let tokens = tokens(&line);
+
+            let Some(alphabet) = &alphabet else {
+                // the header is the first line made up of single letters only
+                let is_header = !tokens.is_empty()
+                    && tokens
+                        .iter()
+                        .all(|(_, t)| t.len() == 1 && t.as_bytes()[0].is_ascii_alphabetic());
+                if is_header {
+                    let header: Vec<u8> = tokens.iter().map(|(_, t)| t.as_bytes()[0]).collect();
+                    let half = header.len() / 2;
+                    has_frequencies =
+                        header.len().is_multiple_of(2) && header[..half] == header[half..];
+                    alphabet = Some(if has_frequencies {
+                        header[..half].to_vec()
+                    } else {
+                        header
+                    });
+                }
+                continue;
+            };
+
+            // the position block ends at the first line not starting with a position
+            if tokens.is_empty() || tokens[0].1.parse::<usize>().is_err() {
+                if query.is_empty() {
+                    continue;
+                }
+                break;
+            }
+
+            let size = alphabet.len();
+            let expected_len = if has_frequencies {
+                2 + 2 * size
+            } else {
+                2 + size
+            };
+            if tokens.len() != expected_len && tokens.len() != expected_len + 2 {
+                return Err(parse_error(
+                    line_no,
+                    tokens.last().map(|(c, _)| *c).unwrap_or(1),
+                    format!(
+                        "expected {} or {} fields in row, found {}",
+                        expected_len,
+                        expected_len + 2,
+                        tokens.len()
+                    ),
+                ));
+            }
+
+            let (column, residue) = tokens[1];
+            let &[residue] = residue.as_bytes() else {
+                return Err(parse_error(
+                    line_no,
+                    column,
+                    format!("expected a residue, found '{residue}'"),
+                ));
+            };
+            query.push(residue);
+
+            for &(column, token) in &tokens[2..2 + size] {
+                let value = token.parse::<i32>().map_err(|_| {
+                    parse_error(
+                        line_no,
+                        column,
+                        format!("expected integer, found '{token}'"),
+                    )
+                })?;
+                values.push(value);
+            }
+            // catch-all column, filled in below
+            values.push(i32::MAX);
+
+            if has_frequencies {
+                for &(column, token) in &tokens[2 + size..2 + 2 * size] {
+                    let value = token.parse::<u32>().map_err(|_| {
+                        parse_error(
+                            line_no,
+                            column,
+                            format!("expected percentage, found '{token}'"),
+                        )
+                    })?;
+                    frequencies.push(value);
+                }
+            }
+
+            if tokens.len() == expected_len + 2 {
+                for (&(column, token), target) in tokens[expected_len..]
+                    .iter()
+                    .zip([&mut information, &mut relative_weights])
+                {
+                    let value = token.parse::<f64>().map_err(|_| {
+                        parse_error(line_no, column, format!("expected number, found '{token}'"))
+                    })?;
+                    target.push(value);
+                }
+            }
+        }
+
+        let Some(alphabet) = alphabet else {
+            return Err(parse_error(
+                line_no + 1,
+                1,
+                String::from("missing alphabet header"),
+            ));
+        };
+        if query.is_empty() {
+            return Err(parse_error(
+                line_no + 1,
+                1,
+                String::from("PSSM has no positions"),
+            ));
+        }
+
+        // residues outside of the alphabet get the lowest score
+        let min = values
+            .iter()
+            .copied()
+            .filter(|v| *v != i32::MAX)
+            .min()
+            .unwrap_or_default();
+        for value in values.iter_mut().filter(|v| **v == i32::MAX) {
+            *value = min;
+        }
+
+        let positions = query.len();
+        let alphabet = [alphabet, vec![b'*']].concat();
+        let matrix = Matrix::from_raw(MatrixKind::Pssm, &alphabet, &values, Some(&query))?;
+
+        Ok(Self {
+            matrix,
+            frequencies: has_frequencies.then_some(frequencies),
+            information: (information.len() == positions).then_some(information),
+            relative_weights: (relative_weights.len() == positions).then_some(relative_weights),
+        })
+    }
+
+    /// Write the PSSM in PSI-BLAST ASCII format, including the optional
+    /// columns that are present.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        write_psiblast(
+            &self.matrix,
+            self.frequencies.as_deref(),
+            self.information.as_deref(),
+            self.relative_weights.as_deref(),
+            writer,
+        )
+    }
+}
+
+impl Matrix {
+    /// Write a PSSM in the PSI-BLAST ASCII format (log-odds block only). The
+    /// catch-all symbol of the alphabet is left out, and positions are labeled
+    /// with the PSSM query, or 'X' if the PSSM has no query.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::Matrix;
+    ///
+    /// let pssm = Matrix::from_file("pssm.txt")?;
+    /// let mut file = std::fs::File::create("query.pssm")?;
+    /// pssm.write_psiblast(&mut file)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn write_psiblast<W: Write>(&self, writer: W) -> Result<()> {
+        write_psiblast(self, None, None, None, writer)
+    }
+}
+
+fn write_psiblast<W: Write>(
+    matrix: &Matrix,
+    frequencies: Option<&[u32]>,
+    information: Option<&[f64]>,
+    relative_weights: Option<&[f64]>,
+    mut writer: W,
+) -> Result<()> {
+    let Some(rows) = matrix.pssm_rows() else {
+        return Err(Error::NotPssm.into());
+    };
+
+    let alphabet = matrix.alphabet();
+    let size = alphabet.len() - 1;
+    let positions = rows.len();
+
+    if let Some(frequencies) = frequencies {
+        if frequencies.len() != positions * size {
+            return Err(Error::InvalidValues {
+                expected: positions * size,
+                found: frequencies.len(),
+            }
+            .into());
+        }
+    }
+    // information and relative weights are written as a pair
+    let extra = match (information, relative_weights) {
+        (Some(information), Some(weights))
+            if information.len() == positions && weights.len() == positions =>
+        {
+            Some((information, weights))
+        }
+        (None, None) => None,
+        _ => {
+            return Err(Error::InvalidValues {
+                expected: positions,
+                found: information.or(relative_weights).map_or(0, <[f64]>::len),
+            }
+            .into())
+        }
+    };
+
+    let write = |writer: &mut W| -> std::io::Result<()> {
+        writeln!(writer)?;
+        writeln!(writer, "{TITLE}")?;
+        write!(writer, "        ")?;
+        for _ in 0..if frequencies.is_some() { 2 } else { 1 } {
+            for symbol in &alphabet[..size] {
+                write!(writer, "{:>4}", *symbol as char)?;
+            }
+        }
+        writeln!(writer)?;
+
+        for (i, row) in rows.enumerate() {
+            let residue = matrix.pssm_query().map_or(b'X', |query| query[i]);
+            write!(writer, "{:>5} {} ", i + 1, residue as char)?;
+            for value in &row[..size] {
+                write!(writer, "{value:>4}")?;
+            }
+            if let Some(frequencies) = frequencies {
+                for value in &frequencies[i * size..(i + 1) * size] {
+                    write!(writer, "{value:>4}")?;
+                }
+            }
+            if let Some((information, weights)) = extra {
+                write!(writer, " {:>5.2} {:>5.2}", information[i], weights[i])?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    };
+
+    write(&mut writer).map_err(Error::Io)?;
+    Ok(())
+}
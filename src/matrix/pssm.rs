@@ -0,0 +1,376 @@
+//! Position-specific scoring matrices built from multiple sequence alignments.
+
+use super::{Error, Matrix, MatrixKind};
+use crate::prelude::Result;
+
+/// Lowest log-odds score (in bits) given to residues with a zero probability,
+/// i.e., residues never observed in a column when no pseudocount is used.
+const MIN_LOG_ODDS: f64 = -10.0;
+
+/// Robinson & Robinson (1991) amino acid frequencies, as used by BLAST.
+const PROTEIN_FREQUENCIES: [(u8, f64); 20] = [
+    (b'A', 0.07805),
+    (b'R', 0.05129),
+    (b'N', 0.04487),
+    (b'D', 0.05364),
+    (b'C', 0.01925),
+    (b'Q', 0.04264),
+    (b'E', 0.06295),
+    (b'G', 0.07377),
+    (b'H', 0.02199),
+    (b'I', 0.05142),
+    (b'L', 0.09019),
+    (b'K', 0.05744),
+    (b'M', 0.02243),
+    (b'F', 0.03856),
+    (b'P', 0.05203),
+    (b'S', 0.07120),
+    (b'T', 0.05841),
+    (b'W', 0.01330),
+    (b'Y', 0.03216),
+    (b'V', 0.06441),
+];
+
+fn is_gap(residue: u8) -> bool {
+    residue == b'-' || residue == b'.'
+}
+
+/// Background residue frequencies used to compute log-odds scores. The
+/// background alphabet is also the alphabet of the resulting PSSM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Background {
+    alphabet: Vec<u8>,
+    frequencies: Vec<f64>,
+}
+
+impl Background {
+    /// Create a background from residues and their frequencies. Symbols are
+    /// case-insensitive and frequencies are normalized to sum to one.
+    pub fn new(alphabet: &[u8], frequencies: &[f64]) -> Result<Self> {
+        if alphabet.is_empty() || alphabet.len() != frequencies.len() {
+            return Err(Error::InvalidBackground(format!(
+                "expected one frequency per residue, found {} residues and {} frequencies",
+                alphabet.len(),
+                frequencies.len()
+            ))
+            .into());
+        }
+
+        let alphabet = alphabet.to_ascii_uppercase();
+        for (i, symbol) in alphabet.iter().enumerate() {
+            if alphabet[..i].contains(symbol) || is_gap(*symbol) || *symbol == b'*' {
+                return Err(Error::InvalidBackground(format!(
+                    "invalid or duplicate residue '{}'",
+                    *symbol as char
+                ))
+                .into());
+            }
+        }
+
+        if frequencies.iter().any(|f| !f.is_finite() || *f <= 0.0) {
+            return Err(
+                Error::InvalidBackground(String::from("frequencies should be positive")).into(),
+            );
+        }
+
+        let total: f64 = frequencies.iter().sum();
+        Ok(Self {
+            alphabet,
+            frequencies: frequencies.iter().map(|f| f / total).collect(),
+        })
+    }
+
+    /// Equal frequencies for every residue in the alphabet.
+    pub fn uniform(alphabet: &[u8]) -> Result<Self> {
+        Self::new(alphabet, &vec![1.0; alphabet.len()])
+    }
+
+    /// Amino acid frequencies of Robinson & Robinson (1991), as used by BLAST.
+    pub fn protein() -> Self {
+        Self {
+            alphabet: PROTEIN_FREQUENCIES.iter().map(|(aa, _)| *aa).collect(),
+            frequencies: PROTEIN_FREQUENCIES.iter().map(|(_, f)| *f).collect(),
+        }
+    }
+
+    /// Equal frequencies for A, C, G and T.
+    pub fn dna() -> Self {
+        Self {
+            alphabet: b"ACGT".to_vec(),
+            frequencies: vec![0.25; 4],
+        }
+    }
+
+    /// Get the background residues.
+    pub fn alphabet(&self) -> &[u8] {
+        &self.alphabet
+    }
+
+    /// Get the (normalized) background frequencies, in alphabet order.
+    pub fn frequencies(&self) -> &[f64] {
+        &self.frequencies
+    }
+}
+
+/// How alignment columns containing gaps ('-' or '.') become PSSM positions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GapHandling {
+    /// Keep every column. Gaps are left out of the residue counts.
+    KeepAll,
+    /// Keep only the columns where the first sequence has a residue, as
+    /// PSI-BLAST does for its query. The first sequence (without gaps) becomes
+    /// the PSSM query.
+    QueryAnchored,
+    /// Drop columns where the (weighted) fraction of gaps is above the given
+    /// threshold (between 0 and 1).
+    MaxGapFraction(f64),
+}
+
+/// PSSM builder helper for configuring `Matrix::pssm_from_msa`.
+///
+/// ```rust,no_run
+/// use parasail_rs::matrix::{Background, GapHandling, PssmBuilder};
+///
+/// let msa = [b"ACGT-A".as_slice(), b"ACGTTA", b"AGGT-A"];
+/// let pssm = PssmBuilder::new(&msa, Background::dna())
+///     .pseudocount(0.5)
+///     .henikoff_weights()
+///     .gap_handling(GapHandling::QueryAnchored)
+///     .build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct PssmBuilder<'a, S: AsRef<[u8]>> {
+    sequences: &'a [S],
+    background: Background,
+    pseudocount: f64,
+    henikoff_weights: bool,
+    gap_handling: GapHandling,
+    scale: f64,
+}
+
+impl<'a, S: AsRef<[u8]>> PssmBuilder<'a, S> {
+    /// Create a new PSSM builder given aligned sequences (all of the same
+    /// length) and background frequencies. By default, a pseudocount of 1 is
+    /// used, sequences are not weighted, all columns are kept, and scores are
+    /// in half-bit units.
+    pub fn new(sequences: &'a [S], background: Background) -> Self {
+        Self {
+            sequences,
+            background,
+            pseudocount: 1.0,
+            henikoff_weights: false,
+            gap_handling: GapHandling::KeepAll,
+            scale: 2.0,
+        }
+    }
+
+    /// Set the total pseudocount weight, distributed among residues according
+    /// to the background frequencies.
+    pub fn pseudocount(&mut self, pseudocount: f64) -> &mut Self {
+        assert!(
+            pseudocount >= 0.0,
+            "Pseudocount should be a non-negative number."
+        );
+        self.pseudocount = pseudocount;
+        self
+    }
+
+    /// Weight sequences with the position-based scheme of Henikoff & Henikoff
+    /// (1994), so that groups of near-identical sequences do not dominate the
+    /// profile.
+    pub fn henikoff_weights(&mut self) -> &mut Self {
+        self.henikoff_weights = true;
+        self
+    }
+
+    /// Set how columns with gaps are handled.
+    pub fn gap_handling(&mut self, gap_handling: GapHandling) -> &mut Self {
+        self.gap_handling = gap_handling;
+        self
+    }
+
+    /// Set the number of score units per bit (e.g., 2 for half-bits).
+    pub fn scale(&mut self, scale: f64) -> &mut Self {
+        assert!(scale > 0.0, "Scale should be a positive number.");
+        self.scale = scale;
+        self
+    }
+
+    /// Build the PSSM.
+    pub fn build(&self) -> Result<Matrix> {
+        let sequences: Vec<Vec<u8>> = self
+            .sequences
+            .iter()
+            .map(|seq| seq.as_ref().to_ascii_uppercase())
+            .collect();
+
+        let Some(columns) = sequences.first().map(Vec::len) else {
+            return Err(Error::EmptyAlignment.into());
+        };
+        if columns == 0 {
+            return Err(Error::EmptyAlignment.into());
+        }
+        for (i, seq) in sequences.iter().enumerate() {
+            if seq.len() != columns {
+                return Err(Error::UnequalLengths {
+                    sequence: i,
+                    expected: columns,
+                    found: seq.len(),
+                }
+                .into());
+            }
+        }
+
+        let weights = if self.henikoff_weights {
+            henikoff_weights(&sequences)
+        } else {
+            vec![1.0; sequences.len()]
+        };
+
+        let alphabet = self.background.alphabet();
+        let size = alphabet.len() + 1;
+        let mut values = Vec::new();
+        let mut query = Vec::new();
+
+        for column in 0..columns {
+            let mut counts = vec![0.0; alphabet.len()];
+            let mut gaps = 0.0;
+            for (seq, weight) in sequences.iter().zip(&weights) {
+                let residue = seq[column];
+                if is_gap(residue) {
+                    gaps += weight;
+                } else if let Some(i) = alphabet.iter().position(|r| *r == residue) {
+                    counts[i] += weight;
+                }
+            }
+
+            let keep = match self.gap_handling {
+                GapHandling::KeepAll => true,
+                GapHandling::QueryAnchored => !is_gap(sequences[0][column]),
+                GapHandling::MaxGapFraction(max) => {
+                    let total: f64 = weights.iter().sum();
+                    total == 0.0 || gaps / total <= max
+                }
+            };
+            if !keep {
+                continue;
+            }
+
+            let observed: f64 = counts.iter().sum();
+            let scores: Vec<i32> = counts
+                .iter()
+                .zip(self.background.frequencies())
+                .map(|(count, background)| {
+                    let total = observed + self.pseudocount;
+                    let p = if total > 0.0 {
+                        (count + self.pseudocount * background) / total
+                    } else {
+                        *background
+                    };
+                    let bits = if p > 0.0 {
+                        (p / background).log2().max(MIN_LOG_ODDS)
+                    } else {
+                        MIN_LOG_ODDS
+                    };
+                    (bits * self.scale).round() as i32
+                })
+                .collect();
+
+            // the representative residue is the best scoring one, unless the
+            // query is anchored on the first sequence
+            let residue = match self.gap_handling {
+                GapHandling::QueryAnchored => sequences[0][column],
+                _ => {
+                    let best = (0..scores.len())
+                        .rev()
+                        .max_by_key(|i| scores[*i])
+                        .unwrap_or_default();
+                    alphabet[best]
+                }
+            };
+
+            query.push(residue);
+            values.extend(scores);
+            // placeholder for the catch-all column
+            values.push(i32::MAX);
+        }
+
+        if query.is_empty() {
+            return Err(Error::EmptyAlignment.into());
+        }
+
+        // residues outside of the alphabet get the lowest score
+        let min = values
+            .iter()
+            .copied()
+            .filter(|v| *v != i32::MAX)
+            .min()
+            .unwrap_or_default();
+        for row in values.chunks_exact_mut(size) {
+            row[size - 1] = min;
+        }
+
+        let alphabet = [alphabet, b"*"].concat();
+        Matrix::from_raw(MatrixKind::Pssm, &alphabet, &values, Some(&query))
+    }
+}
+
+/// Position-based sequence weights of Henikoff & Henikoff (1994), normalized
+/// to sum to the number of sequences.
+fn henikoff_weights(sequences: &[Vec<u8>]) -> Vec<f64> {
+    let mut weights = vec![0.0; sequences.len()];
+    let columns = sequences.first().map(Vec::len).unwrap_or_default();
+
+    for column in 0..columns {
+        let mut counts = [0usize; 256];
+        for seq in sequences {
+            if !is_gap(seq[column]) {
+                counts[seq[column] as usize] += 1;
+            }
+        }
+        let distinct = counts.iter().filter(|c| **c > 0).count();
+        for (seq, weight) in sequences.iter().zip(weights.iter_mut()) {
+            if !is_gap(seq[column]) {
+                *weight += 1.0 / (distinct * counts[seq[column] as usize]) as f64;
+            }
+        }
+    }
+
+    let total: f64 = weights.iter().sum();
+    if total == 0.0 {
+        return vec![1.0; sequences.len()];
+    }
+    let n = sequences.len() as f64;
+    weights.iter().map(|w| w * n / total).collect()
+}
+
+impl Matrix {
+    /// Create a PSSM (position-specific scoring matrix) from a multiple
+    /// sequence alignment. Each column of the alignment becomes a position of
+    /// the PSSM scored in half-bits as `log2(p / background)`, where `p` is the
+    /// residue frequency in the column after adding pseudocounts. The best
+    /// scoring residue at each position forms the PSSM query.
+    ///
+    /// Use `PssmBuilder` to weight sequences or to drop gappy columns.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::matrix::Background;
+    /// use parasail_rs::prelude::{Aligner, Matrix};
+    ///
+    /// let msa = [b"ACGTAC".as_slice(), b"ACGTTC", b"AGGTAC"];
+    /// let pssm = Matrix::pssm_from_msa(&msa, &Background::dna(), 1.0)?;
+    /// let query = pssm.pssm_query().unwrap_or_default().to_vec();
+    /// let aligner = Aligner::new().matrix(pssm).semi_global().build();
+    /// let result = aligner.align(Some(&query), b"TTACGTACTT")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn pssm_from_msa<S: AsRef<[u8]>>(
+        sequences: &[S],
+        background: &Background,
+        pseudocount: f64,
+    ) -> Result<Self> {
+        PssmBuilder::new(sequences, background.clone())
+            .pseudocount(pseudocount)
+            .build()
+    }
+}
@@ -0,0 +1,75 @@
+//! Quality-aware position-specific scoring.
+
+use super::{Error, Matrix, MatrixKind};
+use crate::prelude::Result;
+
+impl Matrix {
+    /// Create a PSSM for a query with per-base Phred qualities, as in FASTQ
+    /// files (Phred+33 encoded, one per query base), from a square matrix.
+    ///
+    /// Each row holds the expected scores of the query residue given its
+    /// error probability `p`: `(1 - p) * s(a, b) + p * mean(s(x, b))` over the
+    /// other symbols `x` of the alphabet (without the catch-all symbol). High
+    /// quality rows are the matrix rows, while matches and mismatches of low
+    /// quality residues score close to the mean. Scores are rounded, so
+    /// matrices with larger scores (see `Matrix::scaled`) weight qualities
+    /// more finely. Returns `Error::NotSquare` for PSSMs.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::prelude::Matrix;
+    ///
+    /// let matrix = Matrix::create(b"ACGT", 10, -10)?;
+    /// let pssm = matrix.quality_pssm(b"ACGT", b"II#!")?;
+    /// assert_eq!(pssm.pssm_score(0, b'A'), Some(10));
+    /// assert_eq!(pssm.pssm_score(2, b'G'), Some(-3));
+    /// assert_eq!(pssm.pssm_score(3, b'T'), Some(-5));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the query is empty or if there is not one quality per query
+    /// base.
+    pub fn quality_pssm(&self, query: &[u8], qualities: &[u8]) -> Result<Matrix> {
+        assert!(
+            !query.is_empty(),
+            "PSSM query sequence should not be empty."
+        );
+        assert_eq!(
+            qualities.len(),
+            query.len(),
+            "There should be one quality per query base."
+        );
+        if self.kind() != MatrixKind::Square {
+            return Err(Error::NotSquare.into());
+        }
+
+        let size = self.size();
+        let catch_all = size - 1;
+        let values = self.values();
+        // the error probability at which all residues are equally likely
+        let max_error = (catch_all.saturating_sub(1)) as f64 / catch_all.max(1) as f64;
+
+        let mut pssm = Vec::with_capacity(query.len() * size);
+        for (&residue, &quality) in query.iter().zip(qualities) {
+            let row = self.index_of(residue);
+            let error = 10f64
+                .powf(-(quality.saturating_sub(33) as f64) / 10.0)
+                .min(max_error);
+            for col in 0..size {
+                let score = values[row * size + col] as f64;
+                if row == catch_all || catch_all < 2 {
+                    pssm.push(score as i32);
+                    continue;
+                }
+                let others = (0..catch_all)
+                    .filter(|&other| other != row)
+                    .map(|other| values[other * size + col] as f64)
+                    .sum::<f64>()
+                    / (catch_all - 1) as f64;
+                pssm.push(((1.0 - error) * score + error * others).round() as i32);
+            }
+        }
+
+        Matrix::from_raw(MatrixKind::Pssm, self.alphabet(), &pssm, Some(query))
+    }
+}
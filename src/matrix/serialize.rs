@@ -0,0 +1,85 @@
+//! Serde support for matrices (requires the `serde` feature).
+//!
+//! Builtin matrices are serialized by name (e.g., `"blosum62"`). Other
+//! matrices, including PSSMs built from builtin matrices, are serialized
+//! inline with their alphabet (including the catch-all symbol), row-major
+//! values and, for PSSMs, the query:
+//!
+//! ```toml
+//! [matrix]
+//! kind = "square"
+//! alphabet = "ACGT*"
+//! values = [...]
+//! ```
+//!
+//! Either form can be deserialized. When `kind` is left out, the matrix is
+//! square if it has one row per symbol and no query, and a PSSM otherwise.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Matrix, MatrixKind};
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum MatrixRepr {
+    Builtin(String),
+    Values {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        kind: Option<MatrixKind>,
+        alphabet: String,
+        values: Vec<i32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        query: Option<String>,
+    },
+}
+
+impl Serialize for Matrix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self.name() {
+            Some(name) if self.kind() == MatrixKind::Square => {
+                MatrixRepr::Builtin(name.to_string())
+            }
+            _ => MatrixRepr::Values {
+                kind: Some(self.kind()),
+                alphabet: String::from_utf8_lossy(self.alphabet()).into_owned(),
+                values: self.values().to_vec(),
+                query: self
+                    .pssm_query()
+                    .map(|query| String::from_utf8_lossy(query).into_owned()),
+            },
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Matrix {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match MatrixRepr::deserialize(deserializer)? {
+            MatrixRepr::Builtin(name) if name.is_empty() => {
+                Err(D::Error::custom("matrix name should not be empty"))
+            }
+            MatrixRepr::Builtin(name) => Matrix::from(&name).map_err(D::Error::custom),
+            MatrixRepr::Values {
+                kind,
+                alphabet,
+                values,
+                query,
+            } => {
+                let size = alphabet.len();
+                let kind = kind.unwrap_or(if query.is_none() && values.len() == size * size {
+                    MatrixKind::Square
+                } else {
+                    MatrixKind::Pssm
+                });
+                Matrix::from_raw(
+                    kind,
+                    alphabet.as_bytes(),
+                    &values,
+                    query.as_deref().map(str::as_bytes),
+                )
+                .map_err(D::Error::custom)
+            }
+        }
+    }
+}
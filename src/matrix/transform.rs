@@ -0,0 +1,213 @@
+//! Non-mutating transforms deriving new matrices from existing ones.
+
+use super::{Error, Matrix, MatrixKind};
+use crate::prelude::Result;
+
+impl Matrix {
+    /// Create a copy of the matrix with every score multiplied by a factor and
+    /// rounded to the nearest integer.
+    ///
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Matrix;
+    /// let blosum62 = Matrix::from("blosum62")?;
+    /// let doubled = blosum62.scaled(2.0)?;
+    /// assert_eq!(doubled.score(b'W', b'W'), Some(22));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn scaled(&self, factor: f64) -> Result<Matrix> {
+        let values: Vec<i32> = self
+            .values()
+            .iter()
+            .map(|v| (*v as f64 * factor).round() as i32)
+            .collect();
+        self.derive(self.alphabet(), &values, self.pssm_query())
+    }
+
+    /// Create a copy of the matrix with a constant added to every score.
+    pub fn shifted(&self, offset: i32) -> Result<Matrix> {
+        let values: Vec<i32> = self.values().iter().map(|v| v + offset).collect();
+        self.derive(self.alphabet(), &values, self.pssm_query())
+    }
+
+    /// Create a copy of the matrix keeping only the given symbols, in the given
+    /// order. The catch-all symbol is always kept as the last symbol. Returns
+    /// `Error::UnknownSymbol` if a symbol is not in the alphabet.
+    ///
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Matrix;
+    /// let blosum62 = Matrix::from("blosum62")?;
+    /// let standard = blosum62.restrict_alphabet(b"ARNDCQEGHILKMFPSTWYV")?;
+    /// assert_eq!(standard.size(), 21);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn restrict_alphabet(&self, symbols: &[u8]) -> Result<Matrix> {
+        let size = self.size();
+        let catch_all = size - 1;
+
+        let mut indices = Vec::with_capacity(symbols.len() + 1);
+        for &symbol in symbols {
+            let index = self.index_of(symbol);
+            if index == catch_all {
+                if symbol == self.alphabet()[catch_all] {
+                    continue;
+                }
+                return Err(Error::UnknownSymbol(symbol).into());
+            }
+            if indices.contains(&index) {
+                return Err(Error::DuplicateSymbol(symbol).into());
+            }
+            indices.push(index);
+        }
+        indices.push(catch_all);
+
+        let alphabet: Vec<u8> = indices.iter().map(|i| self.alphabet()[*i]).collect();
+        let values: Vec<i32> = match self.kind() {
+            MatrixKind::Square => indices
+                .iter()
+                .flat_map(|row| indices.iter().map(move |col| row * size + col))
+                .map(|i| self.values()[i])
+                .collect(),
+            MatrixKind::Pssm => self
+                .values()
+                .chunks_exact(size)
+                .flat_map(|row| indices.iter().map(move |col| row[*col]))
+                .collect(),
+        };
+        self.derive(&alphabet, &values, self.pssm_query())
+    }
+
+    /// Create a copy of the matrix with an additional symbol, inserted before
+    /// the catch-all symbol.
+    ///
+    /// For square matrices, `row` holds the scores of the new symbol against
+    /// every symbol of the extended alphabet (i.e., `size() + 1` values, with
+    /// the new symbol second to last), and is used for both the new row and
+    /// column. For PSSMs, `row` holds the score of the new symbol at each
+    /// position.
+    ///
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Matrix;
+    /// let dna = Matrix::create(b"ACGT", 2, -1)?;
+    /// // score N as 0 against everything
+    /// let with_n = dna.extend_alphabet(b'N', &[0, 0, 0, 0, 0, 0])?;
+    /// assert_eq!(with_n.alphabet(), b"ACGTN*");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn extend_alphabet(&self, symbol: u8, row: &[i32]) -> Result<Matrix> {
+        let size = self.size();
+        let catch_all = size - 1;
+        if self.index_of(symbol) != catch_all || symbol == self.alphabet()[catch_all] {
+            return Err(Error::DuplicateSymbol(symbol).into());
+        }
+
+        let mut alphabet = self.alphabet().to_vec();
+        alphabet.insert(catch_all, symbol);
+
+        let values: Vec<i32> = match self.kind() {
+            MatrixKind::Square => {
+                if row.len() != size + 1 {
+                    return Err(Error::InvalidValues {
+                        expected: size + 1,
+                        found: row.len(),
+                    }
+                    .into());
+                }
+
+                // index of the extended alphabet in the original alphabet
+                let original = |i: usize| if i < catch_all { i } else { i - 1 };
+                let mut values = Vec::with_capacity((size + 1) * (size + 1));
+                for i in 0..=size {
+                    for j in 0..=size {
+                        values.push(match (i == catch_all, j == catch_all) {
+                            (true, _) => row[j],
+                            (false, true) => row[i],
+                            _ => self.values()[original(i) * size + original(j)],
+                        });
+                    }
+                }
+                values
+            }
+            MatrixKind::Pssm => {
+                let positions = self.values().len() / size;
+                if row.len() != positions {
+                    return Err(Error::InvalidValues {
+                        expected: positions,
+                        found: row.len(),
+                    }
+                    .into());
+                }
+
+                self.values()
+                    .chunks_exact(size)
+                    .zip(row)
+                    .flat_map(|(existing, value)| {
+                        existing[..catch_all]
+                            .iter()
+                            .chain([value, &existing[catch_all]])
+                            .copied()
+                    })
+                    .collect()
+            }
+        };
+        self.derive(&alphabet, &values, self.pssm_query())
+    }
+
+    /// Create a copy of the matrix with every symbol (including the catch-all
+    /// symbol and the PSSM query) renamed by a function. Symbols are matched
+    /// case-insensitively, so the renamed symbols should be unique regardless
+    /// of case.
+    ///
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Matrix;
+    /// let dna = Matrix::create(b"ACGT", 2, -1)?;
+    /// let rna = dna.map_symbols(|symbol| if symbol == b'T' { b'U' } else { symbol })?;
+    /// assert_eq!(rna.score(b'U', b'U'), Some(2));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn map_symbols<F: FnMut(u8) -> u8>(&self, mut f: F) -> Result<Matrix> {
+        let alphabet: Vec<u8> = self.alphabet().iter().map(|s| f(*s)).collect();
+        for (i, symbol) in alphabet.iter().enumerate() {
+            if alphabet[..i].iter().any(|s| s.eq_ignore_ascii_case(symbol)) {
+                return Err(Error::DuplicateSymbol(*symbol).into());
+            }
+        }
+
+        let query: Option<Vec<u8>> = self
+            .pssm_query()
+            .map(|query| query.iter().map(|s| f(*s)).collect());
+        self.derive(&alphabet, self.values(), query.as_deref())
+    }
+
+    /// Create a new matrix by combining the scores of two matrices with the
+    /// same kind, alphabet and dimensions, e.g. to average two matrices.
+    /// Returns `Error::IncompatibleMatrices` otherwise.
+    ///
+    /// ```rust,no_run
+    /// # use parasail_rs::prelude::Matrix;
+    /// let blosum62 = Matrix::from("blosum62")?;
+    /// let blosum80 = Matrix::from("blosum80")?;
+    /// let mixed = blosum62.combine(&blosum80, |a, b| (a + b) / 2)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn combine<F: FnMut(i32, i32) -> i32>(&self, other: &Matrix, mut f: F) -> Result<Matrix> {
+        if self.kind() != other.kind()
+            || self.alphabet() != other.alphabet()
+            || self.values().len() != other.values().len()
+        {
+            return Err(Error::IncompatibleMatrices.into());
+        }
+
+        let values: Vec<i32> = self
+            .values()
+            .iter()
+            .zip(other.values())
+            .map(|(a, b)| f(*a, *b))
+            .collect();
+        self.derive(self.alphabet(), &values, self.pssm_query())
+    }
+
+    /// Create a new user matrix of the same kind.
+    fn derive(&self, alphabet: &[u8], values: &[i32], query: Option<&[u8]>) -> Result<Matrix> {
+        Matrix::from_raw(self.kind(), alphabet, values, query)
+    }
+}
@@ -0,0 +1,272 @@
+//! Merging of overlapping paired-end reads.
+//!
+//! The second read is reverse complemented and aligned against the first
+//! with semi-global alignment, with free end gaps for the parts of the reads
+//! outside of their overlap: the start of the first read and the end of the
+//! second one, or, for staggered pairs where the insert is shorter than the
+//! reads, the end of the first read and the start of the second one (adapter
+//! read-through, which is removed). Overlapping bases are merged into a
+//! consensus with recomputed qualities.
+
+use crate::fastx::FastqRecord;
+use crate::prelude::{Aligner, CigarOp, Matrix, Result};
+use crate::sequence::reverse_complement;
+
+/// Pair of reads merged into a single read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedPair {
+    /// Merged read, with the name of the first read.
+    pub record: FastqRecord,
+    /// Length of the overlap, in alignment columns.
+    pub overlap: usize,
+    /// Number of mismatches and indels in the overlap.
+    pub differences: usize,
+    /// Whether the reads overran each other (and the overrun was removed).
+    pub staggered: bool,
+}
+
+/// Overlap found by an `Overlapper`.
+struct Overlap {
+    score: i32,
+    /// Positions of the overlap start in both reads.
+    start: (usize, usize),
+    ops: Vec<(CigarOp, usize)>,
+    len: usize,
+    differences: usize,
+}
+
+/// Semi-global alignment of the first read (query) against the reverse
+/// complement of the second read (reference), with free end gaps on one
+/// side of each read.
+#[derive(Clone)]
+struct Overlapper {
+    aligner: Aligner,
+    free_start: CigarOp,
+    free_end: CigarOp,
+}
+
+impl Overlapper {
+    fn new(query_gaps: &str, ref_gaps: &str, free_start: CigarOp, free_end: CigarOp) -> Self {
+        let mut matrix = Matrix::create(b"ACGTN", 1, -1).unwrap();
+        for k in 0..5 {
+            matrix.set_value(k, 4, 0).unwrap();
+            matrix.set_value(4, k, 0).unwrap();
+        }
+        let aligner = Aligner::new()
+            .semi_global()
+            .allow_query_gaps(vec![String::from(query_gaps)])
+            .allow_ref_gaps(vec![String::from(ref_gaps)])
+            .matrix(matrix)
+            .gap_open(3)
+            .gap_extend(2)
+            .use_trace()
+            .build();
+        Overlapper {
+            aligner,
+            free_start,
+            free_end,
+        }
+    }
+
+    /// Align the first read against the reverse complement of the second
+    /// one, and return their overlap without the free end gaps.
+    fn find(&self, sequence1: &[u8], sequence2: &[u8]) -> Result<Overlap> {
+        let alignment = self.aligner.align(Some(sequence1), sequence2)?;
+        let cigar = alignment.cigar(sequence1, sequence2)?;
+
+        let mut ops = cigar.ops();
+        let (mut i, mut j) = (cigar.query_start, cigar.ref_start);
+        while let Some((&(op, len), rest)) = ops.split_first() {
+            if op != self.free_start {
+                break;
+            }
+            i += op.consumes_query() as usize * len;
+            j += op.consumes_ref() as usize * len;
+            ops = rest;
+        }
+        while let Some((&(op, _), rest)) = ops.split_last() {
+            if op != self.free_end {
+                break;
+            }
+            ops = rest;
+        }
+
+        Ok(Overlap {
+            score: alignment.get_score(),
+            start: (i, j),
+            ops: ops.to_vec(),
+            len: ops.iter().map(|(_, len)| len).sum(),
+            differences: ops
+                .iter()
+                .filter(|(op, _)| matches!(op, CigarOp::Diff | CigarOp::Ins | CigarOp::Del))
+                .map(|(_, len)| len)
+                .sum(),
+        })
+    }
+}
+
+/// Merger of overlapping paired-end reads.
+///
+/// ```rust,no_run
+/// use parasail_rs::fastx::FastqRecord;
+/// use parasail_rs::merge::PairMerger;
+///
+/// let read1 = FastqRecord::new("pair", b"ACGTTGCAAGGTCAAGCTTG", b"IIIIIIIIIIIIIIIIIIII");
+/// let read2 = FastqRecord::new("pair", b"TTGCCAGGCACAAGCTTGAC", b"IIIIIIIIIIIIIIIIIIII");
+///
+/// let mut merger = PairMerger::new();
+/// merger.min_overlap(8);
+/// let merged = merger.merge(&read1, &read2)?.expect("reads should overlap");
+/// assert_eq!(merged.overlap, 10);
+/// assert_eq!(merged.record.sequence, b"ACGTTGCAAGGTCAAGCTTGTGCCTGGCAA");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct PairMerger {
+    overlappers: [Overlapper; 2],
+    min_overlap: usize,
+    max_difference_rate: f64,
+    max_quality: u8,
+}
+
+/// Default merger requires an overlap of at least 10 bases with at most 10%
+/// differences, and caps merged qualities at Phred 41.
+impl Default for PairMerger {
+    fn default() -> Self {
+        PairMerger {
+            overlappers: [
+                Overlapper::new("prefix", "suffix", CigarOp::Ins, CigarOp::Del),
+                Overlapper::new("suffix", "prefix", CigarOp::Del, CigarOp::Ins),
+            ],
+            min_overlap: 10,
+            max_difference_rate: 0.1,
+            max_quality: 41,
+        }
+    }
+}
+
+impl PairMerger {
+    /// Create a new merger with default parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the minimum length of the overlap.
+    pub fn min_overlap(&mut self, min_overlap: usize) -> &mut Self {
+        assert!(min_overlap > 0, "Minimum overlap should be positive.");
+        self.min_overlap = min_overlap;
+        self
+    }
+
+    /// Set the maximum number of mismatches and indels per overlap column.
+    pub fn max_difference_rate(&mut self, max_difference_rate: f64) -> &mut Self {
+        assert!(
+            (0.0..1.0).contains(&max_difference_rate),
+            "Maximum difference rate should be between 0 and 1."
+        );
+        self.max_difference_rate = max_difference_rate;
+        self
+    }
+
+    /// Set the maximum Phred quality of merged bases.
+    pub fn max_quality(&mut self, max_quality: u8) -> &mut Self {
+        self.max_quality = max_quality;
+        self
+    }
+
+    /// Merge a pair of reads, or return `None` if they do not overlap.
+    ///
+    /// In the overlap, bases on which the reads agree get the sum of their
+    /// qualities (up to the maximum quality), and on mismatches the base with
+    /// the higher quality is kept, with the difference of the qualities.
+    ///
+    /// # Panics
+    /// Panics if a read does not have one quality per base.
+    pub fn merge(&self, read1: &FastqRecord, read2: &FastqRecord) -> Result<Option<MergedPair>> {
+        for read in [read1, read2] {
+            assert_eq!(
+                read.sequence.len(),
+                read.qualities.len(),
+                "There should be one quality per base."
+            );
+        }
+        if read1.is_empty() || read2.is_empty() {
+            return Ok(None);
+        }
+        let sequence1 = read1.sequence.to_ascii_uppercase();
+        let sequence2 = reverse_complement(&read2.sequence.to_ascii_uppercase());
+        let qualities2 = read2.qualities.iter().rev().copied().collect::<Vec<_>>();
+
+        let mut best: Option<Overlap> = None;
+        for overlapper in &self.overlappers {
+            let overlap = overlapper.find(&sequence1, &sequence2)?;
+            let max_differences = (overlap.len as f64 * self.max_difference_rate).floor() as usize;
+            if overlap.len < self.min_overlap || overlap.differences > max_differences {
+                continue;
+            }
+            if best.as_ref().is_none_or(|best| overlap.score > best.score) {
+                best = Some(overlap);
+            }
+        }
+        let Some(overlap) = best else {
+            return Ok(None);
+        };
+
+        // first read before the overlap
+        let (mut i, mut j) = overlap.start;
+        let mut sequence = sequence1[..i].to_vec();
+        let mut qualities = read1.qualities[..i].to_vec();
+        for (op, len) in overlap.ops {
+            for _ in 0..len {
+                let (base, quality) = match op {
+                    CigarOp::Ins => (sequence1[i], read1.qualities[i]),
+                    CigarOp::Del => (sequence2[j], qualities2[j]),
+                    _ => self.consensus(
+                        (sequence1[i], read1.qualities[i]),
+                        (sequence2[j], qualities2[j]),
+                    ),
+                };
+                sequence.push(base);
+                qualities.push(quality);
+                i += op.consumes_query() as usize;
+                j += op.consumes_ref() as usize;
+            }
+        }
+        // second read after the overlap
+        sequence.extend_from_slice(&sequence2[j..]);
+        qualities.extend_from_slice(&qualities2[j..]);
+
+        Ok(Some(MergedPair {
+            record: FastqRecord {
+                id: read1.id.clone(),
+                sequence,
+                qualities,
+            },
+            overlap: overlap.len,
+            differences: overlap.differences,
+            staggered: overlap.start.1 > 0 || i < sequence1.len(),
+        }))
+    }
+
+    /// Consensus of two aligned bases with Phred+33 qualities.
+    fn consensus(&self, (base1, quality1): (u8, u8), (base2, quality2): (u8, u8)) -> (u8, u8) {
+        let phred1 = quality1.saturating_sub(33);
+        let phred2 = quality2.saturating_sub(33);
+        if base1 == base2 {
+            (
+                base1,
+                phred1.saturating_add(phred2).min(self.max_quality) + 33,
+            )
+        } else if base2 == b'N' || (base1 != b'N' && phred1 >= phred2) {
+            (base1, (phred1 - phred2.min(phred1)).max(2) + 33)
+        } else {
+            (base2, (phred2 - phred1.min(phred2)).max(2) + 33)
+        }
+    }
+}
+
+/// Merge a pair of reads with the default `PairMerger`, or return `None` if
+/// they do not overlap.
+pub fn merge_pairs(read1: &FastqRecord, read2: &FastqRecord) -> Result<Option<MergedPair>> {
+    PairMerger::new().merge(read1, read2)
+}
@@ -1,8 +1,8 @@
 pub use crate::aligner::{Aligner, AlignerBuilder};
-pub use crate::alignment::table::{Table, TraceFlags, TracebackTable};
-pub use crate::alignment::{Alignment, SSWResult, Traceback};
+pub use crate::alignment::table::{Table, TraceFlags, TraceOp, TraceStep, TracebackTable};
+pub use crate::alignment::{Alignment, AlignmentRecord, Cigar, CigarOp, SSWResult, Traceback};
 pub use crate::error::{Error, Result};
-pub use crate::matrix::Matrix;
+pub use crate::matrix::{BuiltinMatrix, Matrix, MatrixKind};
 pub use crate::profile::Profile;
 
 #[derive(Debug)]
@@ -0,0 +1,300 @@
+//! Nucleotide sequence utilities, and strand-aware and circular alignment
+//! and search results.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io::Write;
+
+use crate::alignment::Error;
+use crate::prelude::{Alignment, Cigar, CigarOp, Result};
+
+/// Strand of the reference a query aligned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Strand {
+    Forward,
+    Reverse,
+}
+
+impl fmt::Display for Strand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strand::Forward => write!(f, "+"),
+            Strand::Reverse => write!(f, "-"),
+        }
+    }
+}
+
+/// Complement of a nucleotide, including IUPAC ambiguity codes (e.g. `R`
+/// for `Y`) and RNA bases (`U` for `A`). Case is preserved and other symbols
+/// (e.g. `N` or gaps) are returned as is.
+pub fn complement(base: u8) -> u8 {
+    let complement = match base.to_ascii_uppercase() {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' | b'U' => b'A',
+        b'R' => b'Y',
+        b'Y' => b'R',
+        b'K' => b'M',
+        b'M' => b'K',
+        b'B' => b'V',
+        b'V' => b'B',
+        b'D' => b'H',
+        b'H' => b'D',
+        _ => return base,
+    };
+    if base.is_ascii_lowercase() {
+        complement.to_ascii_lowercase()
+    } else {
+        complement
+    }
+}
+
+/// Check if a base matches an IUPAC nucleotide code.
+pub(crate) fn iupac_matches(code: u8, base: u8) -> bool {
+    let bases: &[u8] = match code.to_ascii_uppercase() {
+        b'A' => b"A",
+        b'C' => b"C",
+        b'G' => b"G",
+        b'T' | b'U' => b"T",
+        b'R' => b"AG",
+        b'Y' => b"CT",
+        b'S' => b"CG",
+        b'W' => b"AT",
+        b'K' => b"GT",
+        b'M' => b"AC",
+        b'B' => b"CGT",
+        b'D' => b"AGT",
+        b'H' => b"ACT",
+        b'V' => b"ACG",
+        b'N' => b"ACGT",
+        _ => b"",
+    };
+    bases.contains(&base.to_ascii_uppercase())
+}
+
+/// Reverse complement of a nucleotide sequence (see `complement`).
+///
+/// ```rust,no_run
+/// use parasail_rs::sequence::reverse_complement;
+///
+/// assert_eq!(reverse_complement(b"ACGTRYN-acgu"), b"acgt-NRYACGT");
+/// ```
+pub fn reverse_complement(sequence: &[u8]) -> Vec<u8> {
+    sequence
+        .iter()
+        .rev()
+        .map(|&base| complement(base))
+        .collect()
+}
+
+/// Alignment of a query, or of its reverse complement, against the forward
+/// strand of a reference, as returned by `Aligner::align_both_strands`.
+///
+/// Reference coordinates and CIGARs are in forward reference space. As in
+/// SAM, query coordinates of reverse strand alignments are positions in the
+/// reverse complement of the query.
+#[derive(Debug)]
+pub struct StrandedAlignment {
+    pub strand: Strand,
+    pub alignment: Alignment,
+}
+
+impl StrandedAlignment {
+    /// Alignment score.
+    pub fn score(&self) -> i32 {
+        self.alignment.get_score()
+    }
+
+    /// Query in the orientation it was aligned in, i.e. its reverse
+    /// complement for reverse strand alignments.
+    pub fn oriented_query<'a>(&self, query: &'a [u8]) -> Cow<'a, [u8]> {
+        match self.strand {
+            Strand::Forward => Cow::Borrowed(query),
+            Strand::Reverse => Cow::Owned(reverse_complement(query)),
+        }
+    }
+
+    /// CIGAR of the alignment against the forward reference, given the query
+    /// as passed to the aligner. Requires traceback to be enabled.
+    pub fn cigar(&self, query: &[u8], reference: &[u8]) -> Result<Cigar> {
+        self.alignment.cigar(&self.oriented_query(query), reference)
+    }
+}
+
+/// Occurrence of a pattern in a text, as returned by `Aligner::find_all`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternHit {
+    pub strand: Strand,
+    /// Start position (0-based) of the hit in the text.
+    pub start: usize,
+    /// End position (0-based, exclusive) of the hit in the text.
+    pub end: usize,
+    pub score: i32,
+    /// CIGAR of the pattern (reverse complemented for reverse strand hits)
+    /// against the text, starting at `start`.
+    pub cigar: Cigar,
+}
+
+/// Alignment against a circular reference, as returned by
+/// `Aligner::align_circular`.
+///
+/// The alignment is against the reference doubled, and its reference
+/// coordinates are reported modulo the reference length. As for
+/// `StrandedAlignment`, query coordinates of reverse strand alignments are
+/// positions in the reverse complement of the query.
+#[derive(Debug)]
+pub struct CircularAlignment {
+    pub strand: Strand,
+    /// Alignment against the reference doubled.
+    pub alignment: Alignment,
+    pub(crate) ref_len: usize,
+}
+
+impl CircularAlignment {
+    /// Alignment score.
+    pub fn score(&self) -> i32 {
+        self.alignment.get_score()
+    }
+
+    /// Reference end position (0-based, inclusive) of the alignment.
+    pub fn ref_end(&self) -> usize {
+        self.alignment.get_end_ref() as usize % self.ref_len
+    }
+
+    /// CIGAR of the alignment, starting at a position within the reference
+    /// and possibly running past its origin. Reference end gaps are removed
+    /// and query end gaps are soft clipped. Requires traceback to be enabled.
+    pub fn cigar(&self, query: &[u8], reference: &[u8]) -> Result<Cigar> {
+        let query = match self.strand {
+            Strand::Forward => Cow::Borrowed(query),
+            Strand::Reverse => Cow::Owned(reverse_complement(query)),
+        };
+        let doubled = [reference, reference].concat();
+        let cigar = self.alignment.cigar(&query, &doubled)?;
+
+        let mut ops = cigar.ops();
+        let mut ref_start = cigar.ref_start;
+        while let Some((&(CigarOp::Del, len), rest)) = ops.split_first() {
+            ref_start += len;
+            ops = rest;
+        }
+        while let Some((&(CigarOp::Del, _), rest)) = ops.split_last() {
+            ops = rest;
+        }
+
+        let mut trimmed = Cigar::new(cigar.query_start, ref_start % self.ref_len, &[]);
+        for (k, &(op, len)) in ops.iter().enumerate() {
+            let end_gap = op == CigarOp::Ins && (k == 0 || k == ops.len() - 1);
+            trimmed.push(if end_gap { CigarOp::SoftClip } else { op }, len);
+        }
+        Ok(trimmed)
+    }
+
+    /// Split the CIGAR of the alignment at the origin of the reference, into
+    /// one CIGAR for each side of the origin (only one if the alignment does
+    /// not span it). Each CIGAR soft clips the rest of the query, as for SAM
+    /// supplementary alignments. Requires traceback to be enabled.
+    pub fn split_cigar(&self, query: &[u8], reference: &[u8]) -> Result<Vec<Cigar>> {
+        let cigar = self.cigar(query, reference)?;
+        let mut pieces = Vec::new();
+        let mut piece = Cigar::new(0, cigar.ref_start, &[]);
+        piece.push(CigarOp::SoftClip, cigar.query_start);
+        let (mut i, mut j) = (cigar.query_start, cigar.ref_start);
+
+        for &(op, len) in cigar.ops() {
+            let mut len = len;
+            while len > 0 {
+                // start a new piece at the origin
+                if j == self.ref_len && op.consumes_ref() {
+                    piece.push(CigarOp::SoftClip, query.len() - i);
+                    pieces.push(piece);
+                    piece = Cigar::new(0, 0, &[]);
+                    piece.push(CigarOp::SoftClip, i);
+                }
+                let step = if op.consumes_ref() && j < self.ref_len {
+                    len.min(self.ref_len - j)
+                } else {
+                    len
+                };
+                piece.push(op, step);
+                i += op.consumes_query() as usize * step;
+                j += op.consumes_ref() as usize * step;
+                len -= step;
+            }
+        }
+        piece.push(CigarOp::SoftClip, query.len() - i);
+        pieces.push(piece);
+
+        Ok(pieces)
+    }
+
+    /// Write the alignment as SAM records: a primary record and, if the
+    /// alignment spans the origin, a supplementary record, linked with `SA`
+    /// tags. Sequences are written in the orientation of the alignment, and
+    /// qualities and mapping qualities are not available. Requires traceback
+    /// to be enabled.
+    pub fn write_sam<W: Write>(
+        &self,
+        query_name: &str,
+        ref_name: &str,
+        query: &[u8],
+        reference: &[u8],
+        mut writer: W,
+    ) -> Result<()> {
+        let pieces = self.split_cigar(query, reference)?;
+        let sequence = match self.strand {
+            Strand::Forward => String::from_utf8_lossy(query).into_owned(),
+            Strand::Reverse => String::from_utf8_lossy(&reverse_complement(query)).into_owned(),
+        };
+        let distances = pieces
+            .iter()
+            .map(|piece| {
+                piece
+                    .ops()
+                    .iter()
+                    .filter(|(op, _)| matches!(op, CigarOp::Diff | CigarOp::Ins | CigarOp::Del))
+                    .map(|(_, len)| len)
+                    .sum::<usize>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut records = String::new();
+        for (k, piece) in pieces.iter().enumerate() {
+            let mut flag = 0;
+            if self.strand == Strand::Reverse {
+                flag |= 16;
+            }
+            if k > 0 {
+                flag |= 2048;
+            }
+            records += &format!(
+                "{query_name}\t{flag}\t{ref_name}\t{}\t255\t{piece}\t*\t0\t0\t{sequence}\t*\tAS:i:{}\tNM:i:{}",
+                piece.ref_start + 1,
+                self.score(),
+                distances[k],
+            );
+            if pieces.len() > 1 {
+                records += "\tSA:Z:";
+                for (l, (other, distance)) in pieces.iter().zip(&distances).enumerate() {
+                    if l != k {
+                        records += &format!(
+                            "{ref_name},{},{},{other},255,{distance};",
+                            other.ref_start + 1,
+                            self.strand
+                        );
+                    }
+                }
+            }
+            records.push('\n');
+        }
+
+        writer.write_all(records.as_bytes()).map_err(Error::Io)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,330 @@
+//! Translation of nucleotide sequences with the standard and alternative
+//! genetic codes, and results of translated searches.
+
+use std::ops::Range;
+
+use crate::prelude::{Alignment, Result};
+use crate::sequence::reverse_complement;
+
+/// NCBI genetic codes (translation table id, name and amino acids of the
+/// codons, with T, C, A, G as first, second and third base in this order).
+const CODES: [(u8, &str, &[u8; 64]); 19] = [
+    (
+        1,
+        "Standard",
+        b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        2,
+        "Vertebrate Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+    ),
+    (
+        3,
+        "Yeast Mitochondrial",
+        b"FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        4,
+        "Mold, Protozoan, and Coelenterate Mitochondrial and Mycoplasma/Spiroplasma",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        5,
+        "Invertebrate Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+    ),
+    (
+        6,
+        "Ciliate, Dasycladacean and Hexamita Nuclear",
+        b"FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        9,
+        "Echinoderm and Flatworm Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+    ),
+    (
+        10,
+        "Euplotid Nuclear",
+        b"FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        11,
+        "Bacterial, Archaeal and Plant Plastid",
+        b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        12,
+        "Alternative Yeast Nuclear",
+        b"FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        13,
+        "Ascidian Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+    ),
+    (
+        14,
+        "Alternative Flatworm Mitochondrial",
+        b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+    ),
+    (
+        16,
+        "Chlorophycean Mitochondrial",
+        b"FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        21,
+        "Trematode Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+    ),
+    (
+        22,
+        "Scenedesmus obliquus Mitochondrial",
+        b"FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        23,
+        "Thraustochytrium Mitochondrial",
+        b"FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        24,
+        "Rhabdopleuridae Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+    ),
+    (
+        25,
+        "Candidate Division SR1 and Gracilibacteria",
+        b"FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+    (
+        26,
+        "Pachysolen tannophilus Nuclear",
+        b"FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+    ),
+];
+
+/// Genetic code used to translate codons into amino acids.
+///
+/// ```rust,no_run
+/// use parasail_rs::translate::GeneticCode;
+///
+/// let standard = GeneticCode::standard();
+/// assert_eq!(standard.translate(b"ATGTGGTGA"), b"MW*");
+/// // TGA codes for tryptophan in vertebrate mitochondria
+/// let mitochondrial = GeneticCode::from_id(2).unwrap();
+/// assert_eq!(mitochondrial.translate(b"ATGTGGTGA"), b"MWW");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneticCode {
+    id: u8,
+    name: &'static str,
+    amino_acids: &'static [u8; 64],
+}
+
+impl Default for GeneticCode {
+    fn default() -> Self {
+        GeneticCode::standard()
+    }
+}
+
+impl GeneticCode {
+    /// Standard genetic code (NCBI translation table 1).
+    pub fn standard() -> Self {
+        GeneticCode::from_id(1).unwrap()
+    }
+
+    /// Get a genetic code from its NCBI translation table id, e.g. 2 for the
+    /// vertebrate mitochondrial code or 11 for the bacterial code. Returns
+    /// `None` for unknown ids.
+    pub fn from_id(id: u8) -> Option<Self> {
+        CODES
+            .iter()
+            .find(|code| code.0 == id)
+            .map(|&(id, name, amino_acids)| GeneticCode {
+                id,
+                name,
+                amino_acids,
+            })
+    }
+
+    /// NCBI translation table id.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// NCBI translation table name.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Translate a codon (case-insensitive, `U` read as `T`). Codons with
+    /// IUPAC ambiguity codes are translated if all the codons they stand for
+    /// code for the same amino acid (e.g. `GCN` for alanine), and to `X`
+    /// otherwise. Stop codons are translated to `*`.
+    ///
+    /// # Panics
+    /// Panics if the codon is not 3 bases long.
+    pub fn translate_codon(&self, codon: &[u8]) -> u8 {
+        assert_eq!(codon.len(), 3, "Codons should be 3 bases long.");
+        let bases = [bases(codon[0]), bases(codon[1]), bases(codon[2])];
+        let mut amino_acid = None;
+        for first in 0..4 {
+            for second in 0..4 {
+                for third in 0..4 {
+                    if bases[0] & (1 << first) == 0
+                        || bases[1] & (1 << second) == 0
+                        || bases[2] & (1 << third) == 0
+                    {
+                        continue;
+                    }
+                    let aa = self.amino_acids[first * 16 + second * 4 + third];
+                    match amino_acid {
+                        Some(other) if other != aa => return b'X',
+                        _ => amino_acid = Some(aa),
+                    }
+                }
+            }
+        }
+        amino_acid.unwrap_or(b'X')
+    }
+
+    /// Translate the complete codons of a sequence in its first frame.
+    pub fn translate(&self, sequence: &[u8]) -> Vec<u8> {
+        sequence
+            .chunks_exact(3)
+            .map(|codon| self.translate_codon(codon))
+            .collect()
+    }
+
+    /// Translate a sequence in a reading frame, from 1 to 3 on the forward
+    /// strand or from -1 to -3 on the reverse strand (BLAST convention).
+    ///
+    /// # Panics
+    /// Panics if the frame is not within -3 to -1 or 1 to 3.
+    pub fn translate_frame(&self, sequence: &[u8], frame: i8) -> Translation {
+        assert!(
+            (1..=3).contains(&frame.abs()),
+            "Frames should be within -3 to -1 or 1 to 3."
+        );
+        let offset = (frame.unsigned_abs() - 1) as usize;
+        let protein = if frame > 0 {
+            self.translate(sequence.get(offset..).unwrap_or_default())
+        } else {
+            let reverse = reverse_complement(sequence);
+            self.translate(reverse.get(offset..).unwrap_or_default())
+        };
+
+        Translation {
+            frame,
+            protein,
+            nucleotide_len: sequence.len(),
+        }
+    }
+
+    /// Translate a sequence in the six reading frames, in the order 1, 2, 3,
+    /// -1, -2 and -3.
+    pub fn six_frames(&self, sequence: &[u8]) -> Vec<Translation> {
+        [1, 2, 3, -1, -2, -3]
+            .into_iter()
+            .map(|frame| self.translate_frame(sequence, frame))
+            .collect()
+    }
+}
+
+/// Translation of a nucleotide sequence in a reading frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translation {
+    /// Reading frame, from 1 to 3 on the forward strand or from -1 to -3 on
+    /// the reverse strand.
+    pub frame: i8,
+    pub protein: Vec<u8>,
+    nucleotide_len: usize,
+}
+
+impl Translation {
+    /// Map a range of residues of the translation to the range of
+    /// nucleotides coding for them, as 0-based positions on the forward
+    /// strand.
+    ///
+    /// ```rust,no_run
+    /// use parasail_rs::translate::GeneticCode;
+    ///
+    /// let translation = GeneticCode::standard().translate_frame(b"TGGCCATA", -2);
+    /// assert_eq!(translation.protein, b"MA");
+    /// assert_eq!(translation.nucleotide_range(0..1), 4..7);
+    /// ```
+    pub fn nucleotide_range(&self, residues: Range<usize>) -> Range<usize> {
+        let offset = (self.frame.unsigned_abs() - 1) as usize;
+        let (start, end) = (offset + 3 * residues.start, offset + 3 * residues.end);
+        if self.frame > 0 {
+            start..end
+        } else {
+            self.nucleotide_len - end..self.nucleotide_len - start
+        }
+    }
+}
+
+/// Alignment against (or of) a nucleotide sequence translated in a reading
+/// frame, as returned by `Aligner::align_translated` and
+/// `Aligner::align_translated_query`.
+#[derive(Debug)]
+pub struct TranslatedAlignment {
+    /// Reading frame of the translated sequence.
+    pub frame: i8,
+    /// Translation of the nucleotide sequence in the frame.
+    pub translation: Translation,
+    /// Alignment with the translation.
+    pub alignment: Alignment,
+    pub(crate) query_translated: bool,
+}
+
+impl TranslatedAlignment {
+    /// Alignment score.
+    pub fn score(&self) -> i32 {
+        self.alignment.get_score()
+    }
+
+    /// Range of the nucleotide sequence (0-based, on the forward strand)
+    /// covered by the alignment, given the protein sequence it was aligned
+    /// with. Requires traceback to be enabled.
+    pub fn nucleotide_range(&self, protein: &[u8]) -> Result<Range<usize>> {
+        let translated = &self.translation.protein;
+        let residues = if self.query_translated {
+            let cigar = self.alignment.cigar(translated, protein)?;
+            cigar.query_start..cigar.query_start + cigar.query_len()
+        } else {
+            let cigar = self.alignment.cigar(protein, translated)?;
+            cigar.ref_start..cigar.ref_start + cigar.ref_len()
+        };
+        Ok(self.translation.nucleotide_range(residues))
+    }
+}
+
+/// Bitset of the bases (T, C, A and G) a nucleotide code stands for.
+fn bases(base: u8) -> u8 {
+    const T: u8 = 1;
+    const C: u8 = 2;
+    const A: u8 = 4;
+    const G: u8 = 8;
+    match base.to_ascii_uppercase() {
+        b'T' | b'U' => T,
+        b'C' => C,
+        b'A' => A,
+        b'G' => G,
+        b'R' => A | G,
+        b'Y' => C | T,
+        b'S' => G | C,
+        b'W' => A | T,
+        b'K' => G | T,
+        b'M' => A | C,
+        b'B' => C | G | T,
+        b'D' => A | G | T,
+        b'H' => A | C | T,
+        b'V' => A | C | G,
+        b'N' => A | C | G | T,
+        _ => 0,
+    }
+}
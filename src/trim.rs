@@ -0,0 +1,388 @@
+//! Adapter and quality trimming of reads.
+//!
+//! Adapters and primers are found by semi-global alignment against the read,
+//! with free end gaps on the side of the read the adapter can run off: a
+//! 3' adapter may be cut short by the end of the read, and a 5' adapter by
+//! its start. Matches are accepted if enough adapter bases are aligned and
+//! the number of errors (substitutions and indels) is below a maximum error
+//! rate, and the read is trimmed from the start of a 3' adapter or up to the
+//! end of a 5' adapter. Quality trimming of the 3' end is done before
+//! adapter trimming.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+
+use crate::fastx::FastqRecord;
+use crate::prelude::{Aligner, CigarOp, Result};
+
+/// End of the read an adapter is ligated to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum AdapterEnd {
+    /// Adapter after the insert, removed with everything after it.
+    ThreePrime,
+    /// Adapter or primer before the insert, removed with everything before it.
+    FivePrime,
+}
+
+impl fmt::Display for AdapterEnd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdapterEnd::ThreePrime => write!(f, "3'"),
+            AdapterEnd::FivePrime => write!(f, "5'"),
+        }
+    }
+}
+
+/// Quality trimming of the 3' end of reads, with a Phred quality cutoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityTrim {
+    /// Modified Mott algorithm (as in BWA and cutadapt): trim the 3' suffix
+    /// maximizing the sum of `cutoff - quality`.
+    Mott { cutoff: u8 },
+    /// Sliding window (as in Trimmomatic): trim from the start of the first
+    /// window whose mean quality is below the cutoff.
+    SlidingWindow { size: usize, cutoff: u8 },
+}
+
+impl QualityTrim {
+    /// Length of the read to keep, given its Phred+33 qualities.
+    fn keep(&self, qualities: &[u8]) -> usize {
+        let phred = |q: u8| q.saturating_sub(33) as i64;
+        match *self {
+            QualityTrim::Mott { cutoff } => {
+                let (mut sum, mut max, mut keep) = (0, 0, qualities.len());
+                for (i, &q) in qualities.iter().enumerate().rev() {
+                    sum += cutoff as i64 - phred(q);
+                    if sum < 0 {
+                        break;
+                    }
+                    if sum > max {
+                        max = sum;
+                        keep = i;
+                    }
+                }
+                keep
+            }
+            QualityTrim::SlidingWindow { size, cutoff } => {
+                let size = size.clamp(1, qualities.len().max(1));
+                qualities
+                    .windows(size)
+                    .position(|window| {
+                        window.iter().map(|&q| phred(q)).sum::<i64>() < cutoff as i64 * size as i64
+                    })
+                    .unwrap_or(qualities.len())
+            }
+        }
+    }
+}
+
+/// Adapter or primer sequence to remove from reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Adapter {
+    pub name: String,
+    pub sequence: Vec<u8>,
+    pub end: AdapterEnd,
+}
+
+/// Adapter found in a read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdapterMatch {
+    /// Name of the adapter.
+    pub adapter: String,
+    pub end: AdapterEnd,
+    /// Range of the adapter in the read (0-based, end-exclusive).
+    pub range: Range<usize>,
+    /// Number of adapter bases aligned to the read.
+    pub overlap: usize,
+    /// Number of substitutions and indels in the alignment.
+    pub errors: usize,
+    pub score: i32,
+}
+
+/// Summary of the trimming of a read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrimReport {
+    pub id: String,
+    /// Length of the untrimmed read.
+    pub length: usize,
+    /// Number of bases removed from the 3' end by quality trimming.
+    pub quality_trimmed: usize,
+    /// Adapter found, if any.
+    pub adapter: Option<AdapterMatch>,
+    /// Range of the untrimmed read that was kept.
+    pub kept: Range<usize>,
+}
+
+/// Adapter alignment found by a `Search`.
+struct Hit {
+    range: Range<usize>,
+    overlap: usize,
+    errors: usize,
+    score: i32,
+}
+
+/// Semi-global alignment of an adapter (query) against reads (reference),
+/// with the end gaps that are free.
+#[derive(Clone)]
+struct Search {
+    aligner: Aligner,
+    free_start: CigarOp,
+    free_end: CigarOp,
+}
+
+impl Search {
+    fn new(query_gaps: &[&str], ref_gaps: &[&str], free_start: CigarOp, free_end: CigarOp) -> Self {
+        let aligner = Aligner::new()
+            .semi_global()
+            .allow_query_gaps(query_gaps.iter().map(|&gap| String::from(gap)).collect())
+            .allow_ref_gaps(ref_gaps.iter().map(|&gap| String::from(gap)).collect())
+            .gap_open(2)
+            .gap_extend(2)
+            .use_trace()
+            .build();
+        Search {
+            aligner,
+            free_start,
+            free_end,
+        }
+    }
+
+    /// Align the adapter against the read, and return the aligned part of
+    /// the adapter, if any.
+    fn find(&self, adapter: &[u8], read: &[u8]) -> Result<Option<Hit>> {
+        let alignment = self.aligner.align(Some(adapter), read)?;
+        let cigar = alignment.cigar(adapter, read)?;
+
+        let mut ops = cigar.ops();
+        let mut start = cigar.ref_start;
+        while let Some((&(op, len), rest)) = ops.split_first() {
+            if op != self.free_start {
+                break;
+            }
+            start += op.consumes_ref() as usize * len;
+            ops = rest;
+        }
+        while let Some((&(op, _), rest)) = ops.split_last() {
+            if op != self.free_end {
+                break;
+            }
+            ops = rest;
+        }
+        if ops.is_empty() {
+            return Ok(None);
+        }
+
+        let (mut overlap, mut errors, mut end) = (0, 0, start);
+        for &(op, len) in ops {
+            overlap += op.consumes_query() as usize * len;
+            end += op.consumes_ref() as usize * len;
+            if matches!(op, CigarOp::Diff | CigarOp::Ins | CigarOp::Del) {
+                errors += len;
+            }
+        }
+        Ok(Some(Hit {
+            range: start..end,
+            overlap,
+            errors,
+            score: alignment.get_score(),
+        }))
+    }
+}
+
+/// Trimmer of adapters, primers and low quality ends of reads.
+///
+/// ```rust,no_run
+/// use parasail_rs::fastx::FastqRecord;
+/// use parasail_rs::trim::{AdapterEnd, QualityTrim, Trimmer};
+///
+/// let mut trimmer = Trimmer::new();
+/// trimmer
+///     .adapter("truseq", b"AGATCGGAAGAGC", AdapterEnd::ThreePrime)
+///     .quality_trim(QualityTrim::Mott { cutoff: 20 });
+///
+/// let read = FastqRecord::new("read1", b"ACGTTGCAAGGTCAAGATCGG", b"IIIIIIIIIIIIIIIIIIIII");
+/// let (trimmed, report) = trimmer.trim(&read)?;
+/// assert_eq!(trimmed.sequence, b"ACGTTGCAAGGTCA");
+/// assert_eq!(report.adapter.unwrap().overlap, 7);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct Trimmer {
+    adapters: Vec<(Adapter, [Search; 2])>,
+    min_overlap: usize,
+    max_error_rate: f64,
+    quality_trim: Option<QualityTrim>,
+}
+
+/// Default trimmer has no adapters or quality trimming, a minimum overlap
+/// of 3 bases and a maximum error rate of 10%.
+impl Default for Trimmer {
+    fn default() -> Self {
+        Trimmer {
+            adapters: Vec::new(),
+            min_overlap: 3,
+            max_error_rate: 0.1,
+            quality_trim: None,
+        }
+    }
+}
+
+impl Trimmer {
+    /// Create a new trimmer with default parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an adapter to remove. When several adapters are given, the best
+    /// scoring match is removed.
+    pub fn adapter(&mut self, name: &str, sequence: &[u8], end: AdapterEnd) -> &mut Self {
+        let full = Search::new(&[], &["prefix", "suffix"], CigarOp::Del, CigarOp::Del);
+        let partial = match end {
+            AdapterEnd::ThreePrime => {
+                Search::new(&["suffix"], &["prefix"], CigarOp::Del, CigarOp::Ins)
+            }
+            AdapterEnd::FivePrime => {
+                Search::new(&["prefix"], &["suffix"], CigarOp::Ins, CigarOp::Del)
+            }
+        };
+        let adapter = Adapter {
+            name: String::from(name),
+            sequence: sequence.to_ascii_uppercase(),
+            end,
+        };
+        self.adapters.push((adapter, [full, partial]));
+        self
+    }
+
+    /// Set the minimum number of adapter bases aligned to the read.
+    pub fn min_overlap(&mut self, min_overlap: usize) -> &mut Self {
+        assert!(min_overlap > 0, "Minimum overlap should be positive.");
+        self.min_overlap = min_overlap;
+        self
+    }
+
+    /// Set the maximum number of errors per aligned adapter base.
+    pub fn max_error_rate(&mut self, max_error_rate: f64) -> &mut Self {
+        assert!(
+            (0.0..1.0).contains(&max_error_rate),
+            "Maximum error rate should be between 0 and 1."
+        );
+        self.max_error_rate = max_error_rate;
+        self
+    }
+
+    /// Set quality trimming of the 3' end of reads.
+    pub fn quality_trim(&mut self, quality_trim: QualityTrim) -> &mut Self {
+        self.quality_trim = Some(quality_trim);
+        self
+    }
+
+    /// Get the adapters to remove.
+    pub fn adapters(&self) -> impl Iterator<Item = &Adapter> {
+        self.adapters.iter().map(|(adapter, _)| adapter)
+    }
+
+    /// Trim a read, and return the trimmed read and a report of what was
+    /// removed.
+    pub fn trim(&self, record: &FastqRecord) -> Result<(FastqRecord, TrimReport)> {
+        let mut kept = 0..record.len();
+        if let Some(quality_trim) = &self.quality_trim {
+            kept.end = quality_trim.keep(&record.qualities);
+        }
+        let quality_trimmed = record.len() - kept.end;
+
+        let read = record.sequence[kept.clone()].to_ascii_uppercase();
+        // reads emptied by quality trimming have no adapter left to search
+        let adapters = if read.is_empty() {
+            &[][..]
+        } else {
+            &self.adapters[..]
+        };
+        let mut best: Option<AdapterMatch> = None;
+        for (adapter, searches) in adapters {
+            for search in searches {
+                let Some(hit) = search.find(&adapter.sequence, &read)? else {
+                    continue;
+                };
+                let max_errors = (hit.overlap as f64 * self.max_error_rate).floor() as usize;
+                if hit.overlap < self.min_overlap || hit.errors > max_errors {
+                    continue;
+                }
+                if best
+                    .as_ref()
+                    .is_some_and(|best| (best.score, best.overlap) >= (hit.score, hit.overlap))
+                {
+                    continue;
+                }
+                best = Some(AdapterMatch {
+                    adapter: adapter.name.clone(),
+                    end: adapter.end,
+                    range: kept.start + hit.range.start..kept.start + hit.range.end,
+                    overlap: hit.overlap,
+                    errors: hit.errors,
+                    score: hit.score,
+                });
+            }
+        }
+
+        if let Some(found) = &best {
+            match found.end {
+                AdapterEnd::ThreePrime => kept.end = found.range.start,
+                AdapterEnd::FivePrime => kept.start = found.range.end,
+            }
+        }
+
+        let trimmed = FastqRecord {
+            id: record.id.clone(),
+            sequence: record.sequence[kept.clone()].to_vec(),
+            qualities: record.qualities[kept.clone()].to_vec(),
+        };
+        let report = TrimReport {
+            id: String::from(record.name()),
+            length: record.len(),
+            quality_trimmed,
+            adapter: best,
+            kept,
+        };
+        Ok((trimmed, report))
+    }
+}
+
+/// Write trim reports as a tab-separated table with a header, one row per
+/// read. Columns for the adapter are `.` if none was found.
+pub fn write_reports<W: Write>(reports: &[TrimReport], mut writer: W) -> io::Result<()> {
+    writeln!(
+        writer,
+        "id\tlength\tquality_trimmed\tadapter\tend\tadapter_start\tadapter_end\toverlap\terrors\tkept_start\tkept_end"
+    )?;
+    for report in reports {
+        write!(
+            writer,
+            "{}\t{}\t{}\t",
+            report.id, report.length, report.quality_trimmed
+        )?;
+        match &report.adapter {
+            Some(found) => write!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}\t{}\t",
+                found.adapter,
+                found.end,
+                found.range.start,
+                found.range.end,
+                found.overlap,
+                found.errors
+            )?,
+            None => write!(writer, ".\t.\t.\t.\t.\t.\t")?,
+        }
+        writeln!(writer, "{}\t{}", report.kept.start, report.kept.end)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,404 @@
+//! HGVS-style coding (`c.`) and protein (`p.`) notation of alignment
+//! differences against a coding sequence.
+
+use super::{edits, Edit};
+use crate::prelude::{Alignment, Result};
+use crate::translate::GeneticCode;
+
+/// Effect of a change on the protein.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CodingEffect {
+    /// No amino acid change.
+    Synonymous,
+    /// Single amino acid substitution.
+    Missense,
+    /// Premature stop codon.
+    Nonsense,
+    /// Loss of the stop codon.
+    StopLoss,
+    /// In-frame insertion, deletion or multiple amino acid substitution.
+    InFrame,
+    /// Insertion or deletion shifting the reading frame.
+    Frameshift,
+    /// Change of the start codon, or of bases before and within the CDS,
+    /// with an unpredictable effect (`p.?`).
+    Unknown,
+    /// Change outside of the CDS.
+    Noncoding,
+}
+
+/// Difference between a coding query and a coding reference, in HGVS-like
+/// notation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodingChange {
+    pub effect: CodingEffect,
+    /// Coding DNA notation, e.g. `c.35G>A`.
+    pub coding: String,
+    /// Protein notation, e.g. `p.Gly12Asp`.
+    pub protein: String,
+    /// 1-based position of the first changed (or, for synonymous changes,
+    /// mutated) amino acid, if within the CDS.
+    pub codon: Option<usize>,
+}
+
+/// Annotator of changes in a coding sequence.
+///
+/// ```rust,no_run
+/// use parasail_rs::prelude::Aligner;
+/// use parasail_rs::variants::CodingAnnotator;
+///
+/// // 5' UTR, then ATG GGT GCA ... TAA
+/// let reference = b"CCCATGGGTGCAAAATGGTACGGCTAA";
+/// let variant = b"CCCATGGATGCAAAATGGTACGGCTAA";
+/// let aligner = Aligner::new().gap_open(5).gap_extend(1).use_trace().build();
+/// let result = aligner.align(Some(variant), reference)?;
+///
+/// let changes = CodingAnnotator::new()
+///     .cds_start(3)
+///     .annotate(&result, variant, reference)?;
+/// assert_eq!(changes[0].coding, "c.5G>A");
+/// assert_eq!(changes[0].protein, "p.Gly2Asp");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CodingAnnotator {
+    cds_start: usize,
+    genetic_code: GeneticCode,
+}
+
+impl CodingAnnotator {
+    /// Create a new annotator for a CDS starting at the first reference base,
+    /// with the standard genetic code.
+    pub fn new() -> Self {
+        CodingAnnotator::default()
+    }
+
+    /// Set the 0-based reference position of the first base of the start
+    /// codon, which sets the reading frame. The CDS ends at the first
+    /// in-frame stop codon, or at the end of the reference.
+    pub fn cds_start(&mut self, cds_start: usize) -> &mut Self {
+        self.cds_start = cds_start;
+        self
+    }
+
+    /// Set the genetic code used to translate codons.
+    pub fn genetic_code(&mut self, genetic_code: GeneticCode) -> &mut Self {
+        self.genetic_code = genetic_code;
+        self
+    }
+
+    /// Annotate the differences of an alignment of `query` against
+    /// `reference`, with traceback enabled.
+    ///
+    /// Indels are shifted to their most 3' position, and differences within
+    /// the same codon are reported as a single change, as HGVS recommends.
+    /// End gaps of semi-global alignments are not reported.
+    ///
+    /// # Panics
+    /// Panics if the CDS start is past the end of the reference.
+    pub fn annotate(
+        &self,
+        alignment: &Alignment,
+        query: &[u8],
+        reference: &[u8],
+    ) -> Result<Vec<CodingChange>> {
+        assert!(
+            self.cds_start <= reference.len(),
+            "The CDS start should be within the reference."
+        );
+        let cigar = alignment
+            .cigar(query, reference)?
+            .right_align(query, reference);
+        let protein = translate(&self.genetic_code, &reference[self.cds_start..]);
+        let cds = Cds {
+            code: self.genetic_code,
+            start: self.cds_start,
+            end: self.cds_start + 3 * protein.len(),
+            protein,
+        };
+
+        let mut groups: Vec<Edit> = Vec::new();
+        for edit in edits(&cigar, alignment.is_semi_global()) {
+            match groups.last_mut() {
+                Some(last) if cds.same_codon(last, &edit) => {
+                    last.query.end = edit.query.end;
+                    last.reference.end = edit.reference.end;
+                }
+                _ => groups.push(edit),
+            }
+        }
+
+        Ok(groups
+            .iter()
+            .map(|edit| cds.annotate(edit, query, reference))
+            .collect())
+    }
+}
+
+/// Coding sequence of a reference, with its translation up to the stop codon.
+struct Cds {
+    code: GeneticCode,
+    start: usize,
+    end: usize,
+    protein: Vec<u8>,
+}
+
+impl Cds {
+    /// Whether `next` starts in the codon where `edit` ends.
+    fn same_codon(&self, edit: &Edit, next: &Edit) -> bool {
+        if edit.reference.start < self.start || edit.reference.end > self.end {
+            return false;
+        }
+        let codon_end = self.start + (edit.reference.end - self.start).div_ceil(3) * 3;
+        next.reference.start < codon_end
+    }
+
+    /// HGVS coding position of the 0-based reference position `j`.
+    fn position(&self, j: usize) -> String {
+        if j < self.start {
+            format!("-{}", self.start - j)
+        } else if j >= self.end {
+            format!("*{}", j - self.end + 1)
+        } else {
+            (j - self.start + 1).to_string()
+        }
+    }
+
+    /// HGVS coding range of the 0-based reference positions `start..end`.
+    fn range(&self, start: usize, end: usize) -> String {
+        if end - start == 1 {
+            self.position(start)
+        } else {
+            format!("{}_{}", self.position(start), self.position(end - 1))
+        }
+    }
+
+    fn annotate(&self, edit: &Edit, query: &[u8], reference: &[u8]) -> CodingChange {
+        let coding = self.coding(edit, query, reference);
+        let (start, end) = (edit.reference.start, edit.reference.end);
+        let noncoding = if start == end {
+            start <= self.start || start >= self.end
+        } else {
+            end <= self.start || start >= self.end
+        };
+
+        let (effect, protein, codon) = if noncoding {
+            (CodingEffect::Noncoding, String::from("p.="), None)
+        } else if start < self.start {
+            (CodingEffect::Unknown, String::from("p.?"), None)
+        } else {
+            let mut cds = reference[self.start..start].to_vec();
+            cds.extend_from_slice(&query[edit.query.clone()]);
+            cds.extend_from_slice(&reference[end..]);
+            let frameshift = !edit
+                .query
+                .len()
+                .abs_diff(edit.reference.len())
+                .is_multiple_of(3);
+            self.protein_change(
+                &translate(&self.code, &cds),
+                (start - self.start) / 3,
+                frameshift,
+            )
+        };
+
+        CodingChange {
+            effect,
+            coding: format!("c.{coding}"),
+            protein,
+            codon,
+        }
+    }
+
+    /// Coding DNA notation of an edit, without the `c.` prefix.
+    fn coding(&self, edit: &Edit, query: &[u8], reference: &[u8]) -> String {
+        let (start, end) = (edit.reference.start, edit.reference.end);
+        let alt = String::from_utf8_lossy(&query[edit.query.clone()]).to_ascii_uppercase();
+        let n = alt.len();
+
+        if n == 1 && end - start == 1 {
+            let base = (reference[start] as char).to_ascii_uppercase();
+            format!("{}{base}>{alt}", self.position(start))
+        } else if start == end {
+            if start >= n && reference[start - n..start].eq_ignore_ascii_case(alt.as_bytes()) {
+                format!("{}dup", self.range(start - n, start))
+            } else {
+                format!(
+                    "{}_{}ins{alt}",
+                    self.position(start.saturating_sub(1)),
+                    self.position(start)
+                )
+            }
+        } else if n == 0 {
+            format!("{}del", self.range(start, end))
+        } else {
+            format!("{}delins{alt}", self.range(start, end))
+        }
+    }
+
+    /// Protein notation of the translation `alt` of the mutated CDS, changed
+    /// from codon `codon` (0-based) on.
+    fn protein_change(
+        &self,
+        alt: &[u8],
+        codon: usize,
+        frameshift: bool,
+    ) -> (CodingEffect, String, Option<usize>) {
+        let reference = &self.protein;
+        let p = reference
+            .iter()
+            .zip(alt)
+            .take_while(|(a, b)| a == b)
+            .count();
+        if p == reference.len() && p == alt.len() {
+            let aa = reference.get(codon).map_or("Xaa", |&aa| three_letter(aa));
+            return (
+                CodingEffect::Synonymous,
+                format!("p.{aa}{}=", codon + 1),
+                Some(codon + 1),
+            );
+        }
+
+        let ref_aa = reference.get(p).map_or("Xaa", |&aa| three_letter(aa));
+        let alt_aa = alt.get(p).map_or("Xaa", |&aa| three_letter(aa));
+        // position of the next stop codon, counting the changed residue as 1
+        let stop = alt[p.min(alt.len())..]
+            .iter()
+            .position(|&aa| aa == b'*')
+            .map_or(String::from("?"), |stop| (stop + 1).to_string());
+        let n = p + 1;
+
+        if p == 0 && reference.first() == Some(&b'M') {
+            return (CodingEffect::Unknown, String::from("p.Met1?"), Some(n));
+        }
+        if frameshift {
+            let protein = if alt.get(p) == Some(&b'*') {
+                format!("p.{ref_aa}{n}Ter")
+            } else {
+                format!("p.{ref_aa}{n}{alt_aa}fsTer{stop}")
+            };
+            return (CodingEffect::Frameshift, protein, Some(n));
+        }
+        if reference.get(p) == Some(&b'*') {
+            // the new stop is counted from the first added residue
+            let stop = alt[p..]
+                .iter()
+                .position(|&aa| aa == b'*')
+                .map_or(String::from("?"), |stop| stop.to_string());
+            return (
+                CodingEffect::StopLoss,
+                format!("p.Ter{n}{alt_aa}extTer{stop}"),
+                Some(n),
+            );
+        }
+        if alt.get(p) == Some(&b'*') {
+            return (CodingEffect::Nonsense, format!("p.{ref_aa}{n}Ter"), Some(n));
+        }
+
+        let suffix = reference[p..]
+            .iter()
+            .rev()
+            .zip(alt[p..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let deleted = &reference[p..reference.len() - suffix];
+        let inserted = &alt[p..alt.len() - suffix];
+        let inserted_aas = inserted
+            .iter()
+            .map(|&aa| three_letter(aa))
+            .collect::<String>();
+        let last = p + deleted.len();
+        let range = if deleted.len() > 1 {
+            format!(
+                "{ref_aa}{n}_{}{last}",
+                three_letter(deleted[deleted.len() - 1])
+            )
+        } else {
+            format!("{ref_aa}{n}")
+        };
+
+        match (deleted.len(), inserted.len()) {
+            (1, 1) => (
+                CodingEffect::Missense,
+                format!("p.{ref_aa}{n}{alt_aa}"),
+                Some(n),
+            ),
+            (0, k) if p >= k && reference[p - k..p] == *inserted => {
+                let first = p - k + 1;
+                let range = if k > 1 {
+                    format!(
+                        "{}{first}_{}{p}",
+                        three_letter(reference[p - k]),
+                        three_letter(reference[p - 1])
+                    )
+                } else {
+                    format!("{}{p}", three_letter(reference[p - 1]))
+                };
+                (CodingEffect::InFrame, format!("p.{range}dup"), Some(first))
+            }
+            (0, _) if p > 0 => (
+                CodingEffect::InFrame,
+                format!(
+                    "p.{}{p}_{ref_aa}{n}ins{inserted_aas}",
+                    three_letter(reference[p - 1])
+                ),
+                Some(n),
+            ),
+            (0, _) => (CodingEffect::Unknown, String::from("p.?"), None),
+            (_, 0) => (CodingEffect::InFrame, format!("p.{range}del"), Some(n)),
+            _ => (
+                CodingEffect::InFrame,
+                format!("p.{range}delins{inserted_aas}"),
+                Some(n),
+            ),
+        }
+    }
+}
+
+/// Translate the codons of a sequence up to and including the first stop
+/// codon, using one-letter amino acid codes (`X` for ambiguous codons).
+fn translate(code: &GeneticCode, sequence: &[u8]) -> Vec<u8> {
+    let mut protein = Vec::with_capacity(sequence.len() / 3);
+    for codon in sequence.chunks_exact(3) {
+        let aa = code.translate_codon(codon);
+        protein.push(aa);
+        if aa == b'*' {
+            break;
+        }
+    }
+    protein
+}
+
+/// Three-letter code of a one-letter amino acid code.
+fn three_letter(aa: u8) -> &'static str {
+    match aa.to_ascii_uppercase() {
+        b'A' => "Ala",
+        b'R' => "Arg",
+        b'N' => "Asn",
+        b'D' => "Asp",
+        b'C' => "Cys",
+        b'Q' => "Gln",
+        b'E' => "Glu",
+        b'G' => "Gly",
+        b'H' => "His",
+        b'I' => "Ile",
+        b'L' => "Leu",
+        b'K' => "Lys",
+        b'M' => "Met",
+        b'F' => "Phe",
+        b'P' => "Pro",
+        b'S' => "Ser",
+        b'T' => "Thr",
+        b'W' => "Trp",
+        b'Y' => "Tyr",
+        b'V' => "Val",
+        b'*' => "Ter",
+        _ => "Xaa",
+    }
+}
@@ -0,0 +1,351 @@
+//! Calling variants (SNVs, MNVs and indels) from pairwise alignments.
+//!
+//! Variants are called from the traceback of an alignment of a query (e.g.,
+//! a clone or a read) against a reference, after left-aligning indels (see
+//! `Cigar::left_align`), so that calls do not depend on the vectorization
+//! strategy. They can be written as a minimal single-sample VCF, or annotated
+//! against a coding sequence with `CodingAnnotator`.
+
+mod hgvs;
+
+use std::io::Write;
+use std::ops::Range;
+
+use crate::alignment::Error;
+use crate::prelude::{Alignment, Cigar, CigarOp, Result};
+
+pub use hgvs::{CodingAnnotator, CodingChange, CodingEffect};
+
+/// Kind of a variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum VariantKind {
+    /// Single nucleotide variant.
+    Snv,
+    /// Multi-nucleotide variant (adjacent substitutions).
+    Mnv,
+    /// Insertion in the query.
+    Insertion,
+    /// Deletion from the query.
+    Deletion,
+    /// Adjacent insertion and deletion.
+    Complex,
+}
+
+impl VariantKind {
+    fn name(self) -> &'static str {
+        match self {
+            VariantKind::Snv => "snv",
+            VariantKind::Mnv => "mnv",
+            VariantKind::Insertion => "ins",
+            VariantKind::Deletion => "del",
+            VariantKind::Complex => "complex",
+        }
+    }
+}
+
+/// Difference between the query and the reference.
+///
+/// Alleles follow the VCF convention: indels include the reference base
+/// before them (or after them, at the start of the reference).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variant {
+    pub kind: VariantKind,
+    /// 0-based reference position of the first base of `ref_allele`.
+    pub position: usize,
+    pub ref_allele: String,
+    pub alt_allele: String,
+    /// 0-based query position of the first base of `alt_allele`.
+    pub query_position: usize,
+    /// Phred quality, from the lowest quality of the query bases supporting
+    /// the variant (see `VariantCaller::qualities`).
+    pub quality: Option<u8>,
+}
+
+/// Variant caller options.
+///
+/// ```rust,no_run
+/// use parasail_rs::prelude::Aligner;
+/// use parasail_rs::variants::{write_vcf, VariantCaller};
+///
+/// let reference = b"ACGTACGTTTGCAGGT";
+/// let clone = b"ACGAACGTTGCAGGT";
+/// let qualities = b"IIIIIIIIIIIIIII";
+/// let aligner = Aligner::new().gap_open(5).gap_extend(1).use_trace().build();
+/// let result = aligner.align(Some(clone), reference)?;
+///
+/// let variants = VariantCaller::new()
+///     .qualities(qualities)
+///     .call(&result, clone, reference)?;
+/// write_vcf(&variants, "plasmid", reference.len(), "clone1", std::io::stdout())?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct VariantCaller<'a> {
+    qualities: Option<&'a [u8]>,
+    merge_mnvs: bool,
+}
+
+impl Default for VariantCaller<'_> {
+    fn default() -> Self {
+        VariantCaller {
+            qualities: None,
+            merge_mnvs: true,
+        }
+    }
+}
+
+impl<'a> VariantCaller<'a> {
+    /// Create a new variant caller without qualities that merges adjacent
+    /// substitutions into MNVs.
+    pub fn new() -> Self {
+        VariantCaller::default()
+    }
+
+    /// Set the qualities of the query bases, as in FASTQ files (Phred+33
+    /// encoded, one per query base).
+    pub fn qualities(&mut self, qualities: &'a [u8]) -> &mut Self {
+        self.qualities = Some(qualities);
+        self
+    }
+
+    /// Merge adjacent substitutions into a single MNV (the default) or
+    /// report them as separate SNVs.
+    pub fn merge_mnvs(&mut self, merge_mnvs: bool) -> &mut Self {
+        self.merge_mnvs = merge_mnvs;
+        self
+    }
+
+    /// Call variants from an alignment of `query` against `reference`, with
+    /// traceback enabled. Variants are sorted by reference position.
+    ///
+    /// End gaps of semi-global alignments are not reported, since they only
+    /// mean that the sequences do not overlap there.
+    ///
+    /// # Panics
+    /// Panics if qualities were set and there is not one per query base.
+    pub fn call(
+        &self,
+        alignment: &Alignment,
+        query: &[u8],
+        reference: &[u8],
+    ) -> Result<Vec<Variant>> {
+        if let Some(qualities) = self.qualities {
+            assert_eq!(
+                qualities.len(),
+                query.len(),
+                "There should be one quality per query base."
+            );
+        }
+        let cigar = alignment
+            .cigar(query, reference)?
+            .left_align(query, reference);
+
+        let mut variants = Vec::new();
+        for edit in edits(&cigar, alignment.is_semi_global()) {
+            let (ins, del) = (edit.query.len(), edit.reference.len());
+            if ins == del {
+                if self.merge_mnvs && ins > 1 {
+                    variants.push(self.substitution(VariantKind::Mnv, &edit, query, reference));
+                    continue;
+                }
+                for offset in 0..ins {
+                    let snv = Edit {
+                        query: edit.query.start + offset..edit.query.start + offset + 1,
+                        reference: edit.reference.start + offset..edit.reference.start + offset + 1,
+                    };
+                    variants.push(self.substitution(VariantKind::Snv, &snv, query, reference));
+                }
+            } else if let Some(variant) = self.indel(&edit, query, reference) {
+                variants.push(variant);
+            }
+        }
+
+        Ok(variants)
+    }
+
+    /// Create a substitution variant from an edit with as many query as
+    /// reference bases.
+    fn substitution(
+        &self,
+        kind: VariantKind,
+        edit: &Edit,
+        query: &[u8],
+        reference: &[u8],
+    ) -> Variant {
+        Variant {
+            kind,
+            position: edit.reference.start,
+            ref_allele: String::from_utf8_lossy(&reference[edit.reference.clone()]).into_owned(),
+            alt_allele: String::from_utf8_lossy(&query[edit.query.clone()]).into_owned(),
+            query_position: edit.query.start,
+            quality: self.quality(edit.query.start, edit.query.len()),
+        }
+    }
+
+    /// Create an indel variant from an edit, anchored on the previous
+    /// reference base (or the next one at the start of the reference).
+    fn indel(&self, edit: &Edit, query: &[u8], reference: &[u8]) -> Option<Variant> {
+        let (i, j) = (edit.query.start, edit.reference.start);
+        let kind = match (edit.query.len(), edit.reference.len()) {
+            (_, 0) => VariantKind::Insertion,
+            (0, _) => VariantKind::Deletion,
+            _ => VariantKind::Complex,
+        };
+        let inserted = String::from_utf8_lossy(&query[edit.query.clone()]);
+        let deleted = String::from_utf8_lossy(&reference[edit.reference.clone()]);
+        // inserted bases support insertions, flanking bases support deletions
+        let quality = if !edit.query.is_empty() {
+            self.quality(i, edit.query.len())
+        } else {
+            self.quality(i.saturating_sub(1), if i > 0 { 2 } else { 1 })
+        };
+
+        if j > 0 {
+            let anchor = reference[j - 1] as char;
+            Some(Variant {
+                kind,
+                position: j - 1,
+                ref_allele: format!("{anchor}{deleted}"),
+                alt_allele: format!("{anchor}{inserted}"),
+                query_position: i.saturating_sub(1),
+                quality,
+            })
+        } else {
+            let anchor = *reference.get(edit.reference.end)? as char;
+            Some(Variant {
+                kind,
+                position: j,
+                ref_allele: format!("{deleted}{anchor}"),
+                alt_allele: format!("{inserted}{anchor}"),
+                query_position: i,
+                quality,
+            })
+        }
+    }
+
+    /// Lowest Phred quality of `len` query bases at `i`, if qualities were
+    /// set.
+    fn quality(&self, i: usize, len: usize) -> Option<u8> {
+        let qualities = self.qualities?;
+        let end = (i + len).min(qualities.len());
+        qualities[i.min(end)..end]
+            .iter()
+            .min()
+            .map(|quality| quality.saturating_sub(33))
+    }
+}
+
+/// Replacement of the `reference` bases by the `query` bases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edit {
+    query: Range<usize>,
+    reference: Range<usize>,
+}
+
+/// Differences of an alignment CIGAR: runs of mismatches and indels, with
+/// adjacent insertions and deletions merged. End gaps are skipped if
+/// `skip_end_gaps` is set.
+fn edits(cigar: &Cigar, skip_end_gaps: bool) -> Vec<Edit> {
+    let ops = cigar.ops();
+    let aligned = |op: &(CigarOp, usize)| op.0 != CigarOp::SoftClip;
+    let first = ops.iter().position(aligned).unwrap_or(0);
+    let last = ops.iter().rposition(aligned).unwrap_or(0);
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (cigar.query_start, cigar.ref_start);
+    let mut k = 0;
+    while k < ops.len() {
+        let (op, len) = ops[k];
+        match op {
+            CigarOp::Diff => edits.push(Edit {
+                query: i..i + len,
+                reference: j..j + len,
+            }),
+            CigarOp::Ins | CigarOp::Del => {
+                let start = k;
+                let (mut ins, mut del) = (0, 0);
+                while let Some(&(op @ (CigarOp::Ins | CigarOp::Del), len)) = ops.get(k) {
+                    match op {
+                        CigarOp::Ins => ins += len,
+                        _ => del += len,
+                    }
+                    k += 1;
+                }
+                if !(skip_end_gaps && (start == first || k - 1 == last)) {
+                    edits.push(Edit {
+                        query: i..i + ins,
+                        reference: j..j + del,
+                    });
+                }
+                i += ins;
+                j += del;
+                continue;
+            }
+            _ => {}
+        }
+        i += op.consumes_query() as usize * len;
+        j += op.consumes_ref() as usize * len;
+        k += 1;
+    }
+
+    edits
+}
+
+/// Write variants as a minimal VCF 4.3 file with a single sample, on a
+/// reference sequence named `chrom` of `chrom_len` bases. The variant kind is
+/// reported in the `TYPE` INFO field and every sample genotype is `1`
+/// (haploid alternate).
+pub fn write_vcf<W: Write>(
+    variants: &[Variant],
+    chrom: &str,
+    chrom_len: usize,
+    sample: &str,
+    writer: W,
+) -> Result<()> {
+    write_vcf_records(variants, chrom, chrom_len, sample, writer).map_err(Error::Io)?;
+    Ok(())
+}
+
+fn write_vcf_records<W: Write>(
+    variants: &[Variant],
+    chrom: &str,
+    chrom_len: usize,
+    sample: &str,
+    mut writer: W,
+) -> std::io::Result<()> {
+    writeln!(writer, "##fileformat=VCFv4.3")?;
+    writeln!(writer, "##source=parasail-rs")?;
+    writeln!(writer, "##contig=<ID={chrom},length={chrom_len}>")?;
+    writeln!(
+        writer,
+        "##INFO=<ID=TYPE,Number=1,Type=String,Description=\"Variant type (snv, mnv, ins, del or complex)\">"
+    )?;
+    writeln!(
+        writer,
+        "##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">"
+    )?;
+    writeln!(
+        writer,
+        "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\t{sample}"
+    )?;
+    for variant in variants {
+        let quality = variant
+            .quality
+            .map_or(String::from("."), |quality| quality.to_string());
+        writeln!(
+            writer,
+            "{chrom}\t{}\t.\t{}\t{}\t{quality}\t.\tTYPE={}\tGT\t1",
+            variant.position + 1,
+            variant.ref_allele,
+            variant.alt_allele,
+            variant.kind.name()
+        )?;
+    }
+    Ok(())
+}
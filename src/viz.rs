@@ -0,0 +1,381 @@
+//! SVG visualizations of sequences and alignments.
+//!
+//! `DotPlot` draws windowed word matches between two sequences (e.g., to
+//! inspect repeats) and `Heatmap` draws the score table of an alignment with
+//! its traceback path overlaid. Both produce standalone SVG documents.
+
+use std::fmt::Write as _;
+use std::io::Write;
+
+use crate::alignment::Error;
+use crate::prelude::{Result, Table, TraceStep};
+
+/// Space around the plot for axis labels, in pixels.
+const MARGIN: f64 = 40.0;
+
+/// Dot plot of two sequences.
+///
+/// A dot is drawn where a window of `word_size` residues of the query and of
+/// the reference share at least `threshold` identical residues (compared
+/// case-insensitively). With the default threshold of `word_size`, only exact
+/// word matches are drawn. Consecutive hits along a diagonal are drawn as a
+/// single line. The reference runs along the x axis and the query along the
+/// y axis.
+///
+/// ```rust,no_run
+/// use parasail_rs::viz::DotPlot;
+///
+/// let query = b"ACGTACGTACGTTTGCA";
+/// let reference = b"TTACGTACGTACGTACGTGCA";
+/// let svg = DotPlot::new(query, reference)
+///     .word_size(6)
+///     .threshold(5)
+///     .names("read", "repeat")
+///     .to_svg();
+/// std::fs::write("dotplot.svg", svg)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct DotPlot<'a> {
+    query: &'a [u8],
+    reference: &'a [u8],
+    query_name: String,
+    ref_name: String,
+    word_size: usize,
+    threshold: Option<usize>,
+    size: f64,
+}
+
+impl<'a> DotPlot<'a> {
+    /// Create a dot plot of `query` against `reference`, with a word size of
+    /// 10 and a size of 600 pixels.
+    pub fn new(query: &'a [u8], reference: &'a [u8]) -> Self {
+        DotPlot {
+            query,
+            reference,
+            query_name: String::from("query"),
+            ref_name: String::from("reference"),
+            word_size: 10,
+            threshold: None,
+            size: 600.0,
+        }
+    }
+
+    /// Set the number of residues in each window.
+    pub fn word_size(&mut self, word_size: usize) -> &mut Self {
+        assert!(word_size > 0, "Word size must be greater than 0.");
+        self.word_size = word_size;
+        self
+    }
+
+    /// Set the minimum number of identical residues in a window for a dot to
+    /// be drawn. The default is the word size (exact matches).
+    pub fn threshold(&mut self, threshold: usize) -> &mut Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Set the axis labels.
+    pub fn names(&mut self, query_name: &str, ref_name: &str) -> &mut Self {
+        self.query_name = query_name.to_string();
+        self.ref_name = ref_name.to_string();
+        self
+    }
+
+    /// Set the size of the longer side of the plot area, in pixels.
+    pub fn size(&mut self, size: f64) -> &mut Self {
+        assert!(size > 0.0, "Plot size must be greater than 0.");
+        self.size = size;
+        self
+    }
+
+    /// Get the start positions `(query, reference)` of all matching windows,
+    /// ordered by diagonal and then by position.
+    pub fn hits(&self) -> Vec<(usize, usize)> {
+        let k = self.word_size;
+        let threshold = self.threshold.unwrap_or(k).min(k);
+        let (n, m) = (self.query.len(), self.reference.len());
+        let mut hits = Vec::new();
+        if n < k || m < k {
+            return hits;
+        }
+
+        let same =
+            |i: usize, j: usize| self.query[i].eq_ignore_ascii_case(&self.reference[j]) as usize;
+        // slide a window along each diagonal, from the bottom left
+        for diagonal in 0..(n - k + 1) + (m - k + 1) - 1 {
+            let (mut i, mut j) = if diagonal < n - k + 1 {
+                (n - k - diagonal, 0)
+            } else {
+                (0, diagonal - (n - k))
+            };
+            let mut count: usize = (0..k).map(|offset| same(i + offset, j + offset)).sum();
+            loop {
+                if count >= threshold {
+                    hits.push((i, j));
+                }
+                if i + k >= n || j + k >= m {
+                    break;
+                }
+                count = count + same(i + k, j + k) - same(i, j);
+                i += 1;
+                j += 1;
+            }
+        }
+
+        hits
+    }
+
+    /// Render the dot plot as an SVG document.
+    pub fn to_svg(&self) -> String {
+        let (n, m) = (self.query.len(), self.reference.len());
+        let scale = self.size / n.max(m).max(1) as f64;
+        let (width, height) = (m as f64 * scale, n as f64 * scale);
+        let mut svg = header(width + 2.0 * MARGIN, height + 2.0 * MARGIN);
+        axes(&mut svg, width, height, &self.ref_name, &self.query_name);
+
+        // merge consecutive hits along each diagonal into one line
+        let hits = self.hits();
+        let mut runs: Vec<((usize, usize), usize)> = Vec::new();
+        for (i, j) in hits {
+            match runs.last_mut() {
+                Some(((start_i, start_j), len)) if i == *start_i + *len && j == *start_j + *len => {
+                    *len += 1
+                }
+                _ => runs.push(((i, j), 1)),
+            }
+        }
+        for ((i, j), len) in runs {
+            let extent = (len - 1 + self.word_size) as f64 * scale;
+            let (x, y) = (MARGIN + j as f64 * scale, MARGIN + i as f64 * scale);
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{x:.2}\" y1=\"{y:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" \
+                 stroke=\"black\" stroke-width=\"{:.2}\" stroke-linecap=\"square\"/>",
+                x + extent,
+                y + extent,
+                scale.clamp(1.0, 3.0),
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Write the dot plot as an SVG document.
+    pub fn write_svg<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer
+            .write_all(self.to_svg().as_bytes())
+            .map_err(Error::Io)?;
+        Ok(())
+    }
+}
+
+/// Heatmap of the score table of an alignment, with an optional traceback
+/// path overlaid.
+///
+/// Cells are shaded from white (lowest score) to dark blue (highest score),
+/// with the score as a tooltip. The reference runs along the x axis and the
+/// query along the y axis, as in `Table`.
+///
+/// Parasail does not fill the score table and the traceback table in the
+/// same alignment, so the path usually comes from a second alignment with
+/// traceback enabled (see `TracebackTable::path`) or from
+/// `Aligner::tied_paths`.
+///
+/// ```rust,no_run
+/// use parasail_rs::prelude::Aligner;
+/// use parasail_rs::viz::Heatmap;
+///
+/// let query = b"ACGTACGT";
+/// let reference = b"ACGACGT";
+/// let aligner = Aligner::new().gap_open(5).gap_extend(1).use_table().build();
+/// let result = aligner.align(Some(query), reference)?;
+/// let table = result.get_score_table()?;
+/// let aligner = Aligner::new().gap_open(5).gap_extend(1).build();
+/// let path = aligner.tied_paths(query, reference, 1)?.remove(0);
+///
+/// let svg = Heatmap::new(&table)
+///     .path(&path)
+///     .sequences(query, reference)
+///     .to_svg();
+/// std::fs::write("heatmap.svg", svg)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Heatmap<'a> {
+    table: &'a Table<'a>,
+    path: Vec<TraceStep>,
+    query: Option<&'a [u8]>,
+    reference: Option<&'a [u8]>,
+    cell_size: f64,
+}
+
+impl<'a> Heatmap<'a> {
+    /// Create a heatmap of `table` with 12 pixel cells.
+    pub fn new(table: &'a Table<'a>) -> Self {
+        Heatmap {
+            table,
+            path: Vec::new(),
+            query: None,
+            reference: None,
+            cell_size: 12.0,
+        }
+    }
+
+    /// Overlay a traceback path on the heatmap.
+    pub fn path(&mut self, path: &[TraceStep]) -> &mut Self {
+        self.path = path.to_vec();
+        self
+    }
+
+    /// Label the rows and columns with the residues of the query and
+    /// reference.
+    pub fn sequences(&mut self, query: &'a [u8], reference: &'a [u8]) -> &mut Self {
+        self.query = Some(query);
+        self.reference = Some(reference);
+        self
+    }
+
+    /// Set the size of each cell, in pixels.
+    pub fn cell_size(&mut self, cell_size: f64) -> &mut Self {
+        assert!(cell_size > 0.0, "Cell size must be greater than 0.");
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Render the heatmap as an SVG document.
+    pub fn to_svg(&self) -> String {
+        let (rows, cols) = (self.table.rows(), self.table.cols());
+        let cell = self.cell_size;
+        let (width, height) = (cols as f64 * cell, rows as f64 * cell);
+        let mut svg = header(width + 2.0 * MARGIN, height + 2.0 * MARGIN);
+        axes(&mut svg, width, height, "reference", "query");
+
+        let scores = self.table.as_slice();
+        let min = scores.iter().copied().min().unwrap_or(0);
+        let max = scores.iter().copied().max().unwrap_or(0);
+        for (i, row) in self.table.iter_rows().enumerate() {
+            for (j, &score) in row.iter().enumerate() {
+                let level = if max > min {
+                    (score - min) as f64 / (max - min) as f64
+                } else {
+                    0.0
+                };
+                let _ = writeln!(
+                    svg,
+                    "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{cell:.2}\" height=\"{cell:.2}\" \
+                     fill=\"{}\"><title>({i}, {j}): {score}</title></rect>",
+                    MARGIN + j as f64 * cell,
+                    MARGIN + i as f64 * cell,
+                    color(level),
+                );
+            }
+        }
+
+        if let (Some(query), Some(reference)) = (self.query, self.reference) {
+            let font_size = (cell * 0.8).min(14.0);
+            for (j, &residue) in reference.iter().enumerate().take(cols) {
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{font_size:.2}\" \
+                     text-anchor=\"middle\">{}</text>",
+                    MARGIN + (j as f64 + 0.5) * cell,
+                    MARGIN - 4.0,
+                    escape(residue),
+                );
+            }
+            for (i, &residue) in query.iter().enumerate().take(rows) {
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{font_size:.2}\" \
+                     text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>",
+                    MARGIN - 4.0,
+                    MARGIN + (i as f64 + 0.5) * cell,
+                    escape(residue),
+                );
+            }
+        }
+
+        if !self.path.is_empty() {
+            let points: Vec<String> = self
+                .path
+                .iter()
+                .map(|step| {
+                    format!(
+                        "{:.2},{:.2}",
+                        MARGIN + (step.col as f64 + 0.5) * cell,
+                        MARGIN + (step.row as f64 + 0.5) * cell
+                    )
+                })
+                .collect();
+            let _ = writeln!(
+                svg,
+                "<polyline points=\"{}\" fill=\"none\" stroke=\"#d62728\" stroke-width=\"{:.2}\"/>",
+                points.join(" "),
+                (cell / 6.0).max(1.0),
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Write the heatmap as an SVG document.
+    pub fn write_svg<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer
+            .write_all(self.to_svg().as_bytes())
+            .map_err(Error::Io)?;
+        Ok(())
+    }
+}
+
+fn header(width: f64, height: f64) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.0}\" height=\"{height:.0}\" \
+         viewBox=\"0 0 {width:.2} {height:.2}\" font-family=\"monospace\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n"
+    )
+}
+
+/// Draw the plot border with the x axis label above and the y axis label on
+/// the left.
+fn axes(svg: &mut String, width: f64, height: f64, x_label: &str, y_label: &str) {
+    let _ = writeln!(
+        svg,
+        "<rect x=\"{MARGIN:.2}\" y=\"{MARGIN:.2}\" width=\"{width:.2}\" height=\"{height:.2}\" \
+         fill=\"none\" stroke=\"#cccccc\"/>"
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"12\" text-anchor=\"middle\">{}</text>",
+        MARGIN + width / 2.0,
+        MARGIN / 2.0 - 6.0,
+        escape_str(x_label),
+    );
+    let (x, y) = (MARGIN / 2.0 - 6.0, MARGIN + height / 2.0);
+    let _ = writeln!(
+        svg,
+        "<text x=\"{x:.2}\" y=\"{y:.2}\" font-size=\"12\" text-anchor=\"middle\" \
+         transform=\"rotate(-90 {x:.2} {y:.2})\">{}</text>",
+        escape_str(y_label),
+    );
+}
+
+/// Interpolate from white to dark blue.
+fn color(level: f64) -> String {
+    let (low, high) = ([255.0, 255.0, 255.0], [8.0, 48.0, 107.0]);
+    let channel = |k: usize| (low[k] + (high[k] - low[k]) * level).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(0), channel(1), channel(2))
+}
+
+fn escape(residue: u8) -> String {
+    escape_str(&String::from_utf8_lossy(&[residue]))
+}
+
+fn escape_str(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
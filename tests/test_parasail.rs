@@ -1,4 +1,24 @@
-use parasail_rs::prelude::{Aligner, Matrix, Profile, TraceFlags};
+use parasail_rs::alignment::{FormatStyle, Formatter};
+use parasail_rs::crispr::{write_table, OffTargetSearch};
+use parasail_rs::demux::{Assignment, BarcodeSheet, Demultiplexer};
+use parasail_rs::fastx::{FastaReader, FastqReader, FastqRecord};
+use parasail_rs::immune::{write_airr, Germline, GermlineSet, Segment, VdjAssigner};
+use parasail_rs::matrix::{
+    AmbiguityPolicy, Background, Error as MatrixError, GapHandling, MoleculeType, PsiBlastPssm,
+    PssmBuilder,
+};
+use parasail_rs::merge::{merge_pairs, PairMerger};
+use parasail_rs::prelude::{
+    Aligner, BuiltinMatrix, Cigar, CigarOp, Error, Matrix, MatrixKind, Profile, TraceFlags,
+    TraceOp, TraceStep,
+};
+use parasail_rs::sequence::{complement, reverse_complement, Strand};
+use parasail_rs::translate::GeneticCode;
+use parasail_rs::trim::{write_reports, AdapterEnd, QualityTrim, Trimmer};
+use parasail_rs::variants::{
+    write_vcf, CodingAnnotator, CodingEffect, Variant, VariantCaller, VariantKind,
+};
+use parasail_rs::viz::{DotPlot, Heatmap};
 use std::thread;
 
 #[test]
@@ -33,6 +53,357 @@ pub fn matrix_construction() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+pub fn matrix_introspection() -> Result<(), Box<dyn std::error::Error>> {
+    // builtin
+    let blosum62 = Matrix::from("blosum62")?;
+    assert_eq!(blosum62.name(), Some("blosum62"));
+    assert_eq!(blosum62.kind(), MatrixKind::Square);
+    assert_eq!(blosum62.size(), 24);
+    assert_eq!(blosum62.alphabet(), b"ARNDCQEGHILKMFPSTWYVBZX*");
+    assert_eq!(blosum62.score(b'W', b'W'), Some(11));
+    assert_eq!(blosum62.score(b'w', b'W'), Some(11));
+    assert_eq!(blosum62.max_score(), 11);
+    assert_eq!(blosum62.min_score(), -4);
+    assert!(blosum62.pssm_rows().is_none());
+
+    // custom matrix
+    let matrix = Matrix::create(b"ACGT", 3, -2)?;
+    assert_eq!(matrix.name(), None);
+    assert_eq!(matrix.alphabet(), b"ACGT*");
+    assert_eq!(matrix.size(), 5);
+    assert_eq!(matrix.score(b'A', b'A'), Some(3));
+    assert_eq!(matrix.score(b'A', b'C'), Some(-2));
+    assert_eq!(matrix.score(b'A', b'N'), Some(0));
+    assert_eq!(matrix.max_score(), 3);
+    assert_eq!(matrix.min_score(), -2);
+    println!("{}", matrix);
+
+    // PSSM
+    let pssm = blosum62.to_pssm(b"ACGT")?;
+    assert_eq!(pssm.kind(), MatrixKind::Pssm);
+    assert_eq!(pssm.score(b'A', b'A'), None);
+    assert_eq!(pssm.pssm_rows().map(|rows| rows.len()), Some(4));
+    assert_eq!(pssm.pssm_query(), Some(&b"ACGT"[..]));
+    assert_eq!(pssm.pssm_score(0, b'A'), Some(4));
+    assert_eq!(pssm.pssm_score(4, b'A'), None);
+
+    let pssm = Matrix::from_file("./tests/pssm.txt")?;
+    assert_eq!(pssm.pssm_rows().map(|rows| rows.len()), Some(10));
+    assert_eq!(pssm.pssm_query(), Some(&b"YSCDGCLKPI"[..]));
+    println!("{}", pssm);
+
+    Ok(())
+}
+
+#[test]
+pub fn matrix_parse_and_write() -> Result<(), Box<dyn std::error::Error>> {
+    // NCBI BLAST style matrix with comments and an indented header
+    let ncbi = "\
+#  Matrix made by matblas
+#  * column uses minimum score
+   A  C  G  T  *
+A  5 -4 -4 -4 -4
+C -4  5 -4 -4 -4
+G -4 -4  5 -4 -4
+T -4 -4 -4  5 -4
+* -4 -4 -4 -4  1
+";
+    let matrix: Matrix = ncbi.parse()?;
+    assert_eq!(matrix.kind(), MatrixKind::Square);
+    assert_eq!(matrix.alphabet(), b"ACGT*");
+    assert_eq!(matrix.score(b'a', b'A'), Some(5));
+    assert_eq!(matrix.score(b'N', b'N'), Some(1));
+    assert_eq!(matrix.min_score(), -4);
+    assert_eq!(matrix.max_score(), 5);
+
+    // round trip an edited matrix
+    let mut edited = Matrix::create(b"ACGT", 2, -1)?;
+    edited.set_value(0, 1, 7)?;
+    let mut buffer = Vec::new();
+    edited.write_to(&mut buffer)?;
+    let reloaded = Matrix::from_reader(buffer.as_slice())?;
+    assert_eq!(reloaded.alphabet(), edited.alphabet());
+    assert_eq!(reloaded.values(), edited.values());
+
+    // round trip a builtin matrix
+    let blosum62 = Matrix::from("blosum62")?;
+    let reloaded: Matrix = blosum62.to_string().parse()?;
+    assert_eq!(reloaded.alphabet(), blosum62.alphabet());
+    assert_eq!(reloaded.values(), blosum62.values());
+
+    // round trip a PSSM
+    let pssm = Matrix::from_file("./tests/pssm.txt")?;
+    let reloaded: Matrix = pssm.to_string().parse()?;
+    assert_eq!(reloaded.kind(), MatrixKind::Pssm);
+    assert_eq!(reloaded.pssm_query(), pssm.pssm_query());
+    assert_eq!(reloaded.values(), pssm.values());
+
+    // diagnostics point at the offending line and column
+    let bad = "  A  C  *\nA  1 -1  0\nC -1 x1  0\n* 0 0 0\n";
+    match bad.parse::<Matrix>() {
+        Err(Error::Matrix(MatrixError::Parse { line, column, .. })) => {
+            assert_eq!((line, column), (3, 6));
+        }
+        other => panic!("expected parse error, got {other:?}"),
+    }
+
+    let short = "  A  C  *\nA  1 -1\n";
+    match short.parse::<Matrix>() {
+        Err(Error::Matrix(MatrixError::Parse { line, column, .. })) => {
+            assert_eq!((line, column), (2, 8));
+        }
+        other => panic!("expected parse error, got {other:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn builtin_matrices() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(BuiltinMatrix::all().count(), 68);
+    for builtin in BuiltinMatrix::all() {
+        let matrix = Matrix::builtin(builtin)?;
+        assert_eq!(matrix.name(), Some(builtin.name()));
+        assert_eq!(builtin.to_string().parse::<BuiltinMatrix>()?, builtin);
+    }
+
+    assert_eq!(
+        "BLOSUM62".parse::<BuiltinMatrix>()?,
+        BuiltinMatrix::Blosum62
+    );
+    assert_eq!("NUC.4.4".parse::<BuiltinMatrix>()?, BuiltinMatrix::Nuc44);
+    assert_eq!("EDNAFULL".parse::<BuiltinMatrix>()?, BuiltinMatrix::DnaFull);
+    assert_eq!(
+        BuiltinMatrix::DnaFull.molecule_type(),
+        MoleculeType::Nucleotide
+    );
+    assert_eq!(BuiltinMatrix::Pam250.molecule_type(), MoleculeType::Protein);
+
+    match "blosm62".parse::<BuiltinMatrix>() {
+        Err(Error::Matrix(MatrixError::UnknownBuiltin { suggestion, .. })) => {
+            assert_eq!(suggestion, Some("blosum62"));
+        }
+        other => panic!("expected unknown builtin error, got {other:?}"),
+    }
+
+    match "identity".parse::<BuiltinMatrix>() {
+        Err(Error::Matrix(MatrixError::UnknownBuiltin { suggestion, .. })) => {
+            assert_eq!(suggestion, None);
+        }
+        other => panic!("expected unknown builtin error, got {other:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn dna_iupac_matrix() -> Result<(), Box<dyn std::error::Error>> {
+    let expected = Matrix::dna_iupac(5, -4, AmbiguityPolicy::Expected)?;
+    assert_eq!(expected.score(b'A', b'A'), Some(5));
+    assert_eq!(expected.score(b'a', b'A'), Some(5));
+    assert_eq!(expected.score(b'T', b'u'), Some(5));
+    assert_eq!(expected.score(b'A', b'C'), Some(-4));
+    // A vs R: 1/2 * 5 + 1/2 * -4 = 0.5
+    assert_eq!(expected.score(b'A', b'r'), Some(1));
+    // A vs N: 1/4 * 5 + 3/4 * -4 = -1.75
+    assert_eq!(expected.score(b'n', b'A'), Some(-2));
+    assert_eq!(expected.score(b'A', b'Y'), Some(-4));
+    assert_eq!(expected.score(b'A', b'X'), Some(-4));
+
+    let scaled = Matrix::dna_iupac(5, -4, AmbiguityPolicy::Scaled(4))?;
+    assert_eq!(scaled.score(b'A', b'A'), Some(20));
+    assert_eq!(scaled.score(b'A', b'N'), Some(-7));
+
+    let permissive = Matrix::dna_iupac(5, -4, AmbiguityPolicy::AnyOverlap)?;
+    assert_eq!(permissive.score(b'A', b'N'), Some(5));
+    assert_eq!(permissive.score(b'R', b'Y'), Some(-4));
+
+    let strict = Matrix::dna_iupac(5, -4, AmbiguityPolicy::Mismatch)?;
+    assert_eq!(strict.score(b'A', b'N'), Some(-4));
+    assert_eq!(strict.score(b'g', b'G'), Some(5));
+
+    let aligner = Aligner::new()
+        .global()
+        .gap_open(10)
+        .gap_extend(1)
+        .matrix(expected)
+        .build();
+    let result = aligner.align(Some(b"ACGTNACGT"), b"acgtaacgt")?;
+    assert_eq!(result.get_score(), 8 * 5 - 2);
+
+    Ok(())
+}
+
+#[test]
+pub fn pssm_from_msa() -> Result<(), Box<dyn std::error::Error>> {
+    let msa = [b"ACGTAC".as_slice(), b"ACGTTC", b"aggtac"];
+    let pssm = Matrix::pssm_from_msa(&msa, &Background::dna(), 1.0)?;
+    assert_eq!(pssm.kind(), MatrixKind::Pssm);
+    assert_eq!(pssm.alphabet(), b"ACGT*");
+    assert_eq!(pssm.pssm_query(), Some(&b"ACGTAC"[..]));
+    // log2(((3 + 0.25) / 4) / 0.25) = 1.70 bits
+    assert_eq!(pssm.pssm_score(0, b'A'), Some(3));
+    assert_eq!(pssm.pssm_score(0, b'C'), Some(-4));
+    assert_eq!(pssm.pssm_score(4, b'A'), Some(2));
+    assert_eq!(pssm.pssm_score(4, b'T'), Some(1));
+
+    let query = pssm.pssm_query().unwrap_or_default().to_vec();
+    let aligner = Aligner::new()
+        .semi_global()
+        .gap_open(5)
+        .gap_extend(1)
+        .matrix(pssm)
+        .build();
+    let result = aligner.align(Some(&query), b"TTACGTACTT")?;
+    assert_eq!(result.get_end_ref(), 7);
+
+    let gapped = [b"AC-GT".as_slice(), b"ACTGT", b"AC-GT"];
+    let anchored = PssmBuilder::new(&gapped, Background::dna())
+        .gap_handling(GapHandling::QueryAnchored)
+        .build()?;
+    assert_eq!(anchored.pssm_query(), Some(&b"ACGT"[..]));
+    let filtered = PssmBuilder::new(&gapped, Background::dna())
+        .gap_handling(GapHandling::MaxGapFraction(0.5))
+        .build()?;
+    assert_eq!(filtered.pssm_rows().map(|rows| rows.len()), Some(4));
+    let kept = Matrix::pssm_from_msa(&gapped, &Background::dna(), 1.0)?;
+    assert_eq!(kept.pssm_rows().map(|rows| rows.len()), Some(5));
+
+    // three identical sequences count as much as the odd one out
+    let redundant = [b"AAAA".as_slice(), b"AAAA", b"AAAA", b"CCCC"];
+    let unweighted = Matrix::pssm_from_msa(&redundant, &Background::dna(), 1.0)?;
+    assert!(unweighted.pssm_score(0, b'A') > unweighted.pssm_score(0, b'C'));
+    let weighted = PssmBuilder::new(&redundant, Background::dna())
+        .henikoff_weights()
+        .build()?;
+    assert_eq!(weighted.pssm_score(0, b'A'), weighted.pssm_score(0, b'C'));
+
+    let protein = Matrix::pssm_from_msa(&["MKTAYIAK", "MKSAYIAK"], &Background::protein(), 0.5)?;
+    assert_eq!(protein.size(), 21);
+
+    match Matrix::pssm_from_msa(&["ACGT", "ACG"], &Background::dna(), 1.0) {
+        Err(Error::Matrix(MatrixError::UnequalLengths { sequence, .. })) => assert_eq!(sequence, 1),
+        other => panic!("expected unequal lengths error, got {other:?}"),
+    }
+    let empty: [&[u8]; 0] = [];
+    assert!(Matrix::pssm_from_msa(&empty, &Background::dna(), 1.0).is_err());
+    assert!(Background::new(b"ACGT", &[0.5, 0.5]).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn psiblast_pssm() -> Result<(), Box<dyn std::error::Error>> {
+    let pssm = PsiBlastPssm::from_file("./tests/psiblast.pssm")?;
+    assert_eq!(pssm.matrix.pssm_query(), Some(&b"MKTAYIAK"[..]));
+    assert_eq!(pssm.matrix.alphabet(), b"ARNDCQEGHILKMFPSTWYV*");
+    assert_eq!(pssm.matrix.pssm_score(0, b'M'), Some(4));
+    assert_eq!(pssm.matrix.pssm_score(3, b'a'), Some(8));
+    let frequencies = pssm.frequencies.as_deref().unwrap_or_default();
+    assert_eq!(frequencies.len(), 8 * 20);
+    assert_eq!(frequencies[12], 100);
+    assert_eq!(pssm.information.as_ref().map(|i| i[0]), Some(1.60));
+    assert_eq!(pssm.relative_weights.as_ref().map(|w| w[7]), Some(0.65));
+
+    let mut written = Vec::new();
+    pssm.write_to(&mut written)?;
+    let reloaded = PsiBlastPssm::from_reader(written.as_slice())?;
+    assert_eq!(reloaded.matrix.values(), pssm.matrix.values());
+    assert_eq!(reloaded.matrix.pssm_query(), pssm.matrix.pssm_query());
+    assert_eq!(reloaded.frequencies, pssm.frequencies);
+    assert_eq!(reloaded.information, pssm.information);
+    assert_eq!(reloaded.relative_weights, pssm.relative_weights);
+
+    // any PSSM can be exported, without the optional columns
+    let native = Matrix::from_file("./tests/pssm.txt")?;
+    let mut written = Vec::new();
+    native.write_psiblast(&mut written)?;
+    let reloaded = PsiBlastPssm::from_reader(written.as_slice())?;
+    assert_eq!(reloaded.matrix.values(), native.values());
+    assert_eq!(reloaded.matrix.pssm_query(), native.pssm_query());
+    assert!(reloaded.frequencies.is_none());
+    assert!(reloaded.information.is_none());
+
+    assert!(Matrix::from("blosum62")?
+        .write_psiblast(Vec::new())
+        .is_err());
+
+    let truncated = "   A   C\n    1 A    2\n";
+    match PsiBlastPssm::from_reader(truncated.as_bytes()) {
+        Err(Error::Matrix(MatrixError::Parse { line, .. })) => assert_eq!(line, 2),
+        other => panic!("expected parse error, got {:?}", other.map(|_| ())),
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn matrix_transforms() -> Result<(), Box<dyn std::error::Error>> {
+    let blosum62 = Matrix::from("blosum62")?;
+
+    let doubled = blosum62.scaled(2.0)?;
+    assert_eq!(doubled.name(), None);
+    assert_eq!(doubled.alphabet(), blosum62.alphabet());
+    assert_eq!(doubled.score(b'W', b'W'), Some(22));
+    assert_eq!(doubled.min_score(), -8);
+
+    let shifted = blosum62.shifted(4)?;
+    assert_eq!(shifted.score(b'W', b'C'), Some(2));
+    assert_eq!(shifted.min_score(), 0);
+
+    let standard = blosum62.restrict_alphabet(b"ARNDCQEGHILKMFPSTWYV")?;
+    assert_eq!(standard.alphabet(), b"ARNDCQEGHILKMFPSTWYV*");
+    assert_eq!(standard.score(b'W', b'Y'), blosum62.score(b'W', b'Y'));
+    assert_eq!(standard.score(b'*', b'A'), blosum62.score(b'*', b'A'));
+    match blosum62.restrict_alphabet(b"AJ") {
+        Err(Error::Matrix(MatrixError::UnknownSymbol(symbol))) => assert_eq!(symbol, b'J'),
+        other => panic!("expected unknown symbol error, got {other:?}"),
+    }
+
+    let dna = Matrix::create(b"ACGT", 2, -1)?;
+    let with_n = dna.extend_alphabet(b'N', &[0, 0, 0, 0, 1, -2])?;
+    assert_eq!(with_n.alphabet(), b"ACGTN*");
+    assert_eq!(with_n.score(b'A', b'A'), Some(2));
+    assert_eq!(with_n.score(b'N', b'c'), Some(0));
+    assert_eq!(with_n.score(b'G', b'N'), Some(0));
+    assert_eq!(with_n.score(b'N', b'N'), Some(1));
+    assert_eq!(with_n.score(b'N', b'*'), Some(-2));
+    assert_eq!(with_n.score(b'*', b'T'), dna.score(b'*', b'T'));
+    assert!(dna.extend_alphabet(b'N', &[0, 0]).is_err());
+    assert!(dna.extend_alphabet(b'a', &[0; 6]).is_err());
+
+    let rna = dna.map_symbols(|symbol| if symbol == b'T' { b'U' } else { symbol })?;
+    assert_eq!(rna.alphabet(), b"ACGU*");
+    assert_eq!(rna.score(b'u', b'U'), Some(2));
+    assert!(dna.map_symbols(|_| b'A').is_err());
+
+    let blosum80 = Matrix::from("blosum80")?;
+    let mixed = blosum62.combine(&blosum80, |a, b| a.max(b))?;
+    assert_eq!(
+        mixed.score(b'W', b'W'),
+        blosum62.score(b'W', b'W').max(blosum80.score(b'W', b'W'))
+    );
+    assert!(blosum62.combine(&dna, |a, _| a).is_err());
+
+    let pssm = Matrix::from_file("./tests/pssm.txt")?;
+    let pssm_shifted = pssm.shifted(1)?;
+    assert_eq!(pssm_shifted.kind(), MatrixKind::Pssm);
+    assert_eq!(pssm_shifted.pssm_query(), pssm.pssm_query());
+    assert_eq!(
+        pssm_shifted.pssm_score(2, b'C'),
+        pssm.pssm_score(2, b'C').map(|s| s + 1)
+    );
+    let pssm_extended = pssm.extend_alphabet(b'X', &[-1; 10])?;
+    assert_eq!(pssm_extended.pssm_score(3, b'X'), Some(-1));
+    assert_eq!(pssm_extended.pssm_score(3, b'D'), pssm.pssm_score(3, b'D'));
+    let pssm_restricted = pssm.restrict_alphabet(b"CW")?;
+    assert_eq!(pssm_restricted.alphabet(), b"CW*");
+    assert_eq!(pssm_restricted.pssm_score(2, b'C'), Some(12));
+
+    Ok(())
+}
+
 #[test]
 pub fn profile_construction() -> Result<(), Box<dyn std::error::Error>> {
     let query = b"ATGGCACTATAA";
@@ -560,7 +931,7 @@ pub fn trace_table() -> Result<(), Box<dyn std::error::Error>> {
         for col in 0..table.cols() {
             let flags = table
                 .get(row, col)
-                .expect(&format!("Should have flags at ({}, {})", row, col));
+                .unwrap_or_else(|| panic!("Should have flags at ({}, {})", row, col));
             // Flags should be valid (not empty or out of range)
             assert!(
                 !flags.is_empty() || flags == TraceFlags::ZERO,
@@ -577,6 +948,97 @@ pub fn trace_table() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+pub fn owned_table_export() -> Result<(), Box<dyn std::error::Error>> {
+    let query = b"ACGT";
+    let reference = b"TTACGTTT";
+    let aligner = Aligner::new()
+        .local()
+        .gap_open(5)
+        .gap_extend(1)
+        .use_table()
+        .build();
+
+    let table = {
+        let result = aligner.align(Some(query), reference)?;
+        result.get_score_table()?.to_owned()
+    };
+    let table = thread::spawn(move || table).join().unwrap();
+    assert_eq!(table.rows(), 4);
+    assert_eq!(table.cols(), 8);
+    assert_eq!(table.argmax(), Some((3, 5)));
+    assert_eq!(table.get(3, 5), Some(4));
+    assert_eq!(table.row(3).map(<[i32]>::len), Some(8));
+    assert_eq!(table.row(3).map(|row| row[5]), Some(4));
+    assert_eq!(table.col(5), Some(vec![0, 0, 0, 4]));
+    assert_eq!(table.row(4), None);
+    assert_eq!(table.col(8), None);
+    assert_eq!(table.iter_rows().count(), 4);
+    assert!(table
+        .iter_rows()
+        .zip(0..)
+        .all(|(row, i)| table.row(i) == Some(row)));
+
+    let mut csv = Vec::new();
+    table.write_csv(&mut csv)?;
+    let csv = String::from_utf8(csv)?;
+    assert_eq!(csv.lines().count(), 4);
+    assert_eq!(csv.lines().last(), Some("1,1,0,0,0,4,1,1"));
+
+    let mut npy = Vec::new();
+    table.write_npy(&mut npy)?;
+    assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = std::str::from_utf8(&npy[10..10 + header_len])?;
+    assert!(header.contains("'descr': '<i4'"));
+    assert!(header.contains("'shape': (4, 8)"));
+    assert!(header.ends_with('\n'));
+    let data: Vec<i32> = npy[10 + header_len..]
+        .chunks_exact(4)
+        .map(|bytes| i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect();
+    assert_eq!(data, table.as_slice());
+
+    let aligner = Aligner::new().gap_open(5).gap_extend(1).use_trace().build();
+    let result = aligner.align(Some(query), query)?;
+    let trace = result.get_trace_table()?.to_owned();
+    drop(result);
+    assert_eq!(trace.get(3, 3), Some(TraceFlags::DIAG));
+    assert_eq!(trace.row(0).map(<[i8]>::len), Some(4));
+    assert_eq!(trace.col(2).map(|col| col.len()), Some(4));
+    // vectorized trace tables are converted to the same row-major layout
+    let query = b"ACGTTGCAAGCTTAGCCGATAGGCTTACGAT";
+    let reference = b"ACGTTGCAGCTTAGCCCGATAGGTTACGAT";
+    let trace_table = |builder: &mut parasail_rs::prelude::AlignerBuilder| {
+        let aligner = builder.gap_open(5).gap_extend(1).use_trace().build();
+        aligner
+            .align(Some(query), reference)
+            .and_then(|result| Ok(result.get_trace_table()?.to_owned()))
+    };
+    let diag = trace_table(Aligner::new().diag())?;
+    for vectorized in [
+        trace_table(Aligner::new().striped())?,
+        trace_table(Aligner::new().scan())?,
+        trace_table(Aligner::new().striped().solution_width(32))?,
+    ] {
+        for row in 0..query.len() {
+            for col in 0..reference.len() {
+                assert_eq!(vectorized.get(row, col), diag.get(row, col));
+            }
+        }
+    }
+
+    let mut npy = Vec::new();
+    trace.write_npy(&mut npy)?;
+    assert_eq!(npy.len() % 64, 16);
+    let mut csv = Vec::new();
+    trace.write_csv(&mut csv)?;
+    assert_eq!(String::from_utf8(csv)?.lines().count(), 4);
+
+    Ok(())
+}
+
 #[test]
 pub fn get_traceback_strings() -> Result<(), Box<dyn std::error::Error>> {
     let query = b"ACGT";
@@ -615,6 +1077,117 @@ pub fn get_cigar() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+pub fn alignment_record() -> Result<(), Box<dyn std::error::Error>> {
+    let query = b"ACGTACGT";
+    let reference = b"TTACGAACGTTT";
+    let aligner = Aligner::new()
+        .semi_global()
+        .gap_open(5)
+        .gap_extend(1)
+        .use_trace()
+        .build();
+    let record = aligner
+        .align(Some(query), reference)?
+        .record(query, reference)?;
+    assert_eq!(record.score, 6);
+    assert_eq!(record.query_len, 8);
+    assert_eq!(record.ref_len, 12);
+    assert_eq!(record.query_start, Some(0));
+    assert_eq!(record.ref_start, Some(0));
+    assert_eq!(record.query_end, 7);
+    assert_eq!(record.ref_end, 9);
+    assert_eq!(record.cigar.as_deref(), Some("2D3=1X4=2D"));
+    assert_eq!(record.matches, None);
+
+    let aligner = Aligner::new().use_stats().build();
+    let record = aligner.align(Some(query), query)?.record(query, query)?;
+    assert_eq!(record.matches, Some(8));
+    assert_eq!(record.length, Some(8));
+    assert_eq!(record.cigar, None);
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+pub fn serde_round_trips() -> Result<(), Box<dyn std::error::Error>> {
+    use parasail_rs::prelude::{AlignerBuilder, AlignmentRecord};
+
+    // builtin matrices by name, other matrices inline
+    let blosum62 = Matrix::from("blosum62")?;
+    assert_eq!(serde_json::to_string(&blosum62)?, "\"blosum62\"");
+    let custom = Matrix::create(b"ACGT", 2, -3)?;
+    let json = serde_json::to_string(&custom)?;
+    let reloaded: Matrix = serde_json::from_str(&json)?;
+    assert_eq!(reloaded.alphabet(), custom.alphabet());
+    assert_eq!(reloaded.values(), custom.values());
+    assert_eq!(reloaded.kind(), MatrixKind::Square);
+
+    let pssm = Matrix::from_file("./tests/pssm.txt")?;
+    let reloaded: Matrix = serde_json::from_str(&serde_json::to_string(&pssm)?)?;
+    assert_eq!(reloaded.kind(), MatrixKind::Pssm);
+    assert_eq!(reloaded.values(), pssm.values());
+    assert_eq!(reloaded.pssm_query(), pssm.pssm_query());
+    assert!(serde_json::from_str::<Matrix>("\"blosum63\"").is_err());
+
+    // PSSMs built from builtin matrices are not serialized by name
+    let pssm = blosum62.to_pssm(b"ACDW")?;
+    assert_eq!(pssm.name(), None);
+    let reloaded: Matrix = serde_json::from_str(&serde_json::to_string(&pssm)?)?;
+    assert_eq!(reloaded.kind(), MatrixKind::Pssm);
+    assert_eq!(reloaded.pssm_rows().map(|rows| rows.len()), Some(4));
+    assert_eq!(reloaded.values(), pssm.values());
+
+    let config = r#"
+        mode = "semi_global"
+        gap_open = 10
+        gap_extend = 1
+        strategy = "scan"
+        solution_width = 16
+        use_trace = true
+
+        [matrix]
+        alphabet = "ACGT*"
+        values = [
+            2, -3, -3, -3, 0,
+            -3, 2, -3, -3, 0,
+            -3, -3, 2, -3, 0,
+            -3, -3, -3, 2, 0,
+            0, 0, 0, 0, 0,
+        ]
+    "#;
+    let mut builder: AlignerBuilder = toml::from_str(config)?;
+    let aligner = builder.build();
+    assert_eq!(aligner.gap_open, 10);
+    assert_eq!(aligner.vec_strategy, "_scan");
+    assert_eq!(aligner.matrix.score(b'A', b'C'), Some(-3));
+    let result = aligner.align(Some(b"ACGT"), b"TTACGTTT")?;
+    assert!(result.is_semi_global());
+    assert!(result.is_scan());
+    assert!(result.is_trace());
+    assert_eq!(result.get_score(), 8);
+
+    let saved = toml::to_string(&builder)?;
+    let reloaded: AlignerBuilder = toml::from_str(&saved)?;
+    assert_eq!(toml::to_string(&reloaded)?, saved);
+
+    let builder: AlignerBuilder = serde_json::from_str(r#"{"mode": "local", "matrix": "nuc44"}"#)?;
+    let json = serde_json::to_value(&builder)?;
+    assert_eq!(json["mode"], "local");
+    assert_eq!(json["matrix"], "nuc44");
+    assert!(serde_json::from_str::<AlignerBuilder>(r#"{"gap_opne": 1}"#).is_err());
+
+    let query = b"ACGTACGT";
+    let aligner = Aligner::new().use_trace().build();
+    let record = aligner.align(Some(query), query)?.record(query, query)?;
+    let json = serde_json::to_string(&record)?;
+    let reloaded: AlignmentRecord = serde_json::from_str(&json)?;
+    assert_eq!(reloaded, record);
+
+    Ok(())
+}
+
 #[test]
 pub fn global_with_profile() -> Result<(), Box<dyn std::error::Error>> {
     let query = b"ACGT";
@@ -686,10 +1259,1259 @@ pub fn local_with_profile() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+pub fn traceback_paths() -> Result<(), Box<dyn std::error::Error>> {
+    fn cigar(path: &[TraceStep]) -> String {
+        let mut cigar = String::new();
+        let mut ops = path
+            .iter()
+            .map(|step| match step.op {
+                TraceOp::Diag => 'M',
+                TraceOp::Ins => 'D',
+                TraceOp::Del => 'I',
+            })
+            .peekable();
+        while let Some(op) = ops.next() {
+            let mut len = 1;
+            while ops.next_if_eq(&op).is_some() {
+                len += 1;
+            }
+            cigar.push_str(&format!("{len}{op}"));
+        }
+        cigar
+    }
+
+    let query = b"ACGTTTTACGGA";
+    let reference = b"ACGTTTACGCGA";
+    let matrix = Matrix::create(b"ACGT", 2, -3)?;
+    for aligner in [
+        Aligner::new()
+            .matrix(matrix.clone())
+            .gap_open(5)
+            .gap_extend(1)
+            .use_trace()
+            .build(),
+        Aligner::new()
+            .matrix(matrix.clone())
+            .gap_open(5)
+            .gap_extend(1)
+            .use_trace()
+            .scan()
+            .solution_width(32)
+            .build(),
+    ] {
+        let result = aligner.align(Some(query), reference)?;
+        let table = result.get_trace_table()?;
+        let (end_row, end_col) = (
+            result.get_end_query() as usize,
+            result.get_end_ref() as usize,
+        );
+        let path = table.path(end_row, end_col).unwrap();
+        assert_eq!(path.first().map(|step| (step.row, step.col)), Some((0, 0)));
+        assert_eq!(path.last().map(|step| (step.row, step.col)), Some((11, 11)));
+        assert_eq!(cigar(&path), "3M1I6M1D2M");
+        assert_eq!(table.path(12, 0), None);
+
+        // the extra T in the query can be any of the four
+        let paths = aligner.tied_paths(query, reference, 10)?;
+        assert_eq!(paths.len(), 4);
+        assert_eq!(paths[0], path);
+        for path in &paths {
+            assert_eq!(path.len(), 13);
+        }
+        assert_eq!(aligner.tied_paths(query, reference, 2)?.len(), 2);
+    }
+
+    // local paths stop where the alignment starts
+    let aligner = Aligner::new()
+        .local()
+        .matrix(matrix)
+        .gap_open(5)
+        .gap_extend(1)
+        .use_trace()
+        .build();
+    let result = aligner.align(Some(b"GGACGTGG"), b"TTACGTTT")?;
+    let path = result
+        .get_trace_table()?
+        .path(
+            result.get_end_query() as usize,
+            result.get_end_ref() as usize,
+        )
+        .unwrap();
+    assert_eq!(path.len(), 4);
+    assert_eq!(
+        path[0],
+        TraceStep {
+            row: 2,
+            col: 2,
+            op: TraceOp::Diag
+        }
+    );
+    let paths = aligner.tied_paths(b"GGACGTGG", b"TTACGTTT", 10)?;
+    assert_eq!(paths, vec![path]);
+
+    Ok(())
+}
+
+#[test]
+pub fn format_alignment() -> Result<(), Box<dyn std::error::Error>> {
+    let query = b"MKTAYIAKQRQISFVKSHFSRQ";
+    let reference = b"MKTAYLAKQRQISFVKSHFSRQLEER";
+    let aligner = Aligner::new()
+        .semi_global()
+        .matrix(Matrix::from("blosum62")?)
+        .gap_open(10)
+        .gap_extend(1)
+        .use_trace()
+        .build();
+    let result = aligner.align(Some(query), reference)?;
+
+    let blast = Formatter::new()
+        .width(10)
+        .render(&result, query, reference)?;
+    let expected = "\
+Query  1   MKTAYIAKQR  10
+           |||||:||||
+Sbjct  1   MKTAYLAKQR  10
+
+Query  11  QISFVKSHFS  20
+           ||||||||||
+Sbjct  11  QISFVKSHFS  20
+
+Query  21  RQ----  22
+           ||
+Sbjct  21  RQLEER  26
+
+";
+    assert_eq!(blast, expected);
+
+    let mut emboss = Vec::new();
+    Formatter::new()
+        .style(FormatStyle::Emboss)
+        .names("query", "reference")
+        .similar_symbol(b'+')
+        .gap_symbol(b'-')
+        .write(&result, query, reference, &mut emboss)?;
+    let emboss = String::from_utf8(emboss)?;
+    let lines: Vec<&str> = emboss.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+        lines[0],
+        "query              1 MKTAYIAKQRQISFVKSHFSRQ----     22"
+    );
+    assert_eq!(lines[1], "                     |||||+||||||||||||||||----");
+    assert_eq!(
+        lines[2],
+        "reference          1 MKTAYLAKQRQISFVKSHFSRQLEER     26"
+    );
+
+    // local alignments start at the first aligned residues
+    let query = b"GGACGTACGTGG";
+    let reference = b"TTACGAACGTTT";
+    let aligner = Aligner::new()
+        .local()
+        .gap_open(5)
+        .gap_extend(1)
+        .use_trace()
+        .build();
+    let result = aligner.align(Some(query), reference)?;
+    let rendered = Formatter::new()
+        .names("read", "ref")
+        .match_symbol(b'*')
+        .mismatch_symbol(b'x')
+        .render(&result, query, reference)?;
+    assert_eq!(
+        rendered,
+        "read  3   ACGTACGT  10\n          ***x****\nref   3   ACGAACGT  10\n\n"
+    );
+    let rendered = Formatter::new()
+        .coordinates(false)
+        .render(&result, query, reference)?;
+    assert_eq!(
+        rendered,
+        "Query  ACGTACGT\n       |||.||||\nSbjct  ACGAACGT\n\n"
+    );
+
+    let aligner = Aligner::new().build();
+    let result = aligner.align(Some(query), reference)?;
+    assert!(Formatter::new().render(&result, query, reference).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn render_colored_alignment() -> Result<(), Box<dyn std::error::Error>> {
+    let query = b"MKTAYIAKQRQISFVKSHFSRQ";
+    let reference = b"MKTAYLAKQRQISFVKSHFSRQLEER";
+    let matrix = Matrix::from("blosum62")?;
+    let aligner = Aligner::new()
+        .semi_global()
+        .matrix(matrix.clone())
+        .gap_open(10)
+        .gap_extend(1)
+        .use_trace()
+        .build();
+    let result = aligner.align(Some(query), reference)?;
+    let mut formatter = Formatter::new();
+    formatter.width(15).ruler(true).score_track(&matrix);
+
+    let text = formatter.render(&result, query, reference)?;
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "           ........10.....");
+    assert_eq!(lines[1], "Query  1   MKTAYIAKQRQISFV  15");
+    assert_eq!(lines[4].chars().count(), 11 + 15);
+    assert_eq!(lines[6], "           ...20......");
+    // gap columns have no score
+    assert_eq!(lines[10].chars().count(), 11 + 7);
+
+    let ansi = formatter.render_ansi(&result, query, reference)?;
+    assert!(ansi
+        .contains("Query  1   \x1b[32mMKTAY\x1b[0m\x1b[33mI\x1b[0m\x1b[32mAKQRQISFV\x1b[0m  15"));
+    assert!(ansi.contains("Sbjct  16  \x1b[32mKSHFSRQ\x1b[0m\x1b[2mLEER\x1b[0m  26"));
+
+    let mut html = Vec::new();
+    formatter
+        .names("<read>", "ref")
+        .write_html(&result, query, reference, &mut html)?;
+    let html = String::from_utf8(html)?;
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.trim_end().ends_with("</html>"));
+    assert!(html.contains(
+        "&lt;read&gt;  1   <span class=\"match\">MKTAY</span><span class=\"similar\">I</span>"
+    ));
+    assert!(html.contains("<span class=\"gap\">LEER</span>"));
+    assert!(html.contains("<span title=\"8\">"));
+
+    let query = b"ACGTACGT";
+    let reference = b"ACGAACGT";
+    let aligner = Aligner::new().gap_open(5).gap_extend(1).use_trace().build();
+    let result = aligner.align(Some(query), reference)?;
+    let html = Formatter::new().render_html(&result, query, reference)?;
+    assert!(html.contains("<span class=\"match\">ACG</span><span class=\"mismatch\">T</span>"));
+
+    Ok(())
+}
+
+#[test]
+pub fn svg_visualizations() -> Result<(), Box<dyn std::error::Error>> {
+    // tandem repeat of ACGT shows up as parallel diagonals
+    let query = b"ACGTACGTACGT";
+    let reference = b"TTACGTACGTACGTTT";
+    let mut dotplot = DotPlot::new(query, reference);
+    dotplot.word_size(4);
+    let hits = dotplot.hits();
+    assert!(hits.contains(&(0, 2)));
+    assert!(hits.contains(&(0, 6)));
+    assert!(hits.contains(&(4, 2)));
+    assert!(hits
+        .iter()
+        .all(|&(i, j)| query[i..i + 4] == reference[j..j + 4]));
+    assert!(dotplot.threshold(3).hits().len() > hits.len());
+
+    let mut svg = Vec::new();
+    dotplot.names("read", "repeat").write_svg(&mut svg)?;
+    let svg = String::from_utf8(svg)?;
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    assert!(svg.contains(">read</text>"));
+    assert!(svg.contains("<line "));
+    assert!(DotPlot::new(b"AC", b"ACGT").hits().is_empty());
+
+    let query = b"ACGTACGT";
+    let reference = b"ACGACGT";
+    let aligner = Aligner::new().gap_open(5).gap_extend(1).use_table().build();
+    let result = aligner.align(Some(query), reference)?;
+    let table = result.get_score_table()?;
+    let aligner = Aligner::new().gap_open(5).gap_extend(1).build();
+    let path = aligner.tied_paths(query, reference, 1)?.remove(0);
+
+    let svg = Heatmap::new(&table)
+        .path(&path)
+        .sequences(query, reference)
+        .cell_size(10.0)
+        .to_svg();
+    assert_eq!(svg.matches("<rect ").count(), 2 + 8 * 7);
+    assert!(svg.contains("<title>(7, 6): 2</title>"));
+    assert!(svg.contains("<polyline points=\"45.00,45.00 "));
+    assert_eq!(svg.matches("text-anchor=\"end\"").count(), query.len());
+
+    Ok(())
+}
+
+#[test]
+pub fn normalize_indels() -> Result<(), Box<dyn std::error::Error>> {
+    let query = b"ACGTTTTACGGA";
+    let reference = b"ACGTTTACGCGA";
+    let matrix = Matrix::create(b"ACGT", 2, -3)?;
+
+    // every strategy and tied path agrees once indels are normalized
+    let mut cigars = Vec::new();
+    for strategy in ["striped", "scan", "diag"] {
+        let mut builder = Aligner::new();
+        builder
+            .matrix(matrix.clone())
+            .gap_open(5)
+            .gap_extend(1)
+            .use_trace();
+        match strategy {
+            "scan" => builder.scan(),
+            "diag" => builder.diag(),
+            _ => builder.striped(),
+        };
+        let result = builder.build().align(Some(query), reference)?;
+        let cigar = result.cigar(query, reference)?;
+        assert_eq!(cigar.to_string(), result.get_cigar(query, reference)?);
+        cigars.push(cigar);
+    }
+    let aligner = Aligner::new()
+        .matrix(matrix.clone())
+        .gap_open(5)
+        .gap_extend(1)
+        .build();
+    for path in aligner.tied_paths(query, reference, 10)? {
+        cigars.push(Cigar::from_path(&path, query, reference));
+    }
+    assert_eq!(cigars.len(), 7);
+    assert_eq!(cigars[6].to_string(), "6=1I3=1D2=");
+    for cigar in &cigars {
+        assert_eq!(cigar.left_align(query, reference).to_string(), "3=1I6=1D2=");
+        assert_eq!(
+            cigar.right_align(query, reference).to_string(),
+            "6=1I3=1D2="
+        );
+    }
+
+    // dinucleotide repeat
+    let cigar = Cigar::new(
+        0,
+        0,
+        &[(CigarOp::Match, 6), (CigarOp::Del, 2), (CigarOp::Match, 2)],
+    );
+    assert_eq!(cigar.to_string(), "6M2D2M");
+    assert_eq!(
+        cigar.left_align(b"GCACACTT", b"GCACACACTT").to_string(),
+        "1=2D7="
+    );
+    assert_eq!(
+        cigar.right_align(b"GCACACTT", b"GCACACACTT").to_string(),
+        "6=2D2="
+    );
+
+    // adjacent indels are merged
+    let cigar: Cigar = "2=1I1D1I2=".parse()?;
+    assert_eq!(cigar.merge_indels().to_string(), "2=2I1D2=");
+    assert_eq!(
+        cigar.left_align(b"ACGTAC", b"ACTAC").to_string(),
+        "2=2I1D2="
+    );
+    assert_eq!(
+        cigar.right_align(b"ACGTAC", b"ACTAC").to_string(),
+        "2=2I1D2="
+    );
+    let cigar: Cigar = "1=1D1I1=".parse()?;
+    assert_eq!(cigar.left_align(b"AGT", b"ACT").to_string(), "1=1I1D1=");
+
+    // local alignments keep their start positions
+    let aligner = Aligner::new()
+        .local()
+        .matrix(matrix)
+        .gap_open(5)
+        .gap_extend(1)
+        .use_trace()
+        .build();
+    let query = b"GGGGACGTACCCGTAGGGG";
+    let reference = b"TTACGTACCCCGTATT";
+    let result = aligner.align(Some(query), reference)?;
+    let cigar = result.cigar(query, reference)?;
+    assert_eq!((cigar.query_start, cigar.ref_start), (4, 2));
+    assert_eq!(cigar.query_len(), 11);
+    assert_eq!(cigar.ref_len(), 12);
+    let left = cigar.left_align(query, reference);
+    assert_eq!((left.query_start, left.ref_start), (4, 2));
+    assert_eq!(left.to_string(), "5=1D6=");
+
+    assert!("3=2".parse::<Cigar>().is_err());
+    assert!("3Q".parse::<Cigar>().is_err());
+    assert_eq!("".parse::<Cigar>()?.ops(), &[]);
+
+    Ok(())
+}
+
+#[test]
+pub fn call_variants() -> Result<(), Box<dyn std::error::Error>> {
+    // SNV, deletion in a T homopolymer, MNV and insertion
+    let reference = b"ACGTACGGATTTTCAGCTAGCATGCAGT";
+    let query = b"ACGTTCGGATTTCAGCGGGCATGTTCAGT";
+    let mut qualities = vec![b'I'; query.len()];
+    qualities[17] = b'+';
+
+    let aligner = Aligner::new()
+        .gap_open(5)
+        .gap_extend(1)
+        .use_trace()
+        .striped()
+        .build();
+    let result = aligner.align(Some(query), reference)?;
+    let variants = VariantCaller::new()
+        .qualities(&qualities)
+        .call(&result, query, reference)?;
+
+    let variant =
+        |kind, position, ref_allele: &str, alt_allele: &str, query_position, quality| Variant {
+            kind,
+            position,
+            ref_allele: ref_allele.to_string(),
+            alt_allele: alt_allele.to_string(),
+            query_position,
+            quality: Some(quality),
+        };
+    assert_eq!(
+        variants,
+        vec![
+            variant(VariantKind::Snv, 4, "A", "T", 4, 40),
+            variant(VariantKind::Deletion, 8, "AT", "A", 8, 40),
+            variant(VariantKind::Mnv, 17, "TA", "GG", 16, 10),
+            variant(VariantKind::Insertion, 23, "G", "GTT", 22, 40),
+        ]
+    );
+
+    let variants = VariantCaller::new()
+        .merge_mnvs(false)
+        .call(&result, query, reference)?;
+    let mut vcf = Vec::new();
+    write_vcf(&variants, "plasmid", reference.len(), "clone1", &mut vcf)?;
+    let vcf = String::from_utf8(vcf)?;
+    let lines = vcf.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "##fileformat=VCFv4.3");
+    assert!(lines.contains(&"##contig=<ID=plasmid,length=28>"));
+    assert!(lines.contains(&"#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tclone1"));
+    assert_eq!(lines.len(), 6 + 5);
+    assert_eq!(lines[8], "plasmid\t18\t.\tT\tG\t.\t.\tTYPE=snv\tGT\t1");
+    assert_eq!(lines[10], "plasmid\t24\t.\tG\tGTT\t.\t.\tTYPE=ins\tGT\t1");
+
+    // semi-global end gaps are not variants
+    let aligner = Aligner::new()
+        .gap_open(5)
+        .gap_extend(1)
+        .semi_global()
+        .use_trace()
+        .build();
+    let read = &query[3..20];
+    let result = aligner.align(Some(read), reference)?;
+    let variants = VariantCaller::new().call(&result, read, reference)?;
+    assert_eq!(variants.len(), 3);
+    assert_eq!(variants[0].position, 4);
+    assert_eq!(variants[0].query_position, 1);
+
+    Ok(())
+}
+
+#[test]
+pub fn coding_change_notation() -> Result<(), Box<dyn std::error::Error>> {
+    // 5' UTR, ATG GGT GCA AAA TGG TAC GGC TAA, 3' UTR
+    let reference = b"CCCATGGGTGCAAAATGGTACGGCTAAGG";
+    let library: [(&[u8], CodingEffect, &str, &str); 9] = [
+        (
+            b"CCCATGGATGCAAAATGGTACGGCTAAGG",
+            CodingEffect::Missense,
+            "c.5G>A",
+            "p.Gly2Asp",
+        ),
+        (
+            b"CCCATGGGTGCGAAATGGTACGGCTAAGG",
+            CodingEffect::Synonymous,
+            "c.9A>G",
+            "p.Ala3=",
+        ),
+        (
+            b"CCCATGGGTGCAAAATAGTACGGCTAAGG",
+            CodingEffect::Nonsense,
+            "c.14G>A",
+            "p.Trp5Ter",
+        ),
+        (
+            b"CCCATGGGTGCAAATGGTACGGCTAAGG",
+            CodingEffect::Frameshift,
+            "c.12del",
+            "p.Lys4AsnfsTer?",
+        ),
+        (
+            b"CCCATGAGAGCAAAATGGTACGGCTAAGG",
+            CodingEffect::Missense,
+            "c.4_6delinsAGA",
+            "p.Gly2Arg",
+        ),
+        (
+            b"CCCATGGGTGCATGGTACGGCTAAGG",
+            CodingEffect::InFrame,
+            "c.10_12del",
+            "p.Lys4del",
+        ),
+        (
+            b"CCCATGGGTGCAAAAAAATGGTACGGCTAAGG",
+            CodingEffect::InFrame,
+            "c.10_12dup",
+            "p.Lys4dup",
+        ),
+        (
+            b"CCCATGGGTGCAAAATGGTACGGCTACGG",
+            CodingEffect::StopLoss,
+            "c.24A>C",
+            "p.Ter8TyrextTer?",
+        ),
+        (
+            b"CCCATGGGTGCAAAATGGTACGGCTAAGC",
+            CodingEffect::Noncoding,
+            "c.*2G>C",
+            "p.=",
+        ),
+    ];
+
+    let aligner = Aligner::new().gap_open(5).gap_extend(1).use_trace().build();
+    let mut annotator = CodingAnnotator::new();
+    annotator.cds_start(3);
+    for (variant, effect, coding, protein) in library {
+        let result = aligner.align(Some(variant), reference)?;
+        let changes = annotator.annotate(&result, variant, reference)?;
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].effect, effect);
+        assert_eq!(changes[0].coding, coding);
+        assert_eq!(changes[0].protein, protein);
+    }
+
+    let variant = b"CCCATGGATGCAAAATGGTACGGCTAAGG";
+    let result = aligner.align(Some(variant), reference)?;
+    let changes = annotator.annotate(&result, variant, reference)?;
+    assert_eq!(changes[0].codon, Some(2));
+
+    Ok(())
+}
+
+#[test]
+pub fn translated_search() -> Result<(), Box<dyn std::error::Error>> {
+    let standard = GeneticCode::standard();
+    assert_eq!(standard.translate(b"ATGGCNTGARAY"), b"MA*X");
+    assert_eq!(standard.translate_codon(b"ytr"), b'L');
+    let mitochondrial = GeneticCode::from_id(2).unwrap();
+    assert_eq!(mitochondrial.translate(b"TGAAGA"), b"W*");
+    assert!(GeneticCode::from_id(7).is_none());
+
+    let frames = standard.six_frames(b"ATGGCCTAAG");
+    let proteins = frames
+        .iter()
+        .map(|translation| (translation.frame, translation.protein.as_slice()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        proteins,
+        vec![
+            (1, &b"MA*"[..]),
+            (2, b"WPK"),
+            (3, b"GL"),
+            (-1, b"LRP"),
+            (-2, b"LGH"),
+            (-3, b"*A"),
+        ]
+    );
+    assert_eq!(frames[1].nucleotide_range(1..3), 4..10);
+    assert_eq!(frames[3].nucleotide_range(0..1), 7..10);
+
+    // MKTAYIAK coded on the reverse strand of a read
+    let protein = b"MKTAYIAK";
+    let read = b"GGCTCTTTGCGATATACGCGGTTTTCATGG";
+    let aligner = Aligner::new()
+        .matrix(Matrix::from("blosum62")?)
+        .gap_open(11)
+        .gap_extend(1)
+        .local()
+        .use_trace()
+        .build();
+
+    let hits = aligner.align_translated(Some(protein), read, &standard)?;
+    assert_eq!(hits.len(), 6);
+    assert_eq!(hits[0].frame, -3);
+    assert_eq!(hits[0].translation.protein, b"MKTAYIAKS");
+    assert!(hits[0].score() > hits[1].score());
+    assert_eq!(hits[0].nucleotide_range(protein)?, 4..28);
+
+    let hits = aligner.align_translated_query(read, b"WWMKTAYIAKWW", &standard)?;
+    assert_eq!(hits[0].frame, -3);
+    assert_eq!(hits[0].nucleotide_range(b"WWMKTAYIAKWW")?, 4..28);
+
+    Ok(())
+}
+
+#[test]
+pub fn align_both_strands() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(complement(b'r'), b'y');
+    assert_eq!(complement(b'N'), b'N');
+    assert_eq!(reverse_complement(b"AACGTBDHKMSW"), b"WSKMDHVACGTT");
+    assert_eq!(Strand::Reverse.to_string(), "-");
+
+    let reference = b"TTGACCATGGCAGTAACGATTACA";
+    let forward_read = b"CATGGCAGTAAC";
+    let reverse_read = reverse_complement(forward_read);
+    let aligner = Aligner::new()
+        .semi_global()
+        .gap_open(5)
+        .gap_extend(1)
+        .use_trace()
+        .build();
+
+    let result = aligner.align_both_strands(Some(forward_read), reference)?;
+    assert_eq!(result.strand, Strand::Forward);
+    let result = aligner.align_both_strands(Some(&reverse_read), reference)?;
+    assert_eq!(result.strand, Strand::Reverse);
+    assert_eq!(result.score(), 12);
+    assert_eq!(result.alignment.get_end_ref(), 16);
+    assert_eq!(result.oriented_query(&reverse_read), &forward_read[..]);
+    assert_eq!(
+        result.cigar(&reverse_read, reference)?.to_string(),
+        "5D12=7D"
+    );
+
+    // one profile per strand, reused across references
+    let matrix = Matrix::default();
+    let aligner = Aligner::new()
+        .semi_global()
+        .gap_open(5)
+        .gap_extend(1)
+        .profile(Profile::new(&reverse_read, false, &matrix)?)
+        .reverse_profile(Profile::new(forward_read, false, &matrix)?)
+        .build();
+    let result = aligner.align_both_strands(None, reference)?;
+    assert_eq!((result.strand, result.score()), (Strand::Reverse, 12));
+    let result = aligner.align_both_strands(None, &reverse_complement(reference))?;
+    assert_eq!((result.strand, result.score()), (Strand::Forward, 12));
+
+    Ok(())
+}
+
+#[test]
+pub fn circular_alignment() -> Result<(), Box<dyn std::error::Error>> {
+    let plasmid = b"GATCCTAGGCTTACGTTCAGGCATCAAGTC";
+    let aligner = Aligner::new()
+        .semi_global()
+        .gap_open(5)
+        .gap_extend(1)
+        .use_trace()
+        .build();
+
+    // read spanning the origin, in both orientations
+    let read = b"ATCAAGTCGATCCTAGGC";
+    for (query, strand) in [
+        (read.to_vec(), Strand::Forward),
+        (reverse_complement(read), Strand::Reverse),
+    ] {
+        let result = aligner.align_circular(Some(&query), plasmid)?;
+        assert_eq!(result.strand, strand);
+        assert_eq!(result.score(), 18);
+        assert_eq!(result.ref_end(), 9);
+
+        let cigar = result.cigar(&query, plasmid)?;
+        assert_eq!(
+            (cigar.ref_start, cigar.to_string()),
+            (22, String::from("18="))
+        );
+        let pieces = result
+            .split_cigar(&query, plasmid)?
+            .iter()
+            .map(|piece| (piece.ref_start, piece.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pieces,
+            vec![(22, String::from("8=10S")), (0, String::from("8S10="))]
+        );
+    }
+
+    let query = reverse_complement(read);
+    let result = aligner.align_circular(Some(&query), plasmid)?;
+    let mut sam = Vec::new();
+    result.write_sam("read1", "pUC19", &query, plasmid, &mut sam)?;
+    let sam = String::from_utf8(sam)?;
+    let records = sam.lines().collect::<Vec<_>>();
+    assert_eq!(
+        records,
+        vec![
+            "read1\t16\tpUC19\t23\t255\t8=10S\t*\t0\t0\tATCAAGTCGATCCTAGGC\t*\tAS:i:18\tNM:i:0\tSA:Z:pUC19,1,-,8S10=,255,0;",
+            "read1\t2064\tpUC19\t1\t255\t8S10=\t*\t0\t0\tATCAAGTCGATCCTAGGC\t*\tAS:i:18\tNM:i:0\tSA:Z:pUC19,23,-,8=10S,255,0;",
+        ]
+    );
+
+    // read within the plasmid
+    let read = b"TTACGTTCAGG";
+    let result = aligner.align_circular(Some(read), plasmid)?;
+    let pieces = result.split_cigar(read, plasmid)?;
+    assert_eq!(pieces.len(), 1);
+    assert_eq!(
+        (pieces[0].ref_start, pieces[0].to_string()),
+        (10, String::from("11="))
+    );
+    assert_eq!(result.ref_end(), 20);
+
+    Ok(())
+}
+
+#[test]
+pub fn quality_aware_alignment() -> Result<(), Box<dyn std::error::Error>> {
+    let matrix = Matrix::create(b"ACGT", 10, -10)?;
+    let pssm = matrix.quality_pssm(b"ACGT", b"I+#!")?;
+    assert_eq!(pssm.kind(), MatrixKind::Pssm);
+    assert_eq!(pssm.pssm_query(), Some(&b"ACGT"[..]));
+    let rows = pssm
+        .pssm_rows()
+        .unwrap()
+        .map(|row| row[..4].to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![
+            vec![10, -10, -10, -10],
+            vec![-9, 8, -9, -9],
+            vec![-6, -6, -3, -6],
+            vec![-5, -5, -5, -5],
+        ]
+    );
+    assert!(matches!(
+        pssm.quality_pssm(b"ACGT", b"IIII"),
+        Err(Error::Matrix(MatrixError::NotSquare))
+    ));
+
+    // low quality bases at the end of the read
+    let read = b"ACGTTGCAAGGCTTAGCCTA";
+    let qualities = b"IIIIIIIIIIIIIIII####";
+    let reference = b"TTACGTTGCAAGGCTTAGGGGGTT";
+    let aligner = Aligner::new()
+        .matrix(matrix)
+        .gap_open(20)
+        .gap_extend(5)
+        .semi_global()
+        .use_trace()
+        .build();
+
+    let result = aligner.align(Some(read), reference)?;
+    assert_eq!(result.cigar(read, reference)?.to_string(), "2D16=4I6D");
+    let quality_aligner = aligner.with_qualities(read, qualities)?;
+    let result = quality_aligner.align(Some(read), reference)?;
+    assert_eq!(result.cigar(read, reference)?.to_string(), "2D16=4X2D");
+    assert_eq!(result.get_score(), 16 * 10 - 4 * 6);
+
+    // alignments keep the per-read matrix alive after the aligner is dropped
+    let result = aligner
+        .with_qualities(read, qualities)?
+        .align(Some(read), reference)?;
+    assert_eq!(result.cigar(read, reference)?.to_string(), "2D16=4X2D");
+    assert_eq!(
+        result.get_traceback_strings(read, reference)?.query.len(),
+        24
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn trim_reads() -> Result<(), Box<dyn std::error::Error>> {
+    let fastq =
+        b"@full sample=1\nACGTTGCAAGGTCAAGATCGGAAGAGCTTTT\n+\nIIIIIIIIIIIIIIIIIIIIIIIIIIIIIII\n\
+@partial\nACGTTGCAAGGTCAAGA\n+\nIIIIIIIIIIIIIIIII\n\
+@error\nACGTTGCAAGGTCAAGATCGAAGAGCTT\n+\nIIIIIIIIIIIIIIIIIIIIIIIIIIII\n\
+@none\nGGAAGAGCTTTTTTTTTT\n+\nIIIIIIIIIIIIII####\n";
+    let records = FastqReader::new(&fastq[..]).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0].name(), "full");
+
+    let mut trimmer = Trimmer::new();
+    trimmer
+        .adapter("truseq", b"AGATCGGAAGAGC", AdapterEnd::ThreePrime)
+        .quality_trim(QualityTrim::Mott { cutoff: 20 });
+
+    let mut reports = Vec::new();
+    let mut output = Vec::new();
+    for record in &records {
+        let (trimmed, report) = trimmer.trim(record)?;
+        trimmed.write(&mut output)?;
+        reports.push(report);
+    }
+
+    // full, partial and mismatched (one deletion) adapters
+    for report in &reports[..3] {
+        assert_eq!(report.kept, 0..14);
+    }
+    let found = reports[1].adapter.as_ref().unwrap();
+    assert_eq!(
+        (found.range.clone(), found.overlap, found.errors),
+        (14..17, 3, 0)
+    );
+    let found = reports[2].adapter.as_ref().unwrap();
+    assert_eq!(
+        (found.range.clone(), found.overlap, found.errors),
+        (14..26, 13, 1)
+    );
+
+    // adapter suffix at the start of the read is not a 3' adapter
+    assert_eq!(reports[3].adapter, None);
+    assert_eq!(reports[3].quality_trimmed, 4);
+    assert!(output.starts_with(b"@full sample=1\nACGTTGCAAGGTCA\n+\nIIIIIIIIIIIIII\n"));
+    assert!(output.ends_with(b"@none\nGGAAGAGCTTTTTT\n+\nIIIIIIIIIIIIII\n"));
+
+    let mut table = Vec::new();
+    write_reports(&reports, &mut table)?;
+    let table = String::from_utf8(table)?;
+    assert_eq!(
+        table.lines().nth(2),
+        Some("partial\t17\t0\ttruseq\t3'\t14\t17\t3\t0\t0\t14")
+    );
+    assert_eq!(
+        table.lines().nth(4),
+        Some("none\t18\t4\t.\t.\t.\t.\t.\t.\t0\t14")
+    );
+
+    // read emptied by quality trimming
+    let record = FastqRecord::new("low", b"ACGTAGATCGGAAGAGC", b"#################");
+    let (trimmed, report) = trimmer.trim(&record)?;
+    assert!(trimmed.is_empty());
+    assert_eq!(
+        (report.quality_trimmed, report.adapter, report.kept),
+        (17, None, 0..0)
+    );
+
+    // 5' primer, possibly cut short by the start of the read
+    let mut trimmer = Trimmer::new();
+    trimmer
+        .adapter("m13", b"GTTTCCCAGTCACGAC", AdapterEnd::FivePrime)
+        .min_overlap(5)
+        .max_error_rate(0.2);
+    for (read, start) in [
+        (&b"TTGTTTCCCAGTCACGACAAAACCCGGG"[..], 18),
+        (b"CAGTCACGACAAAACCCGGG", 10),
+        (b"CGACAAAACCCGGG", 0),
+    ] {
+        let record = FastqRecord::new("read", read, &vec![b'I'; read.len()]);
+        let (trimmed, report) = trimmer.trim(&record)?;
+        assert_eq!(report.kept.start, start);
+        assert_eq!(trimmed.sequence, &read[start..]);
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn demultiplex_reads() -> Result<(), Box<dyn std::error::Error>> {
+    let sheet = b"# sample,barcode\ns1,ACGTACGT\ns2,TGCATGCA\ns3,ACGTACGA\n";
+    let sheet = BarcodeSheet::from_reader(&sheet[..])?;
+    assert_eq!(sheet.samples().len(), 3);
+    assert!(!sheet.is_dual());
+
+    let reads = [
+        // exact match
+        ("r1", &b"ACGTACGTCCCC"[..]),
+        // one deletion in the barcode
+        ("r2", b"TGCTGCAGGGG"),
+        // one substitution away from both s1 and s3
+        ("r3", b"ACGTACGCTTTT"),
+        // no barcode
+        ("r4", b"GGGGGGGGGGGG"),
+        ("r5", b"ACGTACGAAAAA"),
+    ];
+    let records = reads
+        .iter()
+        .map(|(id, sequence)| FastqRecord::new(id, sequence, &vec![b'I'; sequence.len()]))
+        .collect::<Vec<_>>();
+
+    let mut demux = Demultiplexer::new(sheet);
+    let assignment = demux.assign(&records[1], None)?;
+    assert_eq!(
+        assignment,
+        Assignment::Assigned {
+            sample: 1,
+            distance: 1,
+            barcode_lens: [7, 0]
+        }
+    );
+
+    let mut writers = vec![Vec::new(); 3];
+    let mut unassigned = Vec::new();
+    let stats = demux.demultiplex(
+        records.iter().cloned().map(Ok),
+        &mut writers,
+        &mut unassigned,
+    )?;
+    assert_eq!(stats.total, 5);
+    assert_eq!(stats.assigned, [1, 1, 1]);
+    assert_eq!(stats.perfect, [1, 0, 1]);
+    assert_eq!((stats.ambiguous, stats.unassigned), (1, 2));
+    assert_eq!(writers[0], b"@r1\nCCCC\n+\nIIII\n");
+    assert_eq!(writers[1], b"@r2\nGGGG\n+\nIIII\n");
+    assert!(unassigned.starts_with(b"@r3\nACGTACGCTTTT\n"));
+
+    let mut table = Vec::new();
+    stats.write(demux.sheet(), &mut table)?;
+    let table = String::from_utf8(table)?;
+    assert_eq!(table.lines().nth(1), Some("s1\t1\t1\t0.2000"));
+    assert_eq!(table.lines().last(), Some("unassigned\t2\t0\t0.4000"));
+
+    // a margin of 2 edits leaves r1 and r5 ambiguous between s1 and s3
+    demux.min_margin(2);
+    let stats = demux.demultiplex(records.into_iter().map(Ok), &mut writers, &mut unassigned)?;
+    assert_eq!(stats.assigned, [0, 1, 0]);
+    assert_eq!(stats.ambiguous, 3);
+
+    // dual indexes
+    let mut sheet = BarcodeSheet::new();
+    sheet
+        .add("s1", b"ACGTAC", Some(b"GGATCC"))
+        .add("s2", b"ACGTAC", Some(b"TTAGCA"))
+        .add("s3", b"CATGCA", Some(b"GGATCC"));
+    let mut demux = Demultiplexer::new(sheet);
+    demux.trim_barcodes(false);
+    let read1 = FastqRecord::new("pair", b"ACGTACTTTT", b"IIIIIIIIII");
+    let read2 = FastqRecord::new("pair", b"TTAGGATTTT", b"IIIIIIIIII");
+    assert!(matches!(
+        demux.assign(&read1, Some(&read2))?,
+        Assignment::Assigned {
+            sample: 1,
+            distance: 1,
+            ..
+        }
+    ));
+
+    let mut writers = vec![(Vec::new(), Vec::new()); 3];
+    let mut unassigned = (Vec::new(), Vec::new());
+    let stats = demux.demultiplex_pairs(
+        [Ok(read1.clone())],
+        [Ok(read2.clone())],
+        &mut writers,
+        &mut unassigned,
+    )?;
+    assert_eq!(stats.assigned, [0, 1, 0]);
+    assert_eq!(writers[1].1, b"@pair\nTTAGGATTTT\n+\nIIIIIIIIII\n");
+
+    // read files with different numbers of records
+    assert!(demux
+        .demultiplex_pairs(
+            [Ok(read1.clone()), Ok(read1)],
+            [Ok(read2)],
+            &mut writers,
+            &mut unassigned
+        )
+        .is_err());
+
+    // empty barcode
+    assert!(BarcodeSheet::from_reader(&b"s1,ACGTACGT\ns2,\n"[..]).is_err());
+
+    Ok(())
+}
+
+#[test]
+pub fn merge_read_pairs() -> Result<(), Box<dyn std::error::Error>> {
+    let insert = b"ACGTTGCAAGGTCAAGCTTGTGCCTGGCAATTCGA";
+    // read 1 has a low quality error in the overlap
+    let mut sequence1 = insert[..25].to_vec();
+    sequence1[20] = b'A';
+    let mut qualities1 = vec![b'I'; 25];
+    qualities1[20] = b'#';
+    let read1 = FastqRecord::new("pair/1", &sequence1, &qualities1);
+    let read2 = FastqRecord::new("pair/2", &reverse_complement(&insert[10..]), &[b'5'; 25]);
+
+    let merged = merge_pairs(&read1, &read2)?.expect("reads should overlap");
+    assert_eq!((merged.overlap, merged.differences), (15, 1));
+    assert!(!merged.staggered);
+    assert_eq!(merged.record.id, "pair/1");
+    assert_eq!(merged.record.sequence, insert);
+    // 40 before the overlap, min(40 + 20, 41) in the overlap, 20 - 2 on the
+    // mismatch and 20 after the overlap
+    let mut qualities = [vec![b'I'; 10], vec![b'J'; 15], vec![b'5'; 10]].concat();
+    qualities[20] = b'3';
+    assert_eq!(merged.record.qualities, qualities);
+
+    // staggered pair: the insert is shorter than the reads, which run into
+    // adapters
+    let insert = b"ACGTTGCAAGGTCAA";
+    let read1 = FastqRecord::new("pair", &[&insert[..], b"AGATCGG"].concat(), &[b'I'; 22]);
+    let read2 = FastqRecord::new(
+        "pair",
+        &[&reverse_complement(insert)[..], b"AGATCGG"].concat(),
+        &[b'I'; 22],
+    );
+    let merged = merge_pairs(&read1, &read2)?.expect("reads should overlap");
+    assert!(merged.staggered);
+    assert_eq!(merged.record.sequence, insert);
+
+    // too short an overlap for the default merger
+    let read1 = FastqRecord::new("pair", b"ACGTTGCAAGGTCAAGCTTG", &[b'I'; 20]);
+    let read2 = FastqRecord::new("pair", b"TTTTTTTTTTTTTTCAAGCT", &[b'I'; 20]);
+    assert_eq!(merge_pairs(&read1, &read2)?, None);
+    let mut merger = PairMerger::new();
+    merger.min_overlap(6);
+    let merged = merger.merge(&read1, &read2)?.expect("reads should overlap");
+    assert_eq!(merged.overlap, 6);
+    assert_eq!(merged.record.len(), 34);
+
+    // empty read
+    assert_eq!(merge_pairs(&read1, &FastqRecord::default())?, None);
+
+    Ok(())
+}
+
+#[test]
+pub fn find_all_pattern_hits() -> Result<(), Box<dyn std::error::Error>> {
+    // unit costs, so that -score is the edit distance
+    let aligner = Aligner::new()
+        .matrix(Matrix::create(b"ACGT", 0, -1)?)
+        .gap_open(1)
+        .gap_extend(1)
+        .build();
+    let primer = b"GATTACAGGC";
+    let text = [
+        &b"CCCCC"[..],
+        primer, // exact, at 5
+        b"TTTTTTTTTT",
+        b"GATTCAGGC", // one deletion, at 25
+        b"AAAAAAAAAA",
+        &reverse_complement(b"GATTACTGGC"), // one mismatch, reverse, at 44
+        b"CCCCC",
+    ]
+    .concat();
+
+    let hits = aligner.find_all(primer, &text, -1)?;
+    let summary = hits
+        .iter()
+        .map(|hit| {
+            (
+                hit.strand,
+                hit.start,
+                hit.end,
+                hit.score,
+                hit.cigar.to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            (Strand::Forward, 5, 15, 0, String::from("10=")),
+            (Strand::Forward, 25, 34, -1, String::from("4=1I5=")),
+            (Strand::Reverse, 44, 54, -1, String::from("3=1X6=")),
+        ]
+    );
+
+    // exact matches only
+    let hits = aligner.find_all(primer, &text, 0)?;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(&text[hits[0].start..hits[0].end], primer);
+
+    // free gaps, with a best alignment longer than twice the pattern
+    let text = [&b"A"[..], &[b'T'; 10], b"C", &[b'A'; 10], b"GG"].concat();
+    let hits = Aligner::new().build().find_all(b"ACGG", &text, 4)?;
+    assert_eq!(hits.len(), 1);
+    assert_eq!((hits[0].start, hits[0].end, hits[0].score), (0, 24, 4));
+    assert_eq!(hits[0].cigar.to_string(), "1=10D1=10D2=");
+
+    Ok(())
+}
+
+#[test]
+pub fn crispr_off_targets() -> Result<(), Box<dyn std::error::Error>> {
+    let guide = b"GACGCATAAAGATGAGACGC";
+    let spacer = b"TTTTTTTTTT";
+    let chr1 = [
+        &spacer[..],
+        guide, // on-target, at 10
+        b"TGG",
+        spacer,
+        b"GACGCATAAAGTATGAGACGC", // DNA bulge, at 43
+        b"AGG",
+        spacer,
+        b"GACGCATAAGATGAGACGC", // RNA bulge, at 77
+        b"CGG",
+        spacer,
+        b"GACGCATAAAGATGAGACGC", // NAG PAM, at 109
+        b"TAG",
+        spacer,
+        &reverse_complement(b"GAGGCATAAAGATGAGTCGCGGG"), // mismatches, reverse, at 142
+        spacer,
+    ]
+    .concat();
+    let fasta = [&b">chr1 test\n"[..], &chr1, b"\n>chr2\n", spacer, b"\n"].concat();
+
+    let mut search = OffTargetSearch::new(guide);
+    let off_targets = search.search_fasta(&fasta[..])?;
+    let summary = off_targets
+        .iter()
+        .map(|off_target| {
+            (
+                off_target.start,
+                off_target.strand,
+                off_target.mismatches.clone(),
+                off_target.dna_bulges,
+                off_target.rna_bulges,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            (10, Strand::Forward, vec![], 0, 0),
+            (43, Strand::Forward, vec![], 1, 0),
+            (77, Strand::Forward, vec![], 0, 1),
+            (142, Strand::Reverse, vec![3, 17], 0, 0),
+            // the second NGG PAM in GGG, with an RNA bulge
+            (143, Strand::Reverse, vec![3, 17], 0, 1),
+        ]
+    );
+    assert_eq!(off_targets[1].guide, "GACGCATAAAG-ATGAGACGC");
+    // bulges in homopolymers are leftmost
+    assert_eq!(off_targets[2].target, "GACGCAT-AAGATGAGACGC");
+    assert_eq!(off_targets[3].target, "GAgGCATAAAGATGAGtCGC");
+    assert_eq!(off_targets[3].pam, "GGG");
+
+    // the same candidates are found across chunk boundaries
+    search.chunk_size(17);
+    assert_eq!(search.search("chr1", &chr1)?, off_targets);
+
+    // no bulges, and a NRG PAM
+    search.max_bulges(0).pam(b"NRG");
+    let off_targets = search.search("chr1", &chr1)?;
+    let starts = off_targets
+        .iter()
+        .map(|off_target| off_target.start)
+        .collect::<Vec<_>>();
+    assert_eq!(starts, [10, 109, 142]);
+
+    let mut table = Vec::new();
+    write_table(&off_targets, &mut table)?;
+    let table = String::from_utf8(table)?;
+    assert_eq!(
+        table.lines().nth(3),
+        Some("chr1\t142\t165\t-\tGACGCATAAAGATGAGACGC\tGAgGCATAAAGATGAGtCGC\tGGG\t2\t3,17\t0\t0")
+    );
+
+    // 4 mismatches, where the cheapest alignment has an RNA bulge instead
+    let site = [&spacer[..], b"GGACGATAAAGATGAGACGC", b"TGG", spacer].concat();
+    let mut search = OffTargetSearch::new(guide);
+    search.max_mismatches(4).max_bulges(0);
+    let off_targets = search.search("site", &site)?;
+    assert_eq!(off_targets.len(), 1);
+    assert_eq!(off_targets[0].start, 10);
+    assert_eq!(off_targets[0].mismatches, [2, 3, 4, 5]);
+    search.max_bulges(1);
+    assert_eq!(search.search("site", &site)?.len(), 1);
+
+    // FASTA records are read with wrapped lines
+    let records =
+        FastaReader::new(&b">a x\nAC\nGT\n\n>b\nTT\n"[..]).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(records.len(), 2);
+    assert_eq!(
+        (records[0].name(), &records[0].sequence[..]),
+        ("a", &b"ACGT"[..])
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn vdj_assignment() -> Result<(), Box<dyn std::error::Error>> {
+    let v1 = "GAGGTGCAGCTGGTGGAGTCTGGGGGAGGCTTGGTACAGCCTGGGGGGTCCCTGAGACTCTCCTGTGCAGCCTCTGGATTCACCTTTAGCAGCTATGCCATGAGCTGGGTCCGCCAGGCTCCAGGGAAGGGGCTGGAGTGGGTCTCAGCTATTAGTGGTAGTGGTGGTAGCACATACTACGCAGACTCCGTGAAGGGCCGGTTCACCATCTCCAGAGACAATTCCAAGAACACGCTGTATCTGCAAATGAACAGCCTGAGAGCCGAGGACACGGCCGTATATTACTGTGCGAAAGA";
+    // IMGT-gapped, and a second allele differing outside of the reads
+    let gapped = format!("{}{}{}", &v1[..30], ".".repeat(24), &v1[30..]);
+    let v2 = format!("CAG{}", &v1[3..]);
+    let v3 = "CAGGTGCAGCTGGTGCAGTCTGGGGCTGAGGTGAAGAAGCCTGGGGCCTCAGTGAAGGTCTCCTGCAAGGCTTCTGGATACACCTTCACCGGCTACTATATGCACTGGGTGCGACAGGCCCCTGGACAAGGGCTTGAGTGGATGGGATGGATCAACCCTAACAGTGGTGGCACAAACTATGCACAGAAGTTTCAGGGCAGGGTCACCATGACCAGGGACACGTCCATCAGCACAGCCTACATGGAGCTGAGCAGGCTGAGATCTGACGACACGGCCGTGTATTACTGTGCGAGAGA";
+    let v_fasta = format!(
+        ">X1|IGHV3-test*01|Homo sapiens|F|V-REGION|\n{gapped}\n>X2|IGHV3-test*02|Homo sapiens|F|V-REGION|\n{v2}\n>X3|IGHV1-test*01|Homo sapiens|F|V-REGION|\n{v3}\n"
+    );
+    let v = GermlineSet::from_fasta(v_fasta.as_bytes(), Segment::V)?;
+    assert_eq!(v.len(), 3);
+    assert_eq!(v.genes()[0].sequence, v1.as_bytes());
+    assert_eq!(v.genes()[0].anchor, Some(285));
+    let d = GermlineSet::from_fasta(
+        &b">X4|IGHD3-10*01|\nGTATTACTATGGTTCGGGGAGTTATTATAAC\n>X5|IGHD2-2*01|\nAGGATATTGTAGTAGTACCAGCTGCTATGCC\n"[..],
+        Segment::D,
+    )?;
+    let j = GermlineSet::from_fasta(
+        &b">X6|IGHJ4*02|\nACTACTTTGACTACTGGGGCCAGGGAACCCTGGTCACCGTCTCCTCAG\n>X7|IGHJ6*01|\nATTACTACTACTACTACGGTATGGACGTCTGGGGGCAAGGGACCACGGTCACCGTCTCCTCA\n"[..],
+        Segment::J,
+    )?;
+    let assigner = VdjAssigner::new(v, d, j);
+
+    let tail = &v1[v1.len() - 60..];
+    let read = format!("{tail}GGTACTATGGTTCGGGGAGCCCTTTGACTACTGGGGCCAGGGAACCCTGGTCACCGTC");
+    let rearrangement = assigner.assign("productive", read.as_bytes())?;
+    let v_call = rearrangement.v.as_ref().expect("V should be called");
+    assert_eq!(v_call.genes, ["IGHV3-test*01", "IGHV3-test*02"]);
+    assert_eq!(v_call.identity, 100.0);
+    assert_eq!(rearrangement.d.as_ref().unwrap().genes, ["IGHD3-10*01"]);
+    assert_eq!(rearrangement.j.as_ref().unwrap().genes, ["IGHJ4*02"]);
+    assert_eq!(rearrangement.junction, Some(49..94));
+    assert_eq!(rearrangement.junction_aa().unwrap(), b"CAKEVLWFGEPFDYW");
+    assert_eq!(rearrangement.cdr3().unwrap().len(), 39);
+    assert!(rearrangement.vj_in_frame && !rearrangement.stop_codon && rearrangement.productive);
+
+    // reverse complemented read
+    let reverse = assigner.assign("reverse", &reverse_complement(read.as_bytes()))?;
+    assert!(reverse.rev_comp);
+    assert_eq!(reverse.oriented_sequence(), read.as_bytes());
+    assert_eq!(reverse.junction, rearrangement.junction);
+
+    // frameshift in the junction
+    let frameshift = format!("{tail}GGTACTATGGTTCGGGGAGCCTTTGACTACTGGGGCCAGGGAACCCTGGTCACCGTC");
+    let frameshift = assigner.assign("frameshift", frameshift.as_bytes())?;
+    assert!(!frameshift.vj_in_frame && !frameshift.productive);
+    assert_eq!(frameshift.junction_aa(), None);
+
+    // no V gene
+    let unassigned = assigner.assign("unassigned", b"ACACACACACACACACACACACAC")?;
+    assert!(unassigned.v.is_none() && !unassigned.productive);
+
+    // J gene overlapping the conserved Cys, without a complete junction
+    let v_cys = &v1[..v1.find("TATTACTGT").unwrap() + 9];
+    let short = GermlineSet::from_iter([Germline::new(
+        "IGHV3-short*01",
+        v_cys.as_bytes(),
+        Segment::V,
+    )]);
+    let j4 = b"ACTACTTTGACTACTGGGGCCAGGGAACCCTGGTCACCGTCTCCTCAG";
+    let overlapping = [&v_cys.as_bytes()[v_cys.len() - 60..], &j4[16..]].concat();
+    let overlapping = VdjAssigner::new(
+        short,
+        GermlineSet::default(),
+        GermlineSet::from_iter([Germline::new("IGHJ4*02", j4, Segment::J)]),
+    )
+    .assign("overlapping", &overlapping)?;
+    assert!(overlapping.v.is_some() && overlapping.j.is_some());
+    assert_eq!(overlapping.junction, None);
+    assert_eq!(overlapping.cdr3(), None);
+
+    let mut table = Vec::new();
+    write_airr(
+        &[rearrangement, frameshift, unassigned, overlapping],
+        &mut table,
+    )?;
+    let table = String::from_utf8(table)?;
+    let rows = table
+        .lines()
+        .map(|line| line.split('\t').collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(rows.len(), 5);
+    assert_eq!(
+        &rows[0][..4],
+        ["sequence_id", "sequence", "rev_comp", "productive"]
+    );
+    let column = |name: &str| rows[0].iter().position(|&column| column == name).unwrap();
+    assert_eq!(rows[1][column("v_call")], "IGHV3-test*01,IGHV3-test*02");
+    assert_eq!(rows[1][column("junction_aa")], "CAKEVLWFGEPFDYW");
+    assert_eq!(rows[1][column("v_cigar")], "236N60=58S");
+    assert_eq!(rows[1][column("j_cigar")], "81S4N37=");
+    assert_eq!(rows[1][column("productive")], "T");
+    assert_eq!(rows[2][column("vj_in_frame")], "F");
+    assert_eq!(rows[3][column("v_call")], "");
+    assert_eq!(rows[4][column("junction")], "");
+
+    Ok(())
+}
+
 #[test]
 pub fn multithread_global_alignment() -> Result<(), Box<dyn std::error::Error>> {
     let query = b"ACGT";
-    let refs = vec![b"ACGT", b"ACGT"];
+    let refs = [b"ACGT", b"ACGT"];
     let matrix = Matrix::default();
     let profile = Profile::new(query, true, &matrix)?;
 